@@ -8,12 +8,38 @@
 //! - Slider
 //! - Radio Button
 //! - Number Input
+//! - Select
+//! - TextArea
+//! - Table
+//! - List
+//! - Image
+//! - Progress and Meter
+//! - File Upload
+//! - Canvas
+//! - Chart
+//! - Tabs
+//! - Tree
+//! - Date and Time
+//! - Color
+//! - Markdown
+//! - Log
+//! - Combobox
+//! - Custom element (star rating implemented in the page)
+//! - Form
+//!
+//! Name and quantity are validated on the server; Submit only succeeds when they are valid.
 //!
 //! Run with: cargo run --example all_elements
 //! Then open http://127.0.0.1:3000 in your browser
 
 use std::sync::Arc;
-use webui::{AppState, UiElement, start_server};
+use std::time::Duration;
+use serde::Deserialize;
+use serde_json::json;
+use webui::{
+    AppState, ChartSeries, ChartType, DateTimeMode, DateTimeValue, DrawCommand, ImageSource, ListItem, LogLevel, LogLine, NotificationAction,
+    NotificationLevel, PointerKind, TableColumn, TableRow, TreeNode, UiElement, Validator, form_values, start_server,
+};
 
 #[tokio::main]
 async fn main() {
@@ -24,12 +50,49 @@ async fn main() {
     let state = AppState::new();
 
     // Button elements
+    let state_for_export = state.clone();
+    state.add_element(UiElement::Button {
+        id: "export-btn".to_string(),
+        text: "Export CSV".to_string(),
+        on_click: Some(Arc::new(Box::new(move || {
+            let csv = "fruit,count\napple,12\nbanana,30\ncherry,7\n";
+            state_for_export.download(csv, "inventory.csv", "text/csv");
+        }))),
+    });
+
+    let state_for_rename = state.clone();
+    state.add_element(UiElement::Button {
+        id: "rename-btn".to_string(),
+        text: "Rename...".to_string(),
+        on_click: Some(Arc::new(Box::new(move || {
+            let state = state_for_rename.clone();
+            tokio::spawn(async move {
+                let Some(name) = state.prompt("Rename", "Enter a new name:", "Untitled").await else {
+                    return;
+                };
+                if state.confirm("Confirm", &format!("Rename to \"{}\"?", name)).await {
+                    state.update_element(
+                        "status",
+                        UiElement::Text {
+                            id: "status".to_string(),
+                            text: format!("Renamed to {}", name),
+                        },
+                    );
+                }
+            });
+        }))),
+    });
+
     let state_for_btn = state.clone();
     state.add_element(UiElement::Button {
         id: "submit-btn".to_string(),
         text: "Submit".to_string(),
         on_click: Some(Arc::new(Box::new(move || {
             println!("Submit button clicked!");
+            if !state_for_btn.is_valid() {
+                state_for_btn.notify(NotificationLevel::Warning, "Please fix the highlighted fields", None);
+                return;
+            }
             state_for_btn.update_element(
                 "status",
                 UiElement::Text {
@@ -37,6 +100,26 @@ async fn main() {
                     text: "Form submitted!".to_string(),
                 },
             );
+
+            let state_for_undo = state_for_btn.clone();
+            state_for_btn.notify_with_actions(
+                NotificationLevel::Success,
+                "Form submitted",
+                Some(Duration::from_secs(5)),
+                vec![NotificationAction {
+                    label: "Undo".to_string(),
+                    on_click: Some(Arc::new(Box::new(move || {
+                        state_for_undo.update_element(
+                            "status",
+                            UiElement::Text {
+                                id: "status".to_string(),
+                                text: "Ready".to_string(),
+                            },
+                        );
+                        state_for_undo.notify(NotificationLevel::Info, "Submission undone", Some(Duration::from_secs(3)));
+                    }))),
+                }],
+            );
         }))),
     });
 
@@ -66,6 +149,9 @@ async fn main() {
     state.add_element(UiElement::Input {
         id: "name-input".to_string(),
         value: "".to_string(),
+        label: Some("Enter your name".to_string()),
+        placeholder: Some("Jane Doe".to_string()),
+        tooltip: None,
         on_input: Some(Arc::new(Box::new(move |value| {
             println!("Name input: {}", value);
             state_for_input.update_element(
@@ -77,11 +163,17 @@ async fn main() {
             );
         }))),
     });
+    state.set_validators("name-input", vec![
+        Validator::Required,
+        Validator::pattern(r"^\p{L}[\p{L} .'-]*$", "Please use letters only").unwrap(),
+    ]);
 
     // Checkbox
     state.add_element(UiElement::Checkbox {
         id: "terms-checkbox".to_string(),
         checked: false,
+        label: Some("I agree to the terms and conditions".to_string()),
+        tooltip: Some("Required before submitting".to_string()),
         on_change: Some(Arc::new(Box::new(|checked| {
             println!("Terms accepted: {}", checked);
         }))),
@@ -95,6 +187,8 @@ async fn main() {
         min: 0.0,
         max: 100.0,
         step: Some(1.0),
+        label: None,
+        tooltip: None,
         on_change: Some(Arc::new(Box::new(move |value| {
             println!("Volume: {}", value);
             state_for_slider.update_element(
@@ -113,6 +207,8 @@ async fn main() {
         name: "choice".to_string(),
         value: "a".to_string(),
         checked: true,
+        label: Some("Option A".to_string()),
+        tooltip: None,
         on_change: Some(Arc::new(Box::new(|checked| {
             if checked {
                 println!("Selected: Option A");
@@ -125,6 +221,8 @@ async fn main() {
         name: "choice".to_string(),
         value: "b".to_string(),
         checked: false,
+        label: Some("Option B".to_string()),
+        tooltip: None,
         on_change: Some(Arc::new(Box::new(|checked| {
             if checked {
                 println!("Selected: Option B");
@@ -137,6 +235,8 @@ async fn main() {
         name: "choice".to_string(),
         value: "c".to_string(),
         checked: false,
+        label: Some("Option C".to_string()),
+        tooltip: None,
         on_change: Some(Arc::new(Box::new(|checked| {
             if checked {
                 println!("Selected: Option C");
@@ -152,6 +252,9 @@ async fn main() {
         min: Some(1.0),
         max: Some(100.0),
         step: Some(1.0),
+        label: None,
+        placeholder: None,
+        tooltip: Some("Between 1 and 100".to_string()),
         on_change: Some(Arc::new(Box::new(move |value| {
             println!("Quantity: {}", value);
             state_for_number.update_element(
@@ -163,6 +266,581 @@ async fn main() {
             );
         }))),
     });
+    // The browser accepts typed values outside min and max
+    state.set_validators("quantity-input", vec![Validator::Range { min: Some(1.0), max: Some(100.0) }]);
+
+    // Select
+    let state_for_select = state.clone();
+    state.add_element(UiElement::Select {
+        id: "size-select".to_string(),
+        options: vec![
+            ("s".to_string(), "Small".to_string()),
+            ("m".to_string(), "Medium".to_string()),
+            ("l".to_string(), "Large".to_string()),
+        ],
+        selected: Some("m".to_string()),
+        on_change: Some(Arc::new(Box::new(move |value| {
+            println!("Size: {}", value);
+            state_for_select.update_element(
+                "size-display",
+                UiElement::Text {
+                    id: "size-display".to_string(),
+                    text: format!("Selected size: {}", value),
+                },
+            );
+        }))),
+    });
+
+    state.add_element(UiElement::Text {
+        id: "size-display".to_string(),
+        text: "Selected size: m".to_string(),
+    });
+
+    // Multi-line text area
+    let state_for_notes = state.clone();
+    state.add_element(UiElement::TextArea {
+        id: "notes".to_string(),
+        value: "".to_string(),
+        rows: Some(4),
+        cols: None,
+        on_input: Some(Arc::new(Box::new(move |value| {
+            state_for_notes.update_element(
+                "notes-count",
+                UiElement::Text {
+                    id: "notes-count".to_string(),
+                    text: format!("{} lines", value.lines().count()),
+                },
+            );
+        }))),
+    });
+
+    state.add_element(UiElement::Text {
+        id: "notes-count".to_string(),
+        text: "0 lines".to_string(),
+    });
+
+    // Table with row clicks and server-side sorting
+    let inventory = vec![
+        ("apple", "Apple", 12.0),
+        ("banana", "Banana", 30.0),
+        ("cherry", "Cherry", 7.0),
+    ];
+    let make_rows = |items: &[(&str, &str, f64)]| -> Vec<TableRow> {
+        items
+            .iter()
+            .map(|(key, name, count)| TableRow {
+                key: key.to_string(),
+                cells: vec![json!(name), json!(count)],
+            })
+            .collect()
+    };
+    let state_for_sort = state.clone();
+    let state_for_row = state.clone();
+    state.add_element(UiElement::Table {
+        id: "inventory".to_string(),
+        columns: vec![
+            TableColumn { key: "name".to_string(), label: "Fruit".to_string(), sortable: true },
+            TableColumn { key: "count".to_string(), label: "Count".to_string(), sortable: true },
+        ],
+        rows: make_rows(&inventory),
+        on_row_click: Some(Arc::new(Box::new(move |row| {
+            state_for_row.update_element(
+                "status",
+                UiElement::Text {
+                    id: "status".to_string(),
+                    text: format!("Selected row: {}", row),
+                },
+            );
+        }))),
+        on_sort: Some(Arc::new(Box::new(move |column, ascending| {
+            let mut items = inventory.clone();
+            match column {
+                "count" => items.sort_by(|a, b| a.2.total_cmp(&b.2)),
+                _ => items.sort_by(|a, b| a.1.cmp(b.1)),
+            }
+            if !ascending {
+                items.reverse();
+            }
+            // Move each row to the end in sorted order
+            for row in make_rows(&items) {
+                state_for_sort.delete_table_row("inventory", &row.key);
+                state_for_sort.append_table_row("inventory", row);
+            }
+        }))),
+    });
+
+    // Dynamic list: clicking an item removes it, the button adds one
+    let state_for_list = state.clone();
+    state.add_element(UiElement::List {
+        id: "queue".to_string(),
+        items: vec![],
+        on_item_click: Some(Arc::new(Box::new(move |key| {
+            println!("Removing {}", key);
+            state_for_list.remove_list_item("queue", key);
+        }))),
+    });
+
+    let state_for_add = state.clone();
+    let next_job = Arc::new(std::sync::atomic::AtomicUsize::new(1));
+    state.add_element(UiElement::Button {
+        id: "add-job".to_string(),
+        text: "Add Job".to_string(),
+        on_click: Some(Arc::new(Box::new(move || {
+            let n = next_job.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+            state_for_add.insert_list_item(
+                "queue",
+                usize::MAX,
+                ListItem {
+                    key: format!("job-{}", n),
+                    value: json!({ "name": format!("Job #{}", n), "status": "queued" }),
+                },
+            );
+        }))),
+    });
+
+    // Image generated in Rust, redrawn on each button click
+    let swatch = |hue: usize| {
+        let svg = format!(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="120" height="120"><circle cx="60" cy="60" r="50" fill="hsl({}, 70%, 50%)"/></svg>"#,
+            hue
+        );
+        UiElement::Image {
+            id: "swatch".to_string(),
+            source: ImageSource::bytes(svg, "image/svg+xml"),
+            alt: format!("Color swatch with hue {}", hue),
+        }
+    };
+    state.add_element(swatch(200));
+
+    let state_for_image = state.clone();
+    let hue = Arc::new(std::sync::atomic::AtomicUsize::new(200));
+    state.add_element(UiElement::Button {
+        id: "redraw".to_string(),
+        text: "Redraw".to_string(),
+        on_click: Some(Arc::new(Box::new(move || {
+            let next = (hue.fetch_add(47, std::sync::atomic::Ordering::Relaxed) + 47) % 360;
+            state_for_image.update_element("swatch", swatch(next));
+        }))),
+    });
+
+    // Progress bar and meter driven by a background task
+    state.add_element(UiElement::Progress {
+        id: "job-progress".to_string(),
+        value: Some(0.0),
+        max: 100.0,
+    });
+
+    state.add_element(UiElement::Meter {
+        id: "load-meter".to_string(),
+        value: 0.5,
+        min: 0.0,
+        max: 1.0,
+        low: Some(0.3),
+        high: Some(0.8),
+        optimum: Some(0.1),
+    });
+
+    let state_for_job = state.clone();
+    state.add_element(UiElement::Button {
+        id: "start-job".to_string(),
+        text: "Start Job".to_string(),
+        on_click: Some(Arc::new(Box::new(move || {
+            let state = state_for_job.clone();
+            tokio::spawn(async move {
+                // Only one job at a time
+                state.set_enabled("start-job", false);
+                // Indeterminate while "preparing", then count up
+                state.set_progress("job-progress", None);
+                tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+                for i in 0..=100 {
+                    state.set_progress("job-progress", Some(i as f64));
+                    state.set_meter("load-meter", (i as f64 / 100.0 * std::f64::consts::PI).sin());
+                    tokio::time::sleep(std::time::Duration::from_millis(30)).await;
+                }
+                state.set_enabled("start-job", true);
+            });
+        }))),
+    });
+
+    // File upload: counts the lines of each uploaded text file
+    let state_for_upload = state.clone();
+    state.add_element(UiElement::FileUpload {
+        id: "csv-upload".to_string(),
+        accept: Some(".csv,.txt".to_string()),
+        multiple: true,
+        max_size: Some(1024 * 1024),
+        on_upload: Some(Arc::new(Box::new(move |mut file| {
+            let state = state_for_upload.clone();
+            Box::pin(async move {
+                let mut lines = 0;
+                while let Some(chunk) = file.chunk().await.map_err(|err| err.to_string())? {
+                    lines += chunk.iter().filter(|&&b| b == b'\n').count();
+                }
+                println!("Uploaded {} ({} bytes, {})", file.name, file.size, file.mime_type);
+                state.update_element(
+                    "status",
+                    UiElement::Text {
+                        id: "status".to_string(),
+                        text: format!("{} has {} lines", file.name, lines),
+                    },
+                );
+                Ok(())
+            })
+        }))),
+    });
+
+    // Canvas: drag to draw, the Clear button wipes the drawing
+    let state_for_canvas = state.clone();
+    let last_point = Arc::new(std::sync::Mutex::new(None));
+    state.add_element(UiElement::Canvas {
+        id: "sketch".to_string(),
+        width: 400,
+        height: 200,
+        commands: vec![
+            DrawCommand::Font { font: "14px sans-serif".to_string() },
+            DrawCommand::Text { x: 10.0, y: 20.0, text: "Drag to draw".to_string() },
+        ],
+        on_pointer: Some(Arc::new(Box::new(move |event| {
+            let mut last_point = last_point.lock().unwrap();
+            match event.kind {
+                PointerKind::Down => *last_point = Some((event.x, event.y)),
+                PointerKind::Move => {
+                    if let Some((x1, y1)) = *last_point {
+                        state_for_canvas.draw(
+                            "sketch",
+                            vec![DrawCommand::Line { x1, y1, x2: event.x, y2: event.y }],
+                        );
+                    }
+                    *last_point = Some((event.x, event.y));
+                }
+                PointerKind::Up => *last_point = None,
+            }
+        }))),
+    });
+
+    let state_for_clear = state.clone();
+    state.add_element(UiElement::Button {
+        id: "clear-sketch".to_string(),
+        text: "Clear".to_string(),
+        on_click: Some(Arc::new(Box::new(move || {
+            state_for_clear.draw(
+                "sketch",
+                vec![
+                    DrawCommand::Clear,
+                    DrawCommand::StrokeStyle { color: "#2196f3".to_string() },
+                    DrawCommand::LineWidth { width: 2.0 },
+                ],
+            );
+        }))),
+    });
+
+    // Streaming chart of two simulated signals over the last 100 samples
+    state.add_element(UiElement::Chart {
+        id: "signals".to_string(),
+        chart_type: ChartType::Line,
+        series: vec![
+            ChartSeries { name: "sine".to_string(), color: None, points: vec![] },
+            ChartSeries { name: "cosine".to_string(), color: None, points: vec![] },
+        ],
+        x_label: Some("sample".to_string()),
+        y_label: Some("amplitude".to_string()),
+        max_points: Some(100),
+    });
+
+    // Tabs; the chart only receives new samples while its tab is visible
+    state.add_element(UiElement::Tabs {
+        id: "views".to_string(),
+        tabs: vec![
+            ("chart".to_string(), "Chart".to_string()),
+            ("about".to_string(), "About".to_string()),
+        ],
+        active: "chart".to_string(),
+        on_change: Some(Arc::new(Box::new(|tab| {
+            println!("Tab changed to: {}", tab);
+        }))),
+    });
+
+    let state_for_chart = state.clone();
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(std::time::Duration::from_millis(100));
+        for i in 0u32.. {
+            interval.tick().await;
+            if state_for_chart.active_tab("views").as_deref() != Some("chart") {
+                continue;
+            }
+            let x = i as f64;
+            state_for_chart.append_chart_points("signals", "sine", vec![(x, (x / 10.0).sin())]);
+            state_for_chart.append_chart_points("signals", "cosine", vec![(x, (x / 10.0).cos())]);
+        }
+    });
+
+    // Tree browsing the current directory, loading each folder when expanded
+    let state_for_tree = state.clone();
+    state.add_element(UiElement::Tree {
+        id: "files".to_string(),
+        nodes: vec![TreeNode::branch(".", ".")],
+        selected: None,
+        on_expand: Some(Arc::new(Box::new(|path| {
+            Box::pin(async move {
+                let mut children = Vec::new();
+                let Ok(mut entries) = tokio::fs::read_dir(&path).await else {
+                    return children;
+                };
+                while let Ok(Some(entry)) = entries.next_entry().await {
+                    let child_path = entry.path().to_string_lossy().into_owned();
+                    let name = entry.file_name().to_string_lossy().into_owned();
+                    if entry.file_type().await.is_ok_and(|t| t.is_dir()) {
+                        children.push(TreeNode::branch(child_path, name));
+                    } else {
+                        children.push(TreeNode::leaf(child_path, name));
+                    }
+                }
+                children.sort_by(|a, b| (!a.has_children, &a.label).cmp(&(!b.has_children, &b.label)));
+                children
+            })
+        }))),
+        on_select: Some(Arc::new(Box::new(move |path| {
+            state_for_tree.update_element(
+                "status",
+                UiElement::Text {
+                    id: "status".to_string(),
+                    text: format!("Selected {}", path),
+                },
+            );
+        }))),
+    });
+
+    // Date and time pickers
+    let state_for_date = state.clone();
+    state.add_element(UiElement::DateTime {
+        id: "meeting-date".to_string(),
+        mode: DateTimeMode::Date,
+        value: DateTimeValue::parse(DateTimeMode::Date, "2025-06-02"),
+        min: DateTimeValue::parse(DateTimeMode::Date, "2025-01-01"),
+        max: DateTimeValue::parse(DateTimeMode::Date, "2025-12-31"),
+        on_change: Some(Arc::new(Box::new(move |value| {
+            let text = match value {
+                Some(DateTimeValue::Date { year, month, day }) => format!("Meeting on {}/{}/{}", day, month, year),
+                _ => "No date chosen".to_string(),
+            };
+            state_for_date.update_element(
+                "meeting-display",
+                UiElement::Text {
+                    id: "meeting-display".to_string(),
+                    text,
+                },
+            );
+        }))),
+    });
+
+    state.add_element(UiElement::DateTime {
+        id: "meeting-time".to_string(),
+        mode: DateTimeMode::Time,
+        value: DateTimeValue::parse(DateTimeMode::Time, "09:30"),
+        min: None,
+        max: None,
+        on_change: Some(Arc::new(Box::new(|value| {
+            if let Some(value) = value {
+                println!("Meeting time: {}", value);
+            }
+        }))),
+    });
+
+    state.add_element(UiElement::Text {
+        id: "meeting-display".to_string(),
+        text: "Meeting on 2/6/2025".to_string(),
+    });
+
+    // Color picker with opacity and presets
+    let state_for_color = state.clone();
+    state.add_element(UiElement::Color {
+        id: "highlight-color".to_string(),
+        value: "#2196f3ff".to_string(),
+        alpha: true,
+        presets: vec![
+            "#f44336ff".to_string(),
+            "#4caf50ff".to_string(),
+            "#ff980080".to_string(),
+        ],
+        on_change: Some(Arc::new(Box::new(move |value| {
+            state_for_color.set_style("color-display", "background-color", value);
+            state_for_color.update_element(
+                "color-display",
+                UiElement::Text {
+                    id: "color-display".to_string(),
+                    text: format!("Highlight: {}", value),
+                },
+            );
+        }))),
+    });
+
+    state.add_element(UiElement::Text {
+        id: "color-display".to_string(),
+        text: "Highlight: #2196f3ff".to_string(),
+    });
+    state.set_style("color-display", "background-color", "#2196f3ff");
+
+    // Markdown help text; the link switches the chart tabs instead of navigating
+    let state_for_help = state.clone();
+    state.add_element(UiElement::Markdown {
+        id: "help".to_string(),
+        markdown: "Elements are **created in Rust** and placed with HTML.\n\n\
+                   - Values sync in *both* directions\n\
+                   - Read [about the chart](about) below"
+            .into(),
+        on_link_click: Some(Arc::new(Box::new(move |href| {
+            state_for_help.set_active_tab("views", href);
+        }))),
+    });
+
+    // Log output of a simulated build
+    state.add_element(UiElement::Log {
+        id: "build-log".to_string(),
+        lines: vec![],
+        max_lines: 500,
+    });
+
+    let state_for_log = state.clone();
+    state.add_element(UiElement::Button {
+        id: "run-build".to_string(),
+        text: "Run Build".to_string(),
+        on_click: Some(Arc::new(Box::new(move || {
+            let state = state_for_log.clone();
+            tokio::spawn(async move {
+                state.remove_class("run-build", "has-warnings");
+                state.append_log("build-log", LogLine::new(LogLevel::Debug, "$ cargo build"));
+                for krate in ["serde", "tokio", "axum", "webui"] {
+                    tokio::time::sleep(Duration::from_millis(300)).await;
+                    state.append_log("build-log", format!("   Compiling {} v1.0.0", krate));
+                }
+                state.append_log("build-log", LogLine::new(LogLevel::Warning, "warning: unused variable: `x`"));
+                state.add_class("run-build", "has-warnings");
+                state.append_log("build-log", "    Finished `dev` profile");
+            });
+        }))),
+    });
+
+    // Combobox suggesting countries as the user types
+    let state_for_country = state.clone();
+    state.add_element(UiElement::Combobox {
+        id: "country".to_string(),
+        value: String::new(),
+        on_suggest: Some(Arc::new(Box::new(|query| {
+            Box::pin(async move {
+                const COUNTRIES: &[&str] = &[
+                    "Argentina", "Australia", "Austria", "Belgium", "Brazil", "Canada", "Chile", "Denmark",
+                    "Finland", "France", "Germany", "India", "Ireland", "Italy", "Japan", "Mexico",
+                    "Netherlands", "New Zealand", "Norway", "Portugal", "Spain", "Sweden", "Switzerland",
+                ];
+                let query = query.to_lowercase();
+                // Prefix matches rank before matches elsewhere in the name
+                let mut matches: Vec<(bool, &str)> = COUNTRIES
+                    .iter()
+                    .filter_map(|name| {
+                        let lower = name.to_lowercase();
+                        lower.contains(&query).then(|| (!lower.starts_with(&query), *name))
+                    })
+                    .collect();
+                matches.sort();
+                matches.into_iter().take(8).map(|(_, name)| name.to_string()).collect()
+            })
+        }))),
+        on_select: Some(Arc::new(Box::new(move |value| {
+            state_for_country.update_element(
+                "country-display",
+                UiElement::Text {
+                    id: "country-display".to_string(),
+                    text: format!("Country: {}", value),
+                },
+            );
+        }))),
+    });
+
+    state.add_element(UiElement::Text {
+        id: "country-display".to_string(),
+        text: "".to_string(),
+    });
+
+    // Custom element implemented in JavaScript in all_elements.html
+    let state_for_rating = state.clone();
+    state.add_element(UiElement::Custom {
+        id: "rating".to_string(),
+        kind: "rating".to_string(),
+        props: json!({ "value": 3, "max": 5 }),
+        on_event: Some(Arc::new(Box::new(move |payload| {
+            let Some(value) = payload["value"].as_u64() else {
+                return;
+            };
+            state_for_rating.set_custom_props("rating", json!({ "value": value, "max": 5 }));
+        }))),
+    });
+
+    // Form: the elements are in the form's scope and submitted together
+    #[derive(Deserialize)]
+    struct Subscription {
+        email: String,
+        frequency: f64,
+        digest: bool,
+    }
+
+    let subscribe = state.scope("subscribe");
+    subscribe.add_element(UiElement::Input {
+        id: "email".to_string(),
+        value: String::new(),
+        label: Some("Email".to_string()),
+        placeholder: Some("you@example.com".to_string()),
+        tooltip: None,
+        on_input: None,
+    });
+    subscribe.set_validators("email", vec![
+        Validator::pattern(r"^[^@\s]+@[^@\s]+$", "Not an email address").unwrap(),
+    ]);
+    subscribe.add_element(UiElement::NumberInput {
+        id: "frequency".to_string(),
+        value: 1.0,
+        min: Some(1.0),
+        max: Some(7.0),
+        step: Some(1.0),
+        label: Some("Emails per week".to_string()),
+        placeholder: None,
+        tooltip: None,
+        on_change: None,
+    });
+    subscribe.add_element(UiElement::Checkbox {
+        id: "digest".to_string(),
+        checked: false,
+        label: Some("Send a single weekly digest instead".to_string()),
+        tooltip: None,
+        on_change: None,
+    });
+    subscribe.add_element(UiElement::Button {
+        id: "subscribe-btn".to_string(),
+        text: "Subscribe".to_string(),
+        on_click: None,
+    });
+
+    let state_for_form = state.clone();
+    state.add_element(UiElement::Form {
+        id: "subscribe".to_string(),
+        // Only called once the email address passes its validator
+        on_submit: Some(Arc::new(Box::new(move |values| {
+            match form_values::<Subscription>(values) {
+                Ok(subscription) => {
+                    let schedule = if subscription.digest {
+                        "weekly digest".to_string()
+                    } else {
+                        format!("{} per week", subscription.frequency)
+                    };
+                    let message = format!("Subscribed {} ({})", subscription.email, schedule);
+                    println!("{}", message);
+                    state_for_form.notify(NotificationLevel::Success, message, None);
+                }
+                Err(err) => {
+                    state_for_form.notify(NotificationLevel::Error, format!("Invalid form: {}", err), None);
+                }
+            }
+        }))),
+    });
 
     // Define the UI layout in HTML
     let html = include_str!("all_elements.html");
@@ -68,6 +68,9 @@ async fn main() {
     state.add_element(UiElement::Input {
         id: "name".to_string(),
         value: "".to_string(),
+        label: None,
+        placeholder: None,
+        tooltip: None,
         on_input: Some(Arc::new(Box::new(move |value| {
             println!("Input changed: {}", value);
             state_for_input.update_element(
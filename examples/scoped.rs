@@ -47,6 +47,9 @@ async fn main() {
     form_state.add_element(UiElement::Input {
         id: "name".to_string(),
         value: "".to_string(),
+        label: None,
+        placeholder: None,
+        tooltip: None,
         on_input: None,
     });
 
@@ -69,6 +69,9 @@
 //! let input = UiElement::Input {
 //!     id: "name".to_string(),
 //!     value: "".to_string(),
+//!     label: Some("Name".to_string()),
+//!     placeholder: Some("Your name".to_string()),
+//!     tooltip: None,
 //!     on_input: Some(Arc::new(Box::new(|value| {
 //!         println!("Input changed to: {}", value);
 //!     }))),
@@ -127,6 +130,8 @@
 //!
 //! Scopes can be nested: `form.scope("inputs")` creates path "form.inputs".
 //!
+//! A `<ui-form>` is also the scope of the elements inside it; see [`UiElement::Form`].
+//!
 //! # Example
 //!
 //! ```no_run
@@ -170,20 +175,32 @@
 //! ```
 
 use axum::{
+    body::{Body, BodyDataStream, Bytes},
     extract::{
         ws::{Message, WebSocket, WebSocketUpgrade},
-        State,
+        Path, Query, State,
     },
-    response::{Html, IntoResponse},
-    routing::get,
+    http::{header, HeaderMap, StatusCode},
+    response::{Html, IntoResponse, Response},
+    routing::{get, post},
     Router,
 };
+use futures_util::future::BoxFuture;
 use serde::{Deserialize, Serialize};
 use std::{
-    collections::HashMap,
-    sync::{Arc, Mutex},
+    collections::{BTreeMap, HashMap, HashSet},
+    hash::{Hash, Hasher},
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicU64, AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant, SystemTime},
+};
+use tokio::{
+    sync::{broadcast, mpsc, oneshot},
+    task::AbortHandle,
 };
-use tokio::sync::broadcast;
 use tower_http::services::ServeDir;
 
 /// JSON Protocol: Messages from client to server
@@ -196,6 +213,66 @@ enum ClientMessage {
     Input { id: String, value: String },
     #[serde(rename = "change")]
     Change { id: String, value: serde_json::Value },
+    #[serde(rename = "table")]
+    Table { id: String, event: TableEvent },
+    #[serde(rename = "tree")]
+    Tree { id: String, event: TreeEvent },
+    #[serde(rename = "item_click")]
+    ItemClick { id: String, key: String },
+    #[serde(rename = "link_click")]
+    LinkClick { id: String, href: String },
+    #[serde(rename = "suggest")]
+    Suggest { id: String, query: String, request_id: u64 },
+    #[serde(rename = "custom_event")]
+    CustomEvent { id: String, payload: serde_json::Value },
+    #[serde(rename = "submit")]
+    Submit { id: String, values: HashMap<String, serde_json::Value> },
+    #[serde(rename = "pointer")]
+    Pointer { id: String, kind: PointerKind, x: f64, y: f64 },
+    #[serde(rename = "dialog_response")]
+    DialogResponse { dialog_id: u64, value: serde_json::Value },
+    #[serde(rename = "notification")]
+    Notification { notification_id: u64, action: Option<usize> },
+}
+
+impl ClientMessage {
+    /// The element a user event was reported for, if any.
+    fn element_id(&self) -> Option<&str> {
+        match self {
+            ClientMessage::Click { id }
+            | ClientMessage::Input { id, .. }
+            | ClientMessage::Change { id, .. }
+            | ClientMessage::Table { id, .. }
+            | ClientMessage::Tree { id, .. }
+            | ClientMessage::ItemClick { id, .. }
+            | ClientMessage::LinkClick { id, .. }
+            | ClientMessage::Suggest { id, .. }
+            | ClientMessage::CustomEvent { id, .. }
+            | ClientMessage::Submit { id, .. }
+            | ClientMessage::Pointer { id, .. } => Some(id),
+            ClientMessage::DialogResponse { .. } | ClientMessage::Notification { .. } => None,
+        }
+    }
+}
+
+/// Events reported by a `<ui-table>` element.
+#[derive(Debug, Deserialize)]
+#[serde(tag = "kind")]
+enum TableEvent {
+    #[serde(rename = "row_click")]
+    RowClick { row: String },
+    #[serde(rename = "sort")]
+    Sort { column: String, ascending: bool },
+}
+
+/// Events reported by a `<ui-tree>` element.
+#[derive(Debug, Deserialize)]
+#[serde(tag = "kind")]
+enum TreeEvent {
+    #[serde(rename = "toggle")]
+    Toggle { node: String, expanded: bool },
+    #[serde(rename = "select")]
+    Select { node: String },
 }
 
 /// JSON Protocol: Messages from server to client
@@ -203,1146 +280,6720 @@ enum ClientMessage {
 #[serde(tag = "type")]
 enum ServerMessage {
     #[serde(rename = "init")]
-    Init { elements: Vec<UiElement> },
+    Init { elements: Vec<ClientElement> },
     #[serde(rename = "update")]
-    Update { id: String, element: UiElement },
-}
-
-type ClickCallback = Option<Arc<Box<dyn Fn() + Send + Sync + 'static>>>;
-type InputCallback = Option<Arc<Box<dyn Fn(&str) + Send + Sync + 'static>>>;
-type BoolCallback = Option<Arc<Box<dyn Fn(bool) + Send + Sync + 'static>>>;
-type NumberCallback = Option<Arc<Box<dyn Fn(f64) + Send + Sync + 'static>>>;
-
-/// UI Element types that can be created in Rust and rendered in HTML.
-///
-/// Each element has an `id` for identification and element-specific properties.
-/// Elements do not contain geometry or styling information - that is handled by HTML/CSS.
-#[derive(Clone, Serialize)]
-#[serde(tag = "kind")]
-pub enum UiElement {
-    /// A clickable button.
-    ///
-    /// # Fields
-    /// - `id`: Unique identifier
-    /// - `text`: Button label text
-    /// - `on_click`: Optional click handler (not serialized)
-    ///
-    /// # HTML Element
-    /// Renders as `<ui-button id="...">text</ui-button>`
-    #[serde(rename = "button")]
-    Button {
-        id: String,
-        text: String,
-        #[serde(skip)]
-        on_click: ClickCallback,
+    Update { id: String, element: Box<ClientElement> },
+    #[serde(rename = "enabled")]
+    Enabled { id: String, enabled: bool },
+    #[serde(rename = "visible")]
+    Visible { id: String, visible: bool },
+    #[serde(rename = "add_class")]
+    AddClass { id: String, class: String },
+    #[serde(rename = "remove_class")]
+    RemoveClass { id: String, class: String },
+    #[serde(rename = "style")]
+    Style { id: String, property: String, value: String },
+    #[serde(rename = "validation")]
+    Validation { id: String, error: Option<String> },
+    #[serde(rename = "table_append")]
+    TableAppend { id: String, row: TableRow },
+    #[serde(rename = "table_update")]
+    TableUpdate { id: String, row: TableRow },
+    #[serde(rename = "table_delete")]
+    TableDelete { id: String, key: String },
+    #[serde(rename = "list_insert")]
+    ListInsert { id: String, index: usize, item: ListItem },
+    #[serde(rename = "list_remove")]
+    ListRemove { id: String, key: String },
+    #[serde(rename = "list_move")]
+    ListMove { id: String, key: String, index: usize },
+    #[serde(rename = "value")]
+    Value { id: String, value: Option<f64> },
+    #[serde(rename = "download")]
+    Download { url: String, file_name: String },
+    #[serde(rename = "canvas_draw")]
+    CanvasDraw { id: String, commands: Vec<DrawCommand> },
+    #[serde(rename = "chart_append")]
+    ChartAppend { id: String, series: String, points: Vec<(f64, f64)> },
+    #[serde(rename = "tree_node")]
+    TreeNode { id: String, node: TreeNode },
+    #[serde(rename = "log_append")]
+    LogAppend { id: String, lines: Vec<LogLine> },
+    #[serde(rename = "suggestions")]
+    Suggestions { id: String, request_id: u64, suggestions: Vec<String> },
+    #[serde(rename = "dialog")]
+    Dialog {
+        dialog_id: u64,
+        dialog_type: DialogType,
+        title: String,
+        message: String,
+        default_value: Option<String>,
     },
-
-    /// Read-only text display.
-    ///
-    /// # Fields
-    /// - `id`: Unique identifier
-    /// - `text`: Text content to display
-    ///
-    /// # HTML Element
-    /// Renders as `<ui-text id="...">text</ui-text>`
-    #[serde(rename = "text")]
-    Text { id: String, text: String },
-
-    /// Text input field.
-    ///
-    /// # Fields
-    /// - `id`: Unique identifier
-    /// - `value`: Current input value
-    /// - `on_input`: Optional input change handler (not serialized)
-    ///
-    /// # HTML Element
-    /// Renders as `<ui-input id="...">value</ui-input>`
-    #[serde(rename = "input")]
-    Input {
-        id: String,
-        value: String,
-        #[serde(skip)]
-        on_input: InputCallback,
+    #[serde(rename = "dialog_close")]
+    DialogClose { dialog_id: u64 },
+    #[serde(rename = "notify")]
+    Notify {
+        notification_id: u64,
+        level: NotificationLevel,
+        message: String,
+        duration_ms: Option<u64>,
+        actions: Vec<String>,
     },
+    #[serde(rename = "notification_close")]
+    NotificationClose { notification_id: u64 },
+}
 
-    /// Checkbox input.
+/// A validation rule for an input element, see [`AppState::set_validators`].
+#[derive(Clone)]
+pub enum Validator {
+    /// The value must not be empty. A checkbox must be checked.
     ///
-    /// # Fields
-    /// - `id`: Unique identifier
-    /// - `checked`: Whether the checkbox is checked
-    /// - `on_change`: Optional change handler (not serialized)
+    /// Validators of a radio button check its whole group: the value is `true`
+    /// once any button with the same name is checked.
+    Required,
+    /// A numeric value must be within the bounds.
+    Range { min: Option<f64>, max: Option<f64> },
+    /// A non-empty text value must match `regex`; `message` is shown otherwise.
     ///
-    /// # HTML Element
-    /// Renders as `<ui-checkbox id="...">checked</ui-checkbox>`
-    #[serde(rename = "checkbox")]
-    Checkbox {
-        id: String,
-        checked: bool,
-        #[serde(skip)]
-        on_change: BoolCallback,
-    },
+    /// The regex is not anchored, use `^...$` to match the whole value.
+    Pattern { regex: regex::Regex, message: String },
+    /// Custom check that returns an error message for invalid values.
+    Custom(ValidatorCallback),
+}
 
-    /// Slider input (range).
-    ///
-    /// # Fields
-    /// - `id`: Unique identifier
-    /// - `value`: Current slider value
-    /// - `min`: Minimum value
-    /// - `max`: Maximum value
-    /// - `step`: Optional step increment
-    /// - `on_change`: Optional change handler (not serialized)
-    ///
-    /// # HTML Element
-    /// Renders as `<ui-slider id="...">value</ui-slider>`
-    #[serde(rename = "slider")]
-    Slider {
-        id: String,
-        value: f64,
-        min: f64,
-        max: f64,
-        step: Option<f64>,
-        #[serde(skip)]
-        on_change: NumberCallback,
-    },
+impl Validator {
+    /// Creates a [`Validator::Pattern`], failing if `pattern` is not a valid regex.
+    pub fn pattern(pattern: &str, message: impl Into<String>) -> Result<Self, regex::Error> {
+        Ok(Validator::Pattern {
+            regex: regex::Regex::new(pattern)?,
+            message: message.into(),
+        })
+    }
 
-    /// Radio button input.
-    ///
-    /// # Fields
-    /// - `id`: Unique identifier
-    /// - `name`: Group name (radio buttons with same name are mutually exclusive)
-    /// - `value`: Value when selected
-    /// - `checked`: Whether this radio is selected
-    /// - `on_change`: Optional change handler (not serialized)
-    ///
-    /// # HTML Element
-    /// Renders as `<ui-radio id="..." name="...">checked</ui-radio>`
-    #[serde(rename = "radio")]
-    Radio {
-        id: String,
-        name: String,
-        value: String,
-        checked: bool,
-        #[serde(skip)]
-        on_change: BoolCallback,
-    },
+    /// Creates a [`Validator::Custom`].
+    pub fn custom(check: impl Fn(&serde_json::Value) -> Result<(), String> + Send + Sync + 'static) -> Self {
+        Validator::Custom(Arc::new(Box::new(check)))
+    }
 
-    /// Number input field.
-    ///
-    /// # Fields
-    /// - `id`: Unique identifier
-    /// - `value`: Current numeric value
-    /// - `min`: Optional minimum value
-    /// - `max`: Optional maximum value
-    /// - `step`: Optional step increment
-    /// - `on_change`: Optional change handler (not serialized)
-    ///
-    /// # HTML Element
-    /// Renders as `<ui-number id="...">value</ui-number>`
-    #[serde(rename = "number")]
-    NumberInput {
-        id: String,
-        value: f64,
-        min: Option<f64>,
-        max: Option<f64>,
-        step: Option<f64>,
-        #[serde(skip)]
-        on_change: NumberCallback,
-    },
+    fn check(&self, value: &serde_json::Value) -> Result<(), String> {
+        use serde_json::Value;
+        match self {
+            Validator::Required => match value {
+                Value::Null | Value::Bool(false) => Err("This field is required".to_string()),
+                Value::String(text) if text.trim().is_empty() => Err("This field is required".to_string()),
+                _ => Ok(()),
+            },
+            Validator::Range { min, max } => {
+                let Some(number) = value.as_f64() else {
+                    return Ok(());
+                };
+                if let Some(min) = min
+                    && number < *min
+                {
+                    return Err(format!("Must be at least {}", min));
+                }
+                if let Some(max) = max
+                    && number > *max
+                {
+                    return Err(format!("Must be at most {}", max));
+                }
+                Ok(())
+            }
+            Validator::Pattern { regex, message } => match value.as_str() {
+                Some(text) if !text.is_empty() && !regex.is_match(text) => Err(message.clone()),
+                _ => Ok(()),
+            },
+            Validator::Custom(check) => check(value),
+        }
+    }
 }
 
-impl std::fmt::Debug for UiElement {
+impl std::fmt::Debug for Validator {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            UiElement::Button { id, text, .. } => f
-                .debug_struct("Button")
-                .field("id", id)
-                .field("text", text)
-                .field("on_click", &"<handler>")
-                .finish(),
-            UiElement::Text { id, text } => f
-                .debug_struct("Text")
-                .field("id", id)
-                .field("text", text)
-                .finish(),
-            UiElement::Input { id, value, .. } => f
-                .debug_struct("Input")
-                .field("id", id)
-                .field("value", value)
-                .field("on_input", &"<handler>")
-                .finish(),
-            UiElement::Checkbox { id, checked, .. } => f
-                .debug_struct("Checkbox")
-                .field("id", id)
-                .field("checked", checked)
-                .field("on_change", &"<handler>")
-                .finish(),
-            UiElement::Slider { id, value, min, max, step, .. } => f
-                .debug_struct("Slider")
-                .field("id", id)
-                .field("value", value)
-                .field("min", min)
-                .field("max", max)
-                .field("step", step)
-                .field("on_change", &"<handler>")
-                .finish(),
-            UiElement::Radio { id, name, value, checked, .. } => f
-                .debug_struct("Radio")
-                .field("id", id)
-                .field("name", name)
-                .field("value", value)
-                .field("checked", checked)
-                .field("on_change", &"<handler>")
-                .finish(),
-            UiElement::NumberInput { id, value, min, max, step, .. } => f
-                .debug_struct("NumberInput")
-                .field("id", id)
-                .field("value", value)
-                .field("min", min)
-                .field("max", max)
-                .field("step", step)
-                .field("on_change", &"<handler>")
+            Validator::Required => f.write_str("Required"),
+            Validator::Range { min, max } => f.debug_struct("Range").field("min", min).field("max", max).finish(),
+            Validator::Pattern { regex, message } => f
+                .debug_struct("Pattern")
+                .field("regex", &regex.as_str())
+                .field("message", message)
                 .finish(),
+            Validator::Custom(_) => f.debug_tuple("Custom").field(&"<handler>").finish(),
         }
     }
 }
 
-/// Application state managing UI elements and event handlers.
-///
-/// The `AppState` is the core of the WebUI framework. It:
-/// - Stores all UI elements by ID (buttons and inputs include their handlers)
-/// - Broadcasts updates to all connected WebSocket clients
-/// - Supports scoped namespacing to prevent ID conflicts
+/// Returns the error of the first validator that rejects `value`.
+fn validation_error(validators: &[Validator], value: &serde_json::Value) -> Option<String> {
+    validators.iter().find_map(|validator| validator.check(value).err())
+}
+
+/// State that every element has, kept apart from the element content.
 ///
-/// # Thread Safety
-/// `AppState` is designed to be shared across multiple async tasks and cloned freely.
-/// All mutations are protected by internal locks.
-#[derive(Clone)]
-pub struct AppState {
-    elements: Arc<Mutex<HashMap<String, UiElement>>>,
-    update_tx: broadcast::Sender<ServerMessage>,
-    scope_path: String,
+/// Only values that differ from the default are sent to clients.
+#[derive(Debug, Clone, Serialize)]
+struct ElementState {
+    #[serde(skip_serializing_if = "is_true")]
+    enabled: bool,
+    #[serde(skip_serializing_if = "is_true")]
+    visible: bool,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    classes: Vec<String>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    style: BTreeMap<String, String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+    #[serde(skip)]
+    validators: Vec<Validator>,
 }
 
-impl AppState {
-    /// Creates a new `AppState` instance.
-    ///
-    /// # Example
-    /// ```
-    /// use webui::AppState;
-    ///
-    /// let state = AppState::new();
-    /// ```
-    pub fn new() -> Self {
-        let (tx, _) = broadcast::channel(100);
+impl Default for ElementState {
+    fn default() -> Self {
         Self {
-            elements: Arc::new(Mutex::new(HashMap::new())),
-            update_tx: tx,
-            scope_path: String::new(),
+            enabled: true,
+            visible: true,
+            classes: Vec::new(),
+            style: BTreeMap::new(),
+            error: None,
+            validators: Vec::new(),
         }
     }
+}
 
-    /// Creates a scoped child state with automatic ID prefixing.
-    ///
-    /// Elements added to a scoped state are automatically prefixed with the scope path.
-    /// This prevents ID conflicts when using the same local IDs in different contexts.
-    ///
-    /// # Arguments
-    /// - `name`: The scope name to add to the current path
-    ///
-    /// # Example
-    /// ```
-    /// use webui::{AppState, UiElement};
-    ///
-    /// let state = AppState::new();
-    /// let form_state = state.scope("form");
-    /// let modal_state = state.scope("modal");
-    ///
-    /// // Both use local ID "btn1", but stored as "form.btn1" and "modal.btn1"
-    /// form_state.add_element(UiElement::Button {
-    ///     id: "btn1".to_string(),
-    ///     text: "Submit Form".to_string(),
-    ///     on_click: None,
-    /// });
-    ///
-    /// modal_state.add_element(UiElement::Button {
-    ///     id: "btn1".to_string(),
-    ///     text: "Close Modal".to_string(),
-    ///     on_click: None,
-    /// });
-    /// ```
-    pub fn scope(&self, name: &str) -> Self {
-        let new_path = if self.scope_path.is_empty() {
-            name.to_string()
-        } else {
-            format!("{}.{}", self.scope_path, name)
-        };
+fn is_true(value: &bool) -> bool {
+    *value
+}
 
-        Self {
-            elements: self.elements.clone(),
-            update_tx: self.update_tx.clone(),
-            scope_path: new_path,
-        }
-    }
+/// An element as sent to clients, together with its common state.
+#[derive(Debug, Clone, Serialize)]
+struct ClientElement {
+    #[serde(flatten)]
+    element: UiElement,
+    #[serde(flatten)]
+    state: ElementState,
+}
 
-    /// Builds a full ID by prepending the scope path.
-    fn full_id(&self, local_id: &str) -> String {
-        if self.scope_path.is_empty() {
-            local_id.to_string()
-        } else {
-            format!("{}.{}", self.scope_path, local_id)
+/// Kind of modal dialog shown by [`AppState::confirm`] and [`AppState::prompt`].
+#[derive(Debug, Clone, Copy, Serialize)]
+enum DialogType {
+    #[serde(rename = "confirm")]
+    Confirm,
+    #[serde(rename = "prompt")]
+    Prompt,
+}
+
+type ClickCallback = Option<Arc<Box<dyn Fn() + Send + Sync + 'static>>>;
+type InputCallback = Option<Arc<Box<dyn Fn(&str) + Send + Sync + 'static>>>;
+type BoolCallback = Option<Arc<Box<dyn Fn(bool) + Send + Sync + 'static>>>;
+type NumberCallback = Option<Arc<Box<dyn Fn(f64) + Send + Sync + 'static>>>;
+type SortCallback = Option<Arc<Box<dyn Fn(&str, bool) + Send + Sync + 'static>>>;
+type PointerCallback = Option<Arc<Box<dyn Fn(PointerEvent) + Send + Sync + 'static>>>;
+type TreeLoaderCallback =
+    Option<Arc<Box<dyn Fn(String) -> BoxFuture<'static, Vec<TreeNode>> + Send + Sync + 'static>>>;
+type DateTimeCallback = Option<Arc<Box<dyn Fn(Option<DateTimeValue>) + Send + Sync + 'static>>>;
+type JsonCallback = Option<Arc<Box<dyn Fn(serde_json::Value) + Send + Sync + 'static>>>;
+type FormCallback = Option<Arc<Box<dyn Fn(HashMap<String, serde_json::Value>) + Send + Sync + 'static>>>;
+type ValidatorCallback = Arc<Box<dyn Fn(&serde_json::Value) -> Result<(), String> + Send + Sync + 'static>>;
+type SuggestCallback = Option<Arc<Box<dyn Fn(String) -> BoxFuture<'static, Vec<String>> + Send + Sync + 'static>>>;
+type UploadCallback =
+    Option<Arc<Box<dyn Fn(UploadedFile) -> BoxFuture<'static, Result<(), String>> + Send + Sync + 'static>>>;
+
+/// How long a download registered with [`AppState::download`] can be fetched.
+const DOWNLOAD_TTL: Duration = Duration::from_secs(60);
+
+/// How long [`AppState::confirm`] and [`AppState::prompt`] wait for an answer (5 minutes).
+pub const DIALOG_TIMEOUT: Duration = Duration::from_secs(5 * 60);
+
+/// How long open dialogs wait for a browser to reconnect after the last one disconnects.
+const DIALOG_RECONNECT_GRACE: Duration = Duration::from_secs(10);
+
+/// Suggestion lookups still running for one connection, by combobox ID.
+///
+/// Dropping it cancels them, which happens when the connection closes.
+#[derive(Default)]
+struct PendingSuggestions(HashMap<String, AbortHandle>);
+
+impl Drop for PendingSuggestions {
+    fn drop(&mut self) {
+        for handle in self.0.values() {
+            handle.abort();
         }
     }
+}
 
-    /// Adds a UI element to the application.
-    ///
-    /// The element's ID is automatically prefixed with the current scope path.
-    /// If an element with the same full ID already exists, it will be replaced.
-    ///
-    /// # Example
-    /// ```
-    /// use webui::{AppState, UiElement};
-    ///
-    /// let state = AppState::new();
-    /// state.add_element(UiElement::Button {
-    ///     id: "btn1".to_string(),
-    ///     text: "Click Me!".to_string(),
-    ///     on_click: None,
-    /// });
-    ///
-    /// // Using scopes to avoid ID conflicts
-    /// let form = state.scope("form");
-    /// form.add_element(UiElement::Button {
-    ///     id: "btn1".to_string(),  // Stored as "form.btn1"
-    ///     text: "Submit".to_string(),
-    ///     on_click: None,
-    /// });
-    /// ```
-    pub fn add_element(&self, element: UiElement) {
-        let local_id = match &element {
-            UiElement::Button { id, .. } => id.clone(),
-            UiElement::Text { id, .. } => id.clone(),
-            UiElement::Input { id, .. } => id.clone(),
-            UiElement::Checkbox { id, .. } => id.clone(),
-            UiElement::Slider { id, .. } => id.clone(),
-            UiElement::Radio { id, .. } => id.clone(),
-            UiElement::NumberInput { id, .. } => id.clone(),
-        };
-        let full_id = self.full_id(&local_id);
-        self.elements.lock().unwrap().insert(full_id, element);
-    }
+/// How many notifications with actions are kept; older ones are closed.
+const MAX_PENDING_NOTIFICATIONS: usize = 50;
 
-    /// Updates an existing element and broadcasts the change to all connected clients.
-    ///
-    /// The local ID is automatically prefixed with the current scope path.
-    ///
-    /// # Example
-    /// ```
-    /// # use webui::{AppState, UiElement};
-    /// # let state = AppState::new();
-    /// state.update_element(
-    ///     "status",
-    ///     UiElement::Text {
-    ///         id: "status".to_string(),
-    ///         text: "Updated!".to_string(),
-    ///     },
-    /// );
-    ///
-    /// // With scopes
-    /// let form = state.scope("form");
-    /// form.update_element(
-    ///     "status",  // Updates "form.status"
-    ///     UiElement::Text {
-    ///         id: "status".to_string(),
-    ///         text: "Form submitted!".to_string(),
-    ///     },
-    /// );
-    /// ```
-    pub fn update_element(&self, id: &str, element: UiElement) {
-        let full_id = self.full_id(id);
-        self.elements.lock().unwrap().insert(full_id.clone(), element.clone());
-
-        // Rewrite element ID to full scoped ID for consistency
-        let element_with_full_id = match element {
-            UiElement::Button { text, on_click, .. } => UiElement::Button {
-                id: full_id.clone(),
-                text,
-                on_click,
-            },
-            UiElement::Text { text, .. } => UiElement::Text {
-                id: full_id.clone(),
-                text,
-            },
-            UiElement::Input { value, on_input, .. } => UiElement::Input {
-                id: full_id.clone(),
-                value,
-                on_input,
-            },
-            UiElement::Checkbox { checked, on_change, .. } => UiElement::Checkbox {
-                id: full_id.clone(),
-                checked,
-                on_change,
-            },
-            UiElement::Slider { value, min, max, step, on_change, .. } => UiElement::Slider {
-                id: full_id.clone(),
-                value,
-                min,
-                max,
-                step,
-                on_change,
-            },
-            UiElement::Radio { name, value, checked, on_change, .. } => UiElement::Radio {
-                id: full_id.clone(),
-                name,
-                value,
-                checked,
-                on_change,
-            },
-            UiElement::NumberInput { value, min, max, step, on_change, .. } => UiElement::NumberInput {
-                id: full_id.clone(),
-                value,
-                min,
-                max,
-                step,
-                on_change,
-            },
-        };
+/// A dialog waiting for an answer from a browser.
+struct PendingDialog {
+    message: ServerMessage,
+    responder: oneshot::Sender<serde_json::Value>,
+}
 
-        let _ = self.update_tx.send(ServerMessage::Update {
-            id: full_id,
-            element: element_with_full_id,
+/// Removes a dialog and closes it in all browsers once its future completes or is dropped.
+struct DialogGuard {
+    state: AppState,
+    dialog_id: u64,
+}
+
+impl Drop for DialogGuard {
+    fn drop(&mut self) {
+        self.state.dialogs.lock().unwrap().remove(&self.dialog_id);
+        let _ = self.state.update_tx.send(ServerMessage::DialogClose {
+            dialog_id: self.dialog_id,
         });
     }
+}
 
-    /// Gets all UI elements.
-    ///
-    /// Returns a vector of cloned elements. Used internally when initializing new clients.
-    pub fn get_all_elements(&self) -> Vec<UiElement> {
-        self.elements.lock().unwrap().values().cloned().collect()
-    }
+/// Marks children of a tree node as loading until dropped, also if the loader panics.
+struct TreeLoadGuard {
+    loads: Arc<Mutex<HashSet<(String, String)>>>,
+    load: (String, String),
+}
 
-    /// Gets all UI elements with their full scoped IDs for sending to clients.
-    ///
-    /// Returns elements with IDs rewritten to include scope paths,
-    /// matching what the client-side JavaScript has after auto-rewriting.
-    fn get_all_elements_for_client(&self) -> Vec<UiElement> {
-        self.elements
-            .lock()
-            .unwrap()
-            .iter()
-            .map(|(full_id, element)| {
-                // Clone the element and rewrite its ID to the full scoped ID
-                match element {
-                    UiElement::Button { text, on_click, .. } => UiElement::Button {
-                        id: full_id.clone(),
-                        text: text.clone(),
-                        on_click: on_click.clone(),
-                    },
-                    UiElement::Text { text, .. } => UiElement::Text {
-                        id: full_id.clone(),
-                        text: text.clone(),
-                    },
-                    UiElement::Input { value, on_input, .. } => UiElement::Input {
-                        id: full_id.clone(),
-                        value: value.clone(),
-                        on_input: on_input.clone(),
-                    },
-                    UiElement::Checkbox { checked, on_change, .. } => UiElement::Checkbox {
-                        id: full_id.clone(),
-                        checked: *checked,
-                        on_change: on_change.clone(),
-                    },
-                    UiElement::Slider { value, min, max, step, on_change, .. } => UiElement::Slider {
-                        id: full_id.clone(),
-                        value: *value,
-                        min: *min,
-                        max: *max,
-                        step: *step,
-                        on_change: on_change.clone(),
-                    },
-                    UiElement::Radio { name, value, checked, on_change, .. } => UiElement::Radio {
-                        id: full_id.clone(),
-                        name: name.clone(),
-                        value: value.clone(),
-                        checked: *checked,
-                        on_change: on_change.clone(),
-                    },
-                    UiElement::NumberInput { value, min, max, step, on_change, .. } => UiElement::NumberInput {
-                        id: full_id.clone(),
-                        value: *value,
-                        min: *min,
-                        max: *max,
-                        step: *step,
-                        on_change: on_change.clone(),
-                    },
-                }
-            })
-            .collect()
+impl Drop for TreeLoadGuard {
+    fn drop(&mut self) {
+        self.loads.lock().unwrap().remove(&self.load);
     }
+}
 
-    fn handle_click(&self, id: &str) {
-        let handler = {
-            let elements = self.elements.lock().unwrap();
-            if let Some(UiElement::Button { on_click: Some(handler), .. }) = elements.get(id) {
-                Some(handler.clone())
-            } else {
-                None
-            }
-        };
-        if let Some(handler) = handler {
-            handler();
-        }
-    }
+/// Severity of a notification sent with [`AppState::notify`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum NotificationLevel {
+    #[serde(rename = "info")]
+    Info,
+    #[serde(rename = "success")]
+    Success,
+    #[serde(rename = "warning")]
+    Warning,
+    #[serde(rename = "error")]
+    Error,
+}
 
-    fn handle_input(&self, id: &str, value: &str) {
-        let handler = {
-            let elements = self.elements.lock().unwrap();
-            if let Some(UiElement::Input { on_input: Some(handler), .. }) = elements.get(id) {
-                Some(handler.clone())
-            } else {
-                None
-            }
-        };
-        if let Some(handler) = handler {
-            handler(value);
-        }
+/// A button shown in a notification sent with [`AppState::notify_with_actions`].
+#[derive(Clone)]
+pub struct NotificationAction {
+    /// Button text
+    pub label: String,
+    /// Optional click handler
+    pub on_click: ClickCallback,
+}
+
+impl std::fmt::Debug for NotificationAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("NotificationAction")
+            .field("label", &self.label)
+            .field("on_click", &"<handler>")
+            .finish()
     }
+}
 
-    fn handle_change(&self, id: &str, value: serde_json::Value) {
-        enum HandlerCall {
-            Bool(Arc<Box<dyn Fn(bool) + Send + Sync + 'static>>, bool),
-            Number(Arc<Box<dyn Fn(f64) + Send + Sync + 'static>>, f64),
-        }
+/// Action handlers of a notification that is still shown.
+struct PendingNotification {
+    actions: Vec<ClickCallback>,
+    expires: Option<Instant>,
+}
 
-        let handler_call = {
-            let elements = self.elements.lock().unwrap();
-            if let Some(element) = elements.get(id) {
-                match element {
-                    UiElement::Checkbox { on_change: Some(handler), .. } => {
-                        value.as_bool().map(|checked| HandlerCall::Bool(handler.clone(), checked))
-                    }
-                    UiElement::Slider { on_change: Some(handler), .. } => {
-                        value.as_f64().map(|num| HandlerCall::Number(handler.clone(), num))
-                    }
-                    UiElement::Radio { on_change: Some(handler), .. } => {
-                        value.as_bool().map(|checked| HandlerCall::Bool(handler.clone(), checked))
-                    }
-                    UiElement::NumberInput { on_change: Some(handler), .. } => {
-                        value.as_f64().map(|num| HandlerCall::Number(handler.clone(), num))
-                    }
-                    _ => None
-                }
-            } else {
-                None
-            }
-        };
+/// A connected browser, for sending messages to it alone.
+///
+/// Returned by [`AppState::current_client`] in event handlers.
+#[derive(Clone)]
+pub struct ClientHandle {
+    reply_tx: mpsc::UnboundedSender<ServerMessage>,
+}
 
-        if let Some(handler_call) = handler_call {
-            match handler_call {
-                HandlerCall::Bool(handler, value) => handler(value),
-                HandlerCall::Number(handler, value) => handler(value),
-            }
-        }
+impl std::fmt::Debug for ClientHandle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ClientHandle").finish_non_exhaustive()
     }
 }
 
-impl Default for AppState {
-    fn default() -> Self {
-        Self::new()
-    }
+tokio::task_local! {
+    /// The connection whose message is being handled.
+    static CURRENT_CLIENT: ClientHandle;
 }
 
-async fn websocket_handler(
-    ws: WebSocketUpgrade,
-    State(state): State<AppState>,
-) -> impl IntoResponse {
-    ws.on_upgrade(|socket| websocket(socket, state))
+/// A one-time download waiting to be fetched by a browser.
+struct PendingDownload {
+    data: Vec<u8>,
+    file_name: String,
+    mime_type: String,
+    created: Instant,
 }
 
-async fn websocket(stream: WebSocket, state: AppState) {
-    use futures_util::sink::SinkExt;
-    use futures_util::stream::StreamExt;
+/// Maximum upload size used when [`UiElement::FileUpload`] does not set `max_size` (10 MiB).
+pub const DEFAULT_MAX_UPLOAD_SIZE: u64 = 10 * 1024 * 1024;
+
+/// Column definition for [`UiElement::Table`].
+#[derive(Debug, Clone, Serialize)]
+pub struct TableColumn {
+    /// Identifier reported in sort events
+    pub key: String,
+    /// Header text
+    pub label: String,
+    /// Whether clicking the header requests sorting by this column
+    pub sortable: bool,
+}
 
-    let (mut sender, mut receiver) = stream.split();
+/// A row of [`UiElement::Table`].
+///
+/// Rows are identified by `key`, which is reported in row-click events and used
+/// by the incremental row operations on [`AppState`].
+#[derive(Debug, Clone, Serialize)]
+pub struct TableRow {
+    /// Unique row identifier within the table
+    pub key: String,
+    /// Cell values, one per column
+    pub cells: Vec<serde_json::Value>,
+}
 
-    // Send initial UI state
-    let init_msg = ServerMessage::Init {
-        elements: state.get_all_elements_for_client(),
-    };
-    let json = serde_json::to_string(&init_msg).unwrap();
-    if sender.send(Message::Text(json.into())).await.is_err() {
-        return;
-    }
+/// An item of [`UiElement::List`].
+///
+/// Items are identified by `key`, which is reported in item-click events and used
+/// by the incremental list operations on [`AppState`].
+#[derive(Debug, Clone, Serialize)]
+pub struct ListItem {
+    /// Unique item identifier within the list
+    pub key: String,
+    /// Item content: a string, or a small record whose fields are shown side by side
+    pub value: serde_json::Value,
+}
 
-    // Subscribe to updates from the app state
-    let mut update_rx = state.update_tx.subscribe();
+/// A drawing command for [`UiElement::Canvas`].
+///
+/// Commands are executed in order on the canvas 2D context. Style commands such as
+/// [`DrawCommand::FillStyle`] apply to all following shapes. Colors and fonts use
+/// CSS syntax, e.g. `"#ff0000"` or `"12px sans-serif"`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "op")]
+pub enum DrawCommand {
+    /// Clears the whole canvas and resets all styles to their defaults
+    #[serde(rename = "clear")]
+    Clear,
+    /// Sets the color used by filled shapes and text
+    #[serde(rename = "fill_style")]
+    FillStyle { color: String },
+    /// Sets the color used by outlined shapes and lines
+    #[serde(rename = "stroke_style")]
+    StrokeStyle { color: String },
+    /// Sets the width of lines and outlines
+    #[serde(rename = "line_width")]
+    LineWidth { width: f64 },
+    /// Sets the font used by text
+    #[serde(rename = "font")]
+    Font { font: String },
+    /// Draws a straight line
+    #[serde(rename = "line")]
+    Line { x1: f64, y1: f64, x2: f64, y2: f64 },
+    /// Draws a filled or outlined rectangle
+    #[serde(rename = "rect")]
+    Rect { x: f64, y: f64, width: f64, height: f64, fill: bool },
+    /// Draws a filled or outlined circle
+    #[serde(rename = "circle")]
+    Circle { x: f64, y: f64, radius: f64, fill: bool },
+    /// Draws filled text with its baseline starting at `(x, y)`
+    #[serde(rename = "text")]
+    Text { x: f64, y: f64, text: String },
+    /// Draws a polyline through `points`, optionally closed and filled
+    #[serde(rename = "path")]
+    Path { points: Vec<(f64, f64)>, close: bool, fill: bool },
+}
 
-    // Spawn task to forward updates to this client
-    let mut send_task = tokio::spawn(async move {
-        while let Ok(msg) = update_rx.recv().await {
-            let json = serde_json::to_string(&msg).unwrap();
-            if sender.send(Message::Text(json.into())).await.is_err() {
-                break;
-            }
-        }
-    });
+/// Kind of pointer event reported by a [`UiElement::Canvas`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub enum PointerKind {
+    /// A button was pressed over the canvas
+    #[serde(rename = "down")]
+    Down,
+    /// The pointer moved while a button was pressed
+    #[serde(rename = "move")]
+    Move,
+    /// The button was released
+    #[serde(rename = "up")]
+    Up,
+}
 
-    // Handle incoming messages
-    let state_clone = state.clone();
-    let mut recv_task = tokio::spawn(async move {
-        while let Some(Ok(msg)) = receiver.next().await {
-            if let Message::Text(text) = msg
-                && let Ok(client_msg) = serde_json::from_str::<ClientMessage>(&text) {
-                match client_msg {
-                    ClientMessage::Click { id } => {
-                        state_clone.handle_click(&id);
-                    }
-                    ClientMessage::Input { id, value } => {
-                        state_clone.handle_input(&id, &value);
-                    }
-                    ClientMessage::Change { id, value } => {
-                        state_clone.handle_change(&id, value);
-                    }
-                }
-            }
-        }
-    });
+/// Pointer event on a [`UiElement::Canvas`], in canvas pixel coordinates.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PointerEvent {
+    pub kind: PointerKind,
+    pub x: f64,
+    pub y: f64,
+}
 
-    // Wait for either task to finish
-    tokio::select! {
-        _ = (&mut send_task) => recv_task.abort(),
-        _ = (&mut recv_task) => send_task.abort(),
-    }
+/// How a [`UiElement::Chart`] draws its series.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum ChartType {
+    /// Points connected by lines
+    #[serde(rename = "line")]
+    Line,
+    /// Vertical bars, grouped by x value when there are several series
+    #[serde(rename = "bar")]
+    Bar,
 }
 
-// Default HTML template - wraps user content
-fn generate_html(title: &str, body_content: &str) -> String {
-    format!(r#"<!DOCTYPE html>
-<html lang="en">
-<head>
-    <meta charset="UTF-8">
-    <meta name="viewport" content="width=device-width, initial-scale=1.0">
-    <title>{title}</title>
-    <link rel="stylesheet" href="/static/webui.css">
-</head>
-<body>
-{body_content}
-    <script src="/static/webui.js"></script>
-</body>
-</html>"#, title = title, body_content = body_content)
+/// A named data series of [`UiElement::Chart`].
+#[derive(Debug, Clone, Serialize)]
+pub struct ChartSeries {
+    /// Series name, shown in the legend and used by [`AppState::append_chart_points`]
+    pub name: String,
+    /// Optional CSS color; a default palette color is used if `None`
+    pub color: Option<String>,
+    /// Data points as `(x, y)` pairs, ordered by x
+    pub points: Vec<(f64, f64)>,
 }
 
-/// Configuration for creating a WebUI router
-pub struct RouterConfig {
-    /// Application state
-    pub state: AppState,
-    /// Path to static files directory
-    pub static_dir: String,
-    /// HTML page title
-    pub title: String,
-    /// HTML body content (the UI layout)
-    pub body_html: String,
+/// A node of [`UiElement::Tree`].
+///
+/// Nodes are identified by `id`, which must be unique within the tree. Children
+/// can be loaded lazily: a node with `has_children` set and `children` of `None`
+/// gets its children from the tree's `on_expand` loader when first expanded.
+#[derive(Debug, Clone, Serialize)]
+pub struct TreeNode {
+    /// Unique node identifier within the tree
+    pub id: String,
+    /// Text shown for the node
+    pub label: String,
+    /// Whether the node can be expanded
+    pub has_children: bool,
+    /// Whether the children are shown
+    pub expanded: bool,
+    /// Child nodes, or `None` if not loaded yet
+    pub children: Option<Vec<TreeNode>>,
 }
 
-impl RouterConfig {
-    /// Creates a new router configuration
-    pub fn new(state: AppState, body_html: impl Into<String>) -> Self {
+impl TreeNode {
+    /// Creates a collapsed node whose children are loaded on first expand.
+    pub fn branch(id: impl Into<String>, label: impl Into<String>) -> Self {
         Self {
-            state,
-            static_dir: "static".to_string(),
-            title: "WebUI App".to_string(),
-            body_html: body_html.into(),
+            id: id.into(),
+            label: label.into(),
+            has_children: true,
+            expanded: false,
+            children: None,
         }
     }
 
-    /// Sets the page title
-    pub fn title(mut self, title: impl Into<String>) -> Self {
-        self.title = title.into();
-        self
-    }
-
-    /// Sets the static files directory
-    pub fn static_dir(mut self, dir: impl Into<String>) -> Self {
-        self.static_dir = dir.into();
-        self
+    /// Creates a node without children.
+    pub fn leaf(id: impl Into<String>, label: impl Into<String>) -> Self {
+        Self {
+            id: id.into(),
+            label: label.into(),
+            has_children: false,
+            expanded: false,
+            children: None,
+        }
     }
 }
 
-/// Creates an Axum router configured for WebUI.
-///
-/// The router includes:
-/// - `/` - Serves the main HTML page with your custom UI layout
-/// - `/ws` - WebSocket endpoint for UI communication
-/// - `/static` - Serves static files (webui.js, webui.css, etc.)
-///
-/// # Arguments
-/// - `config`: Router configuration with state and HTML content
-///
-/// # Example
-/// ```no_run
-/// use webui::{AppState, RouterConfig, create_router};
-///
-/// #[tokio::main]
-/// async fn main() {
-///     let state = AppState::new();
-///
-///     let html = r#"
-///         <div class="container">
-///             <h1>My App</h1>
-///             <ui-button id="btn1"></ui-button>
-///             <ui-text id="status"></ui-text>
-///         </div>
-///     "#;
-///
-///     let config = RouterConfig::new(state.clone(), html)
-///         .title("My WebUI App");
+/// Unchecks the other radio buttons in the group of `id` if it is checked, returning their IDs.
 ///
-///     let app = create_router(config);
+/// Browsers only report the radio button that became checked.
+fn uncheck_radio_siblings(elements: &mut HashMap<String, UiElement>, id: &str) -> Vec<String> {
+    let Some(UiElement::Radio { name: group, checked: true, .. }) = elements.get(id) else {
+        return Vec::new();
+    };
+    let group = group.clone();
+    elements
+        .iter_mut()
+        .filter_map(|(other_id, element)| match element {
+            UiElement::Radio { name, checked, .. } if *name == group && other_id != id => {
+                *checked = false;
+                Some(other_id.clone())
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+/// Returns the value validators check for an element.
 ///
-///     let listener = tokio::net::TcpListener::bind("127.0.0.1:3000")
-///         .await
-///         .unwrap();
-///     axum::serve(listener, app).await.unwrap();
-/// }
-/// ```
-pub fn create_router(config: RouterConfig) -> Router {
-    let html_content = generate_html(&config.title, &config.body_html);
-    let state = config.state.clone();
+/// A radio button stands for its group, so its value is whether any button of
+/// the group is checked.
+fn validated_value(elements: &HashMap<String, UiElement>, full_id: &str) -> Option<serde_json::Value> {
+    match elements.get(full_id)? {
+        UiElement::Radio { name: group, .. } => Some(serde_json::Value::Bool(elements.values().any(
+            |element| matches!(element, UiElement::Radio { name, checked: true, .. } if name == group),
+        ))),
+        element => element.value(),
+    }
+}
 
-    Router::new()
-        .route("/", get(move || async move {
-            Html(html_content)
-        }))
-        .route("/ws", get(websocket_handler))
-        .nest_service("/static", ServeDir::new(config.static_dir))
-        .with_state(state)
+/// Finds the node with the given ID anywhere below `nodes`.
+fn find_tree_node_mut<'a>(nodes: &'a mut [TreeNode], node_id: &str) -> Option<&'a mut TreeNode> {
+    for node in nodes {
+        if node.id == node_id {
+            return Some(node);
+        }
+        if let Some(found) = node.children.as_deref_mut().and_then(|children| find_tree_node_mut(children, node_id)) {
+            return Some(found);
+        }
+    }
+    None
 }
 
-/// Convenience function to start a WebUI server.
-///
-/// This is a helper that combines creating a router and starting the server.
-///
-/// # Arguments
-/// - `state`: The application state
-/// - `html`: HTML body content for the UI layout
-/// - `title`: Page title (optional, defaults to "WebUI App")
-/// - `addr`: Address to bind to (e.g., "127.0.0.1:3000")
-///
-/// # Example
-/// ```no_run
-/// use webui::{AppState, UiElement, start_server};
-/// use std::sync::Arc;
-///
-/// #[tokio::main]
-/// async fn main() {
-///     let state = AppState::new();
+/// Which native picker a [`UiElement::DateTime`] shows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum DateTimeMode {
+    /// Calendar date, `YYYY-MM-DD`
+    #[serde(rename = "date")]
+    Date,
+    /// Time of day, `HH:MM` or `HH:MM:SS`
+    #[serde(rename = "time")]
+    Time,
+    /// Date and time without time zone, `YYYY-MM-DDTHH:MM` or `YYYY-MM-DDTHH:MM:SS`
+    #[serde(rename = "datetime-local")]
+    DateTimeLocal,
+}
+
+/// A validated value of [`UiElement::DateTime`].
 ///
-///     state.add_element(UiElement::Button {
-///         id: "btn1".to_string(),
-///         text: "Click Me!".to_string(),
-///         on_click: Some(Arc::new(Box::new(|| {
-///             println!("Clicked!");
-///         }))),
-///     });
+/// Values are exchanged with the browser as ISO-8601 strings. Values of the same
+/// mode are ordered chronologically.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum DateTimeValue {
+    /// A calendar date
+    Date { year: u32, month: u32, day: u32 },
+    /// A time of day
+    Time { hour: u32, minute: u32, second: u32 },
+    /// A date and time without time zone
+    DateTime { year: u32, month: u32, day: u32, hour: u32, minute: u32, second: u32 },
+}
+
+impl DateTimeValue {
+    /// Parses an ISO-8601 string in the format of the given mode.
+    ///
+    /// Returns `None` if the string is malformed or not a real date or time.
+    /// Fractional seconds are accepted and dropped.
+    ///
+    /// # Example
+    /// ```
+    /// # use webui::{DateTimeMode, DateTimeValue};
+    /// let value = DateTimeValue::parse(DateTimeMode::Date, "2024-02-29").unwrap();
+    /// assert_eq!(value, DateTimeValue::Date { year: 2024, month: 2, day: 29 });
+    /// assert_eq!(value.to_string(), "2024-02-29");
+    ///
+    /// assert_eq!(DateTimeValue::parse(DateTimeMode::Date, "2023-02-29"), None);
+    /// ```
+    pub fn parse(mode: DateTimeMode, value: &str) -> Option<Self> {
+        match mode {
+            DateTimeMode::Date => {
+                let (year, month, day) = parse_iso_date(value)?;
+                Some(Self::Date { year, month, day })
+            }
+            DateTimeMode::Time => {
+                let (hour, minute, second) = parse_iso_time(value)?;
+                Some(Self::Time { hour, minute, second })
+            }
+            DateTimeMode::DateTimeLocal => {
+                let (date, time) = value.split_once('T')?;
+                let (year, month, day) = parse_iso_date(date)?;
+                let (hour, minute, second) = parse_iso_time(time)?;
+                Some(Self::DateTime { year, month, day, hour, minute, second })
+            }
+        }
+    }
+
+    /// The mode whose format this value has.
+    pub fn mode(&self) -> DateTimeMode {
+        match self {
+            Self::Date { .. } => DateTimeMode::Date,
+            Self::Time { .. } => DateTimeMode::Time,
+            Self::DateTime { .. } => DateTimeMode::DateTimeLocal,
+        }
+    }
+}
+
+impl std::fmt::Display for DateTimeValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // Seconds are omitted when zero, matching what browsers produce
+        let write_time = |f: &mut std::fmt::Formatter<'_>, hour: u32, minute: u32, second: u32| {
+            write!(f, "{:02}:{:02}", hour, minute)?;
+            if second != 0 {
+                write!(f, ":{:02}", second)?;
+            }
+            Ok(())
+        };
+        match *self {
+            Self::Date { year, month, day } => write!(f, "{:04}-{:02}-{:02}", year, month, day),
+            Self::Time { hour, minute, second } => write_time(f, hour, minute, second),
+            Self::DateTime { year, month, day, hour, minute, second } => {
+                write!(f, "{:04}-{:02}-{:02}T", year, month, day)?;
+                write_time(f, hour, minute, second)
+            }
+        }
+    }
+}
+
+impl Serialize for DateTimeValue {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+/// Parses a fixed number of ASCII digits.
+fn parse_digits(s: &str, len: usize) -> Option<u32> {
+    if s.len() == len && s.bytes().all(|b| b.is_ascii_digit()) {
+        s.parse().ok()
+    } else {
+        None
+    }
+}
+
+/// Parses `YYYY-MM-DD` into `(year, month, day)`.
+fn parse_iso_date(s: &str) -> Option<(u32, u32, u32)> {
+    let mut parts = s.split('-');
+    let year = parts.next()?;
+    // Browsers allow years beyond 9999 with more digits
+    let year = parse_digits(year, year.len().max(4))?;
+    let month = parse_digits(parts.next()?, 2)?;
+    let day = parse_digits(parts.next()?, 2)?;
+    if parts.next().is_some() || year == 0 || !(1..=12).contains(&month) {
+        return None;
+    }
+    let leap = year % 4 == 0 && (year % 100 != 0 || year % 400 == 0);
+    let days = match month {
+        2 if leap => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    };
+    (1..=days).contains(&day).then_some((year, month, day))
+}
+
+/// Parses `HH:MM`, `HH:MM:SS` or `HH:MM:SS.fff` into `(hour, minute, second)`.
+fn parse_iso_time(s: &str) -> Option<(u32, u32, u32)> {
+    let mut parts = s.split(':');
+    let hour = parse_digits(parts.next()?, 2)?;
+    let minute = parse_digits(parts.next()?, 2)?;
+    let second = match parts.next() {
+        Some(second) => {
+            let (second, fraction) = second.split_once('.').unwrap_or((second, "0"));
+            parse_digits(fraction, fraction.len().max(1))?;
+            parse_digits(second, 2)?
+        }
+        None => 0,
+    };
+    if parts.next().is_some() || hour > 23 || minute > 59 || second > 59 {
+        return None;
+    }
+    Some((hour, minute, second))
+}
+
+/// Normalizes a CSS hex color to lowercase `#rrggbb`, or `#rrggbbaa` if `alpha` is set.
 ///
-///     let html = r#"<ui-button id="btn1"></ui-button>"#;
+/// Accepts the `#rgb` and `#rrggbb` forms, and with `alpha` also `#rgba` and
+/// `#rrggbbaa`. Colors without an alpha channel become fully opaque.
+fn normalize_color(value: &str, alpha: bool) -> Option<String> {
+    let hex = value.strip_prefix('#')?;
+    if !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return None;
+    }
+    let hex = hex.to_ascii_lowercase();
+    let mut normalized = match hex.len() {
+        3 | 4 if alpha || hex.len() == 3 => hex.chars().flat_map(|c| [c, c]).collect(),
+        6 | 8 if alpha || hex.len() == 6 => hex,
+        _ => return None,
+    };
+    if alpha && normalized.len() == 6 {
+        normalized.push_str("ff");
+    }
+    Some(format!("#{}", normalized))
+}
+
+/// Severity of a [`LogLine`], used for styling.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum LogLevel {
+    #[serde(rename = "debug")]
+    Debug,
+    #[serde(rename = "info")]
+    Info,
+    #[serde(rename = "warning")]
+    Warning,
+    #[serde(rename = "error")]
+    Error,
+}
+
+/// A line of [`UiElement::Log`].
 ///
-///     start_server(state, html, "My App", "127.0.0.1:3000").await.unwrap();
-/// }
-/// ```
-pub async fn start_server(
-    state: AppState,
-    html: impl Into<String>,
-    title: impl Into<String>,
-    addr: impl AsRef<str>,
-) -> Result<(), std::io::Error> {
-    let config = RouterConfig::new(state, html).title(title);
-    let app = create_router(config);
+/// Plain strings convert to lines of level [`LogLevel::Info`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct LogLine {
+    /// Severity of the line
+    pub level: LogLevel,
+    /// Line text, without trailing newline
+    pub text: String,
+}
 
-    let listener = tokio::net::TcpListener::bind(addr.as_ref()).await?;
-    println!("Server running on http://{}", addr.as_ref());
+impl LogLine {
+    /// Creates a line with the given severity.
+    pub fn new(level: LogLevel, text: impl Into<String>) -> Self {
+        Self { level, text: text.into() }
+    }
+}
 
-    axum::serve(listener, app).await
+impl From<&str> for LogLine {
+    fn from(text: &str) -> Self {
+        Self::new(LogLevel::Info, text)
+    }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use headless_chrome::{Browser, Tab};
-    use std::sync::Arc;
+impl From<String> for LogLine {
+    fn from(text: String) -> Self {
+        Self::new(LogLevel::Info, text)
+    }
+}
 
-    #[test]
-    fn test_app_state_creation() {
-        let state = AppState::new();
-        state.add_element(UiElement::Button {
-            id: "btn1".to_string(),
-            text: "Test".to_string(),
-            on_click: None,
-        });
+/// HTML tags kept when rendering [`UiElement::Markdown`]; everything else is stripped.
+const MARKDOWN_TAGS: &[&str] = &[
+    "a", "blockquote", "br", "code", "del", "em", "h1", "h2", "h3", "h4", "h5", "h6", "hr", "img",
+    "li", "ol", "p", "pre", "strong", "table", "tbody", "td", "th", "thead", "tr", "ul",
+];
+
+/// Renders Markdown to HTML that is safe to insert into the page.
+fn render_markdown(markdown: &str) -> String {
+    use pulldown_cmark::{Options, Parser};
+
+    let parser = Parser::new_ext(markdown, Options::ENABLE_TABLES | Options::ENABLE_STRIKETHROUGH);
+    let mut html = String::new();
+    pulldown_cmark::html::push_html(&mut html, parser);
+    ammonia::Builder::default()
+        .tags(MARKDOWN_TAGS.iter().copied().collect())
+        .clean(&html)
+        .to_string()
+}
 
-        let elements = state.get_all_elements();
-        assert_eq!(elements.len(), 1);
+/// Markdown source of a [`UiElement::Markdown`].
+///
+/// The source is rendered to sanitized HTML once, when the text is created, and
+/// only the HTML is sent to clients.
+#[derive(Clone)]
+pub struct MarkdownText {
+    source: String,
+    html: String,
+}
+
+impl MarkdownText {
+    /// Creates Markdown text from its source.
+    pub fn new(source: impl Into<String>) -> Self {
+        let source = source.into();
+        let html = render_markdown(&source);
+        Self { source, html }
     }
 
-    #[test]
-    fn test_scoped_states() {
-        let state = AppState::new();
+    /// The Markdown source.
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+}
 
-        // Create scoped states
-        let form_state = state.scope("form");
-        let modal_state = state.scope("modal");
+impl From<&str> for MarkdownText {
+    fn from(source: &str) -> Self {
+        Self::new(source)
+    }
+}
 
-        // Add elements with the same local ID to different scopes
-        form_state.add_element(UiElement::Button {
-            id: "btn1".to_string(),
-            text: "Form Button".to_string(),
-            on_click: None,
-        });
+impl From<String> for MarkdownText {
+    fn from(source: String) -> Self {
+        Self::new(source)
+    }
+}
 
-        modal_state.add_element(UiElement::Button {
-            id: "btn1".to_string(),
-            text: "Modal Button".to_string(),
-            on_click: None,
-        });
+impl std::fmt::Debug for MarkdownText {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.source.fmt(f)
+    }
+}
 
-        // Both elements should exist with different full IDs
-        let elements = state.get_all_elements();
-        assert_eq!(elements.len(), 2, "Should have 2 elements with scoped IDs");
+// Only the rendered HTML is sent to clients
+impl Serialize for MarkdownText {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.html)
+    }
+}
 
-        // Verify they're stored with full paths
-        let element_map = state.elements.lock().unwrap();
-        assert!(element_map.contains_key("form.btn1"), "Should contain form.btn1");
-        assert!(element_map.contains_key("modal.btn1"), "Should contain modal.btn1");
+fn serialize_is_some<T, S: serde::Serializer>(value: &Option<T>, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_bool(value.is_some())
+}
+
+/// Source of the image shown by [`UiElement::Image`].
+///
+/// The image is served by the router at `/image/{id}`. Clients receive a version
+/// derived from the image content (or file modification time) and append it to the
+/// URL, so replacing the source with [`AppState::update_element`] reloads the image
+/// in every connected browser. The version is computed when the source is created.
+/// A changed file is served with its new content, but browsers only reload it on
+/// their own when the source is replaced.
+#[derive(Clone)]
+pub struct ImageSource {
+    data: ImageData,
+    version: String,
+}
+
+#[derive(Clone)]
+enum ImageData {
+    /// In-memory image data with its MIME type (e.g. `image/png`)
+    Bytes { data: Bytes, mime_type: String },
+    /// Image file on disk, read when the browser requests it
+    Path(PathBuf),
+}
+
+impl ImageSource {
+    /// Creates an image source from in-memory bytes.
+    pub fn bytes(data: impl Into<Vec<u8>>, mime_type: impl Into<String>) -> Self {
+        Self::new(ImageData::Bytes {
+            data: Bytes::from(data.into()),
+            mime_type: mime_type.into(),
+        })
+    }
+
+    /// Creates an image source from a file path.
+    pub fn path(path: impl Into<PathBuf>) -> Self {
+        Self::new(ImageData::Path(path.into()))
+    }
+
+    fn new(data: ImageData) -> Self {
+        // Cache-busting version that changes whenever the image does
+        let version = match &data {
+            ImageData::Bytes { data, mime_type } => {
+                let mut hasher = std::hash::DefaultHasher::new();
+                data.hash(&mut hasher);
+                mime_type.hash(&mut hasher);
+                format!("{:016x}", hasher.finish())
+            }
+            ImageData::Path(path) => file_version(path, std::fs::metadata(path).and_then(|m| m.modified()).ok()),
+        };
+        Self { data, version }
+    }
+}
+
+/// Version of an image file, derived from its path and modification time.
+fn file_version(path: &std::path::Path, modified: Option<SystemTime>) -> String {
+    let mut hasher = std::hash::DefaultHasher::new();
+    path.hash(&mut hasher);
+    modified.hash(&mut hasher);
+    format!("{:016x}", hasher.finish())
+}
+
+impl std::fmt::Debug for ImageSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.data {
+            ImageData::Bytes { data, mime_type } => f
+                .debug_struct("Bytes")
+                .field("len", &data.len())
+                .field("mime_type", mime_type)
+                .field("version", &self.version)
+                .finish(),
+            ImageData::Path(path) => f
+                .debug_struct("Path")
+                .field("path", path)
+                .field("version", &self.version)
+                .finish(),
+        }
+    }
+}
+
+/// A file being uploaded through a [`UiElement::FileUpload`] element.
+///
+/// The file body is streamed from the request; read it with [`UploadedFile::chunk`]
+/// or collect it with [`UploadedFile::bytes`].
+pub struct UploadedFile {
+    /// File name as reported by the browser
+    pub name: String,
+    /// File size in bytes
+    pub size: u64,
+    /// MIME type as reported by the browser
+    pub mime_type: String,
+    body: BodyDataStream,
+    received: u64,
+}
+
+impl UploadedFile {
+    /// Reads the next chunk of the file body, or `None` once the whole file has been read.
+    ///
+    /// Fails if the connection breaks or the body does not match the declared size.
+    pub async fn chunk(&mut self) -> std::io::Result<Option<Bytes>> {
+        use futures_util::stream::StreamExt;
+
+        match self.body.next().await {
+            Some(Ok(chunk)) => {
+                self.received += chunk.len() as u64;
+                if self.received > self.size {
+                    return Err(std::io::Error::new(
+                        std::io::ErrorKind::InvalidData,
+                        "upload is larger than its declared size",
+                    ));
+                }
+                Ok(Some(chunk))
+            }
+            Some(Err(err)) => Err(std::io::Error::other(err)),
+            None if self.received < self.size => Err(std::io::Error::new(
+                std::io::ErrorKind::UnexpectedEof,
+                "upload ended before its declared size",
+            )),
+            None => Ok(None),
+        }
+    }
+
+    /// Reads the remaining file body into memory.
+    pub async fn bytes(mut self) -> std::io::Result<Vec<u8>> {
+        let mut data = Vec::with_capacity(self.size as usize);
+        while let Some(chunk) = self.chunk().await? {
+            data.extend_from_slice(&chunk);
+        }
+        Ok(data)
+    }
+}
+
+impl std::fmt::Debug for UploadedFile {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("UploadedFile")
+            .field("name", &self.name)
+            .field("size", &self.size)
+            .field("mime_type", &self.mime_type)
+            .field("received", &self.received)
+            .finish()
+    }
+}
+
+// Only the version is sent to clients; the image itself is fetched over HTTP
+impl Serialize for ImageSource {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.version)
+    }
+}
+
+/// UI Element types that can be created in Rust and rendered in HTML.
+///
+/// Each element has an `id` for identification and element-specific properties.
+/// Elements do not contain geometry or styling information - that is handled by HTML/CSS.
+///
+/// Input-like elements have no validator fields. Validators are attached by ID with
+/// [`AppState::set_validators`] and checked on the server. Change handlers still
+/// receive invalid values, so check [`AppState::is_valid`] before acting on them, or
+/// put the elements in a [`UiElement::Form`], whose handler only gets valid values.
+#[derive(Clone, Serialize)]
+#[serde(tag = "kind")]
+pub enum UiElement {
+    /// A clickable button.
+    ///
+    /// # Fields
+    /// - `id`: Unique identifier
+    /// - `text`: Button label text
+    /// - `on_click`: Optional click handler (not serialized)
+    ///
+    /// # HTML Element
+    /// Renders as `<ui-button id="...">text</ui-button>`
+    #[serde(rename = "button")]
+    Button {
+        id: String,
+        text: String,
+        #[serde(skip)]
+        on_click: ClickCallback,
+    },
+
+    /// Read-only text display.
+    ///
+    /// # Fields
+    /// - `id`: Unique identifier
+    /// - `text`: Text content to display
+    ///
+    /// # HTML Element
+    /// Renders as `<ui-text id="...">text</ui-text>`
+    #[serde(rename = "text")]
+    Text { id: String, text: String },
+
+    /// Text input field.
+    ///
+    /// # Fields
+    /// - `id`: Unique identifier
+    /// - `value`: Current input value
+    /// - `label`: Optional label shown with the input
+    /// - `placeholder`: Optional hint shown while the input is empty
+    /// - `tooltip`: Optional tooltip text
+    /// - `on_input`: Optional input change handler (not serialized)
+    ///
+    /// # HTML Element
+    /// Renders as `<ui-input id="...">value</ui-input>`
+    #[serde(rename = "input")]
+    Input {
+        id: String,
+        value: String,
+        label: Option<String>,
+        placeholder: Option<String>,
+        tooltip: Option<String>,
+        #[serde(skip)]
+        on_input: InputCallback,
+    },
+
+    /// Checkbox input.
+    ///
+    /// # Fields
+    /// - `id`: Unique identifier
+    /// - `checked`: Whether the checkbox is checked
+    /// - `label`: Optional label shown after the checkbox
+    /// - `tooltip`: Optional tooltip text
+    /// - `on_change`: Optional change handler (not serialized)
+    ///
+    /// # HTML Element
+    /// Renders as `<ui-checkbox id="...">checked</ui-checkbox>`
+    #[serde(rename = "checkbox")]
+    Checkbox {
+        id: String,
+        checked: bool,
+        label: Option<String>,
+        tooltip: Option<String>,
+        #[serde(skip)]
+        on_change: BoolCallback,
+    },
+
+    /// Slider input (range).
+    ///
+    /// # Fields
+    /// - `id`: Unique identifier
+    /// - `value`: Current slider value
+    /// - `min`: Minimum value
+    /// - `max`: Maximum value
+    /// - `step`: Optional step increment
+    /// - `label`: Optional label shown with the slider
+    /// - `tooltip`: Optional tooltip text
+    /// - `on_change`: Optional change handler (not serialized)
+    ///
+    /// # HTML Element
+    /// Renders as `<ui-slider id="...">value</ui-slider>`
+    #[serde(rename = "slider")]
+    Slider {
+        id: String,
+        value: f64,
+        min: f64,
+        max: f64,
+        step: Option<f64>,
+        label: Option<String>,
+        tooltip: Option<String>,
+        #[serde(skip)]
+        on_change: NumberCallback,
+    },
+
+    /// Radio button input.
+    ///
+    /// # Fields
+    /// - `id`: Unique identifier
+    /// - `name`: Group name (radio buttons with same name are mutually exclusive)
+    /// - `value`: Value when selected
+    /// - `checked`: Whether this radio is selected
+    /// - `label`: Optional label shown after the radio button
+    /// - `tooltip`: Optional tooltip text
+    /// - `on_change`: Optional change handler (not serialized)
+    ///
+    /// # HTML Element
+    /// Renders as `<ui-radio id="..." name="...">checked</ui-radio>`
+    #[serde(rename = "radio")]
+    Radio {
+        id: String,
+        name: String,
+        value: String,
+        checked: bool,
+        label: Option<String>,
+        tooltip: Option<String>,
+        #[serde(skip)]
+        on_change: BoolCallback,
+    },
+
+    /// Number input field.
+    ///
+    /// # Fields
+    /// - `id`: Unique identifier
+    /// - `value`: Current numeric value
+    /// - `min`: Optional minimum value
+    /// - `max`: Optional maximum value
+    /// - `step`: Optional step increment
+    /// - `label`: Optional label shown with the input
+    /// - `placeholder`: Optional hint shown while the input is empty
+    /// - `tooltip`: Optional tooltip text
+    /// - `on_change`: Optional change handler (not serialized)
+    ///
+    /// # HTML Element
+    /// Renders as `<ui-number id="...">value</ui-number>`
+    #[serde(rename = "number")]
+    NumberInput {
+        id: String,
+        value: f64,
+        min: Option<f64>,
+        max: Option<f64>,
+        step: Option<f64>,
+        label: Option<String>,
+        placeholder: Option<String>,
+        tooltip: Option<String>,
+        #[serde(skip)]
+        on_change: NumberCallback,
+    },
+
+    /// Dropdown selection.
+    ///
+    /// # Fields
+    /// - `id`: Unique identifier
+    /// - `options`: Available options as `(value, label)` pairs
+    /// - `selected`: Value of the selected option, if any
+    /// - `on_change`: Optional change handler receiving the selected value (not serialized)
+    ///
+    /// # HTML Element
+    /// Renders as `<ui-select id="...">options</ui-select>`
+    #[serde(rename = "select")]
+    Select {
+        id: String,
+        options: Vec<(String, String)>,
+        selected: Option<String>,
+        #[serde(skip)]
+        on_change: InputCallback,
+    },
+
+    /// Multi-line text input.
+    ///
+    /// # Fields
+    /// - `id`: Unique identifier
+    /// - `value`: Current text
+    /// - `rows`: Optional number of visible text lines
+    /// - `cols`: Optional visible width in characters
+    /// - `on_input`: Optional input change handler (not serialized)
+    ///
+    /// # HTML Element
+    /// Renders as `<ui-textarea id="...">value</ui-textarea>`
+    #[serde(rename = "textarea")]
+    TextArea {
+        id: String,
+        value: String,
+        rows: Option<u32>,
+        cols: Option<u32>,
+        #[serde(skip)]
+        on_input: InputCallback,
+    },
+
+    /// Data table with sortable columns and clickable rows.
+    ///
+    /// # Fields
+    /// - `id`: Unique identifier
+    /// - `columns`: Column definitions
+    /// - `rows`: Table rows
+    /// - `on_row_click`: Optional handler receiving the clicked row key (not serialized)
+    /// - `on_sort`: Optional handler receiving the column key and sort direction (not serialized)
+    ///
+    /// The table does not sort itself; the `on_sort` handler is expected to reorder
+    /// the rows and update the element.
+    ///
+    /// # HTML Element
+    /// Renders as `<ui-table id="...">rows</ui-table>`
+    #[serde(rename = "table")]
+    Table {
+        id: String,
+        columns: Vec<TableColumn>,
+        rows: Vec<TableRow>,
+        #[serde(skip)]
+        on_row_click: InputCallback,
+        #[serde(skip)]
+        on_sort: SortCallback,
+    },
+
+    /// List of items whose length changes at runtime.
+    ///
+    /// # Fields
+    /// - `id`: Unique identifier
+    /// - `items`: List items in display order
+    /// - `on_item_click`: Optional handler receiving the clicked item key (not serialized)
+    ///
+    /// # HTML Element
+    /// Renders as `<ui-list id="...">items</ui-list>`
+    #[serde(rename = "list")]
+    List {
+        id: String,
+        items: Vec<ListItem>,
+        #[serde(skip)]
+        on_item_click: InputCallback,
+    },
+
+    /// Image from in-memory bytes or a file path.
+    ///
+    /// # Fields
+    /// - `id`: Unique identifier
+    /// - `source`: Image data or file (serialized as a cache-busting version)
+    /// - `alt`: Alternative text
+    ///
+    /// # HTML Element
+    /// Renders as `<ui-image id="...">image</ui-image>`
+    #[serde(rename = "image")]
+    Image {
+        id: String,
+        #[serde(rename = "version")]
+        source: ImageSource,
+        alt: String,
+    },
+
+    /// Progress bar for long-running tasks.
+    ///
+    /// # Fields
+    /// - `id`: Unique identifier
+    /// - `value`: Current progress, or `None` for an indeterminate progress bar
+    /// - `max`: Value at which the task is complete
+    ///
+    /// Use [`AppState::set_progress`] for frequent updates.
+    ///
+    /// # HTML Element
+    /// Renders as `<ui-progress id="...">value</ui-progress>`
+    #[serde(rename = "progress")]
+    Progress {
+        id: String,
+        value: Option<f64>,
+        max: f64,
+    },
+
+    /// Gauge showing a value within a known range.
+    ///
+    /// # Fields
+    /// - `id`: Unique identifier
+    /// - `value`: Current value
+    /// - `min`: Minimum value
+    /// - `max`: Maximum value
+    /// - `low`: Optional upper bound of the low range
+    /// - `high`: Optional lower bound of the high range
+    /// - `optimum`: Optional optimal value, which decides how the ranges are colored
+    ///
+    /// Use [`AppState::set_meter`] for frequent updates.
+    ///
+    /// # HTML Element
+    /// Renders as `<ui-meter id="...">value</ui-meter>`
+    #[serde(rename = "meter")]
+    Meter {
+        id: String,
+        value: f64,
+        min: f64,
+        max: f64,
+        low: Option<f64>,
+        high: Option<f64>,
+        optimum: Option<f64>,
+    },
+
+    /// File picker that uploads the selected files to the server.
+    ///
+    /// # Fields
+    /// - `id`: Unique identifier
+    /// - `accept`: Optional accepted file types, e.g. `".csv,text/plain"`
+    /// - `multiple`: Whether several files can be selected at once
+    /// - `max_size`: Optional maximum size per file in bytes, [`DEFAULT_MAX_UPLOAD_SIZE`] if `None`
+    /// - `on_upload`: Optional async handler called once per file (not serialized)
+    ///
+    /// Files are sent to the `/upload/{id}` route and streamed into `on_upload`.
+    /// An `Err` returned by the handler is shown next to the file picker.
+    ///
+    /// # HTML Element
+    /// Renders as `<ui-file-upload id="...">picker</ui-file-upload>`
+    #[serde(rename = "file_upload")]
+    FileUpload {
+        id: String,
+        accept: Option<String>,
+        multiple: bool,
+        max_size: Option<u64>,
+        #[serde(skip)]
+        on_upload: UploadCallback,
+    },
+
+    /// Drawing surface rendered from a list of drawing commands.
+    ///
+    /// # Fields
+    /// - `id`: Unique identifier
+    /// - `width`: Canvas width in pixels
+    /// - `height`: Canvas height in pixels
+    /// - `commands`: Drawing commands executed in order
+    /// - `on_pointer`: Optional handler for pointer events on the canvas (not serialized)
+    ///
+    /// Use [`AppState::draw`] to add commands without resending the whole list.
+    ///
+    /// # HTML Element
+    /// Renders as `<ui-canvas id="...">drawing</ui-canvas>`
+    #[serde(rename = "canvas")]
+    Canvas {
+        id: String,
+        width: u32,
+        height: u32,
+        commands: Vec<DrawCommand>,
+        #[serde(skip)]
+        on_pointer: PointerCallback,
+    },
+
+    /// Line or bar chart of one or more data series.
+    ///
+    /// # Fields
+    /// - `id`: Unique identifier
+    /// - `chart_type`: Whether series are drawn as lines or bars
+    /// - `series`: Data series
+    /// - `x_label`: Optional x axis label
+    /// - `y_label`: Optional y axis label
+    /// - `max_points`: Optional number of most recent points kept per series, also
+    ///   applied to the initial `series`
+    ///
+    /// Use [`AppState::append_chart_points`] to stream new points.
+    /// The chart fills the size of the `<ui-chart>` element, which is set in CSS.
+    ///
+    /// # HTML Element
+    /// Renders as `<ui-chart id="...">chart</ui-chart>`
+    #[serde(rename = "chart")]
+    Chart {
+        id: String,
+        chart_type: ChartType,
+        series: Vec<ChartSeries>,
+        x_label: Option<String>,
+        y_label: Option<String>,
+        max_points: Option<usize>,
+    },
+
+    /// Tab bar switching between panels declared in HTML.
+    ///
+    /// # Fields
+    /// - `id`: Unique identifier
+    /// - `tabs`: Tabs as `(tab_id, label)` pairs
+    /// - `active`: ID of the visible tab
+    /// - `on_change`: Optional handler receiving the ID of the newly selected tab (not serialized)
+    ///
+    /// Child elements of `<ui-tabs>` with a `data-tab` attribute are the panels;
+    /// only the panel of the active tab is shown.
+    ///
+    /// # HTML Element
+    /// Renders as `<ui-tabs id="...">tab bar and panels</ui-tabs>`
+    #[serde(rename = "tabs")]
+    Tabs {
+        id: String,
+        tabs: Vec<(String, String)>,
+        active: String,
+        #[serde(skip)]
+        on_change: InputCallback,
+    },
+
+    /// Tree of nodes whose children can be loaded on demand.
+    ///
+    /// # Fields
+    /// - `id`: Unique identifier
+    /// - `nodes`: Top-level nodes
+    /// - `selected`: ID of the selected node, if any
+    /// - `on_expand`: Optional async loader receiving the ID of a node expanded for the
+    ///   first time and returning its children (not serialized)
+    /// - `on_select`: Optional handler receiving the ID of the selected node (not serialized)
+    ///
+    /// Use [`AppState::update_tree_node`] to change a single node.
+    ///
+    /// # HTML Element
+    /// Renders as `<ui-tree id="...">nodes</ui-tree>`
+    #[serde(rename = "tree")]
+    Tree {
+        id: String,
+        nodes: Vec<TreeNode>,
+        selected: Option<String>,
+        #[serde(skip)]
+        on_expand: TreeLoaderCallback,
+        #[serde(skip)]
+        on_select: InputCallback,
+    },
+
+    /// Date, time or date-and-time picker.
+    ///
+    /// # Fields
+    /// - `id`: Unique identifier
+    /// - `mode`: Which kind of value is picked
+    /// - `value`: Current value, if any
+    /// - `min`: Optional earliest allowed value
+    /// - `max`: Optional latest allowed value
+    /// - `on_change`: Optional change handler receiving the new value, or `None` when
+    ///   cleared (not serialized)
+    ///
+    /// Values from the browser are validated against the mode and bounds before
+    /// they are stored and passed to the handler; invalid values are ignored.
+    ///
+    /// # HTML Element
+    /// Renders as `<ui-datetime id="...">input</ui-datetime>`
+    #[serde(rename = "datetime")]
+    DateTime {
+        id: String,
+        mode: DateTimeMode,
+        value: Option<DateTimeValue>,
+        min: Option<DateTimeValue>,
+        max: Option<DateTimeValue>,
+        #[serde(skip)]
+        on_change: DateTimeCallback,
+    },
+
+    /// Color picker with optional preset swatches.
+    ///
+    /// # Fields
+    /// - `id`: Unique identifier
+    /// - `value`: Current color as `#rrggbb`, or `#rrggbbaa` if `alpha` is set
+    /// - `alpha`: Whether the user can also pick an opacity
+    /// - `presets`: Colors offered as swatches, in the same format as `value`
+    /// - `on_change`: Optional change handler receiving the new color (not serialized)
+    ///
+    /// Colors are validated and normalized to lowercase, both when the element is
+    /// added and when the browser reports a change. An invalid initial `value` is
+    /// replaced with black and invalid presets are dropped; invalid changes are ignored.
+    ///
+    /// # HTML Element
+    /// Renders as `<ui-color id="...">picker</ui-color>`
+    #[serde(rename = "color")]
+    Color {
+        id: String,
+        value: String,
+        alpha: bool,
+        presets: Vec<String>,
+        #[serde(skip)]
+        on_change: InputCallback,
+    },
+
+    /// Formatted text written in Markdown.
+    ///
+    /// # Fields
+    /// - `id`: Unique identifier
+    /// - `markdown`: Markdown source, including tables and strikethrough
+    /// - `on_link_click`: Optional handler receiving the `href` of clicked links; if set,
+    ///   links no longer navigate (only whether it is set is serialized)
+    ///
+    /// The Markdown is rendered to HTML on the server when the [`MarkdownText`] is
+    /// created. Raw HTML in the source is sanitized: tags outside a small formatting
+    /// allowlist, scripts, event handler attributes and unsafe URLs are removed.
+    ///
+    /// # HTML Element
+    /// Renders as `<ui-markdown id="...">html</ui-markdown>`
+    #[serde(rename = "markdown")]
+    Markdown {
+        id: String,
+        #[serde(rename = "html")]
+        markdown: MarkdownText,
+        #[serde(rename = "route_links", serialize_with = "serialize_is_some")]
+        on_link_click: InputCallback,
+    },
+
+    /// Append-only log output keeping the most recent lines.
+    ///
+    /// # Fields
+    /// - `id`: Unique identifier
+    /// - `lines`: Retained lines, oldest first
+    /// - `max_lines`: Number of most recent lines kept, also applied to the initial `lines`
+    ///
+    /// Use [`AppState::append_log`] to add lines. The view follows new lines
+    /// unless the user has scrolled up.
+    ///
+    /// # HTML Element
+    /// Renders as `<ui-log id="...">lines</ui-log>`
+    #[serde(rename = "log")]
+    Log {
+        id: String,
+        lines: Vec<LogLine>,
+        max_lines: usize,
+    },
+
+    /// Text input with suggestions computed on the server as the user types.
+    ///
+    /// # Fields
+    /// - `id`: Unique identifier
+    /// - `value`: Current text
+    /// - `on_suggest`: Optional async provider receiving the typed query and returning
+    ///   suggestions, best first (not serialized)
+    /// - `on_select`: Optional handler receiving the chosen suggestion, or the typed
+    ///   text when Enter is pressed (not serialized)
+    ///
+    /// Suggestions are only sent to the browser that asked for them. Requests are
+    /// debounced in the browser, and a running request is cancelled when a newer
+    /// query arrives, so results for stale queries are never shown.
+    ///
+    /// # HTML Element
+    /// Renders as `<ui-combobox id="...">input and suggestions</ui-combobox>`
+    #[serde(rename = "combobox")]
+    Combobox {
+        id: String,
+        value: String,
+        #[serde(skip)]
+        on_suggest: SuggestCallback,
+        #[serde(skip)]
+        on_select: InputCallback,
+    },
+
+    /// Element implemented in JavaScript and registered with `registerUiElement`.
+    ///
+    /// # Fields
+    /// - `id`: Unique identifier
+    /// - `kind`: Element kind; the element renders as `<ui-{kind}>`
+    /// - `props`: Arbitrary properties passed to the element's `setProps`
+    /// - `on_event`: Optional handler receiving event payloads sent by the element (not serialized)
+    ///
+    /// See `registerUiElement` in `webui.js` for how to implement the browser side.
+    ///
+    /// # HTML Element
+    /// Renders as `<ui-{kind} id="...">custom content</ui-{kind}>`
+    #[serde(rename = "custom")]
+    Custom {
+        id: String,
+        #[serde(rename = "custom_kind")]
+        kind: String,
+        props: serde_json::Value,
+        #[serde(skip)]
+        on_event: JsonCallback,
+    },
+
+    /// Form that submits the values of all elements inside it at once.
+    ///
+    /// The form's ID is also the scope of the elements inside it, so they are added
+    /// with `state.scope(form_id)`. The form is submitted with a `<ui-button submit>`
+    /// or by pressing Enter in a text field.
+    ///
+    /// # Fields
+    /// - `id`: Unique identifier and scope name of the form's elements
+    /// - `on_submit`: Optional handler receiving the values of the enabled elements,
+    ///   keyed by their ID within the form (not serialized)
+    ///
+    /// Submitted values that differ from the stored ones are applied like change
+    /// events, calling the elements' handlers, and shown in all clients. They are
+    /// then validated; the handler is only called when all of them pass their
+    /// validators. Values have
+    /// the same JSON types as change events, except that a radio group is reported
+    /// under its `name` with the `value` of the checked button (or `null`). Use
+    /// [`form_values`] to deserialize them into a struct.
+    ///
+    /// # HTML Element
+    /// Renders as `<ui-form id="...">form elements</ui-form>`
+    #[serde(rename = "form")]
+    Form {
+        id: String,
+        #[serde(skip)]
+        on_submit: FormCallback,
+    },
+}
+
+/// Deserializes the values of a submitted [`UiElement::Form`] into `T`.
+///
+/// # Example
+/// ```
+/// # use std::collections::HashMap;
+/// #[derive(serde::Deserialize)]
+/// struct Signup {
+///     name: String,
+///     newsletter: bool,
+/// }
+///
+/// let values = HashMap::from([
+///     ("name".to_string(), serde_json::json!("Ada")),
+///     ("newsletter".to_string(), serde_json::json!(true)),
+/// ]);
+/// let signup: Signup = webui::form_values(values).unwrap();
+/// assert_eq!(signup.name, "Ada");
+/// ```
+pub fn form_values<T: serde::de::DeserializeOwned>(
+    values: HashMap<String, serde_json::Value>,
+) -> Result<T, serde_json::Error> {
+    serde_json::from_value(serde_json::Value::Object(values.into_iter().collect()))
+}
+
+impl UiElement {
+    /// The element's ID, as given when the element was created.
+    pub fn id(&self) -> &str {
+        match self {
+            UiElement::Button { id, .. }
+            | UiElement::Text { id, .. }
+            | UiElement::Input { id, .. }
+            | UiElement::Checkbox { id, .. }
+            | UiElement::Slider { id, .. }
+            | UiElement::Radio { id, .. }
+            | UiElement::NumberInput { id, .. }
+            | UiElement::Select { id, .. }
+            | UiElement::TextArea { id, .. }
+            | UiElement::Table { id, .. }
+            | UiElement::List { id, .. }
+            | UiElement::Image { id, .. }
+            | UiElement::Progress { id, .. }
+            | UiElement::Meter { id, .. }
+            | UiElement::FileUpload { id, .. }
+            | UiElement::Canvas { id, .. }
+            | UiElement::Chart { id, .. }
+            | UiElement::Tabs { id, .. }
+            | UiElement::Tree { id, .. }
+            | UiElement::DateTime { id, .. }
+            | UiElement::Color { id, .. }
+            | UiElement::Markdown { id, .. }
+            | UiElement::Log { id, .. }
+            | UiElement::Combobox { id, .. }
+            | UiElement::Custom { id, .. }
+            | UiElement::Form { id, .. } => id,
+        }
+    }
+
+    /// The current value of an input-like element, in the form clients report it.
+    fn value(&self) -> Option<serde_json::Value> {
+        use serde_json::Value;
+        match self {
+            UiElement::Input { value, .. }
+            | UiElement::TextArea { value, .. }
+            | UiElement::Color { value, .. }
+            | UiElement::Combobox { value, .. } => Some(Value::from(value.as_str())),
+            UiElement::Checkbox { checked, .. } | UiElement::Radio { checked, .. } => Some(Value::from(*checked)),
+            UiElement::Slider { value, .. } | UiElement::NumberInput { value, .. } => Some(Value::from(*value)),
+            UiElement::Select { selected, .. } => Some(Value::from(selected.clone())),
+            UiElement::DateTime { value, .. } => Some(Value::from(value.map(|value| value.to_string()))),
+            _ => None,
+        }
+    }
+
+    /// Sets the value of an input-like element from a value in the form clients report it.
+    ///
+    /// Returns false, leaving the element unchanged, if the value is not valid for it.
+    fn set_value(&mut self, new_value: &serde_json::Value) -> bool {
+        match self {
+            UiElement::Input { value, .. } | UiElement::TextArea { value, .. } | UiElement::Combobox { value, .. } => {
+                new_value.as_str().map(|new_value| *value = new_value.to_string()).is_some()
+            }
+            UiElement::Checkbox { checked, .. } | UiElement::Radio { checked, .. } => {
+                new_value.as_bool().map(|new_value| *checked = new_value).is_some()
+            }
+            UiElement::Slider { value, .. } | UiElement::NumberInput { value, .. } => {
+                new_value.as_f64().map(|new_value| *value = new_value).is_some()
+            }
+            UiElement::Select { options, selected, .. } => {
+                // Keep the stored selection in sync so later option updates preserve it
+                match new_value.as_str() {
+                    Some(new_value) if options.iter().any(|(v, _)| v == new_value) => {
+                        *selected = Some(new_value.to_string());
+                        true
+                    }
+                    _ => false,
+                }
+            }
+            UiElement::DateTime { mode, value, min, max, .. } => {
+                // An empty string clears the value
+                let parsed = match new_value.as_str() {
+                    Some("") => Some(None),
+                    Some(new_value) => DateTimeValue::parse(*mode, new_value)
+                        .filter(|new_value| min.is_none_or(|min| *new_value >= min))
+                        .filter(|new_value| max.is_none_or(|max| *new_value <= max))
+                        .map(Some),
+                    None => None,
+                };
+                parsed.map(|parsed| *value = parsed).is_some()
+            }
+            UiElement::Color { value, alpha, .. } => new_value
+                .as_str()
+                .and_then(|new_value| normalize_color(new_value, *alpha))
+                .map(|new_value| *value = new_value)
+                .is_some(),
+            _ => false,
+        }
+    }
+
+    /// Applies the limits and value formats an element declares to the content it was created with.
+    fn normalized(mut self) -> Self {
+        match &mut self {
+            UiElement::Chart { series, max_points: Some(max_points), .. } => {
+                for series in series {
+                    if series.points.len() > *max_points {
+                        let excess = series.points.len() - *max_points;
+                        series.points.drain(..excess);
+                    }
+                }
+            }
+            UiElement::Log { lines, max_lines, .. } if lines.len() > *max_lines => {
+                let excess = lines.len() - *max_lines;
+                lines.drain(..excess);
+            }
+            UiElement::Color { value, alpha, presets, .. } => {
+                let black = if *alpha { "#000000ff" } else { "#000000" };
+                *value = normalize_color(value, *alpha).unwrap_or_else(|| black.to_string());
+                *presets = presets.iter().filter_map(|preset| normalize_color(preset, *alpha)).collect();
+            }
+            _ => {}
+        }
+        self
+    }
+
+    /// Used to rewrite local IDs to full scoped IDs.
+    fn id_mut(&mut self) -> &mut String {
+        match self {
+            UiElement::Button { id, .. }
+            | UiElement::Text { id, .. }
+            | UiElement::Input { id, .. }
+            | UiElement::Checkbox { id, .. }
+            | UiElement::Slider { id, .. }
+            | UiElement::Radio { id, .. }
+            | UiElement::NumberInput { id, .. }
+            | UiElement::Select { id, .. }
+            | UiElement::TextArea { id, .. }
+            | UiElement::Table { id, .. }
+            | UiElement::List { id, .. }
+            | UiElement::Image { id, .. }
+            | UiElement::Progress { id, .. }
+            | UiElement::Meter { id, .. }
+            | UiElement::FileUpload { id, .. }
+            | UiElement::Canvas { id, .. }
+            | UiElement::Chart { id, .. }
+            | UiElement::Tabs { id, .. }
+            | UiElement::Tree { id, .. }
+            | UiElement::DateTime { id, .. }
+            | UiElement::Color { id, .. }
+            | UiElement::Markdown { id, .. }
+            | UiElement::Log { id, .. }
+            | UiElement::Combobox { id, .. }
+            | UiElement::Custom { id, .. }
+            | UiElement::Form { id, .. } => id,
+        }
+    }
+}
+
+impl std::fmt::Debug for UiElement {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            UiElement::Button { id, text, .. } => f
+                .debug_struct("Button")
+                .field("id", id)
+                .field("text", text)
+                .field("on_click", &"<handler>")
+                .finish(),
+            UiElement::Text { id, text } => f
+                .debug_struct("Text")
+                .field("id", id)
+                .field("text", text)
+                .finish(),
+            UiElement::Input { id, value, label, placeholder, tooltip, .. } => f
+                .debug_struct("Input")
+                .field("id", id)
+                .field("value", value)
+                .field("label", label)
+                .field("placeholder", placeholder)
+                .field("tooltip", tooltip)
+                .field("on_input", &"<handler>")
+                .finish(),
+            UiElement::Checkbox { id, checked, label, tooltip, .. } => f
+                .debug_struct("Checkbox")
+                .field("id", id)
+                .field("checked", checked)
+                .field("label", label)
+                .field("tooltip", tooltip)
+                .field("on_change", &"<handler>")
+                .finish(),
+            UiElement::Slider { id, value, min, max, step, label, tooltip, .. } => f
+                .debug_struct("Slider")
+                .field("id", id)
+                .field("value", value)
+                .field("min", min)
+                .field("max", max)
+                .field("step", step)
+                .field("label", label)
+                .field("tooltip", tooltip)
+                .field("on_change", &"<handler>")
+                .finish(),
+            UiElement::Radio { id, name, value, checked, label, tooltip, .. } => f
+                .debug_struct("Radio")
+                .field("id", id)
+                .field("name", name)
+                .field("value", value)
+                .field("checked", checked)
+                .field("label", label)
+                .field("tooltip", tooltip)
+                .field("on_change", &"<handler>")
+                .finish(),
+            UiElement::NumberInput { id, value, min, max, step, label, placeholder, tooltip, .. } => f
+                .debug_struct("NumberInput")
+                .field("id", id)
+                .field("value", value)
+                .field("min", min)
+                .field("max", max)
+                .field("step", step)
+                .field("label", label)
+                .field("placeholder", placeholder)
+                .field("tooltip", tooltip)
+                .field("on_change", &"<handler>")
+                .finish(),
+            UiElement::Select { id, options, selected, .. } => f
+                .debug_struct("Select")
+                .field("id", id)
+                .field("options", options)
+                .field("selected", selected)
+                .field("on_change", &"<handler>")
+                .finish(),
+            UiElement::TextArea { id, value, rows, cols, .. } => f
+                .debug_struct("TextArea")
+                .field("id", id)
+                .field("value", value)
+                .field("rows", rows)
+                .field("cols", cols)
+                .field("on_input", &"<handler>")
+                .finish(),
+            UiElement::Table { id, columns, rows, .. } => f
+                .debug_struct("Table")
+                .field("id", id)
+                .field("columns", columns)
+                .field("rows", rows)
+                .field("on_row_click", &"<handler>")
+                .field("on_sort", &"<handler>")
+                .finish(),
+            UiElement::List { id, items, .. } => f
+                .debug_struct("List")
+                .field("id", id)
+                .field("items", items)
+                .field("on_item_click", &"<handler>")
+                .finish(),
+            UiElement::Image { id, source, alt } => f
+                .debug_struct("Image")
+                .field("id", id)
+                .field("source", source)
+                .field("alt", alt)
+                .finish(),
+            UiElement::Progress { id, value, max } => f
+                .debug_struct("Progress")
+                .field("id", id)
+                .field("value", value)
+                .field("max", max)
+                .finish(),
+            UiElement::Meter { id, value, min, max, low, high, optimum } => f
+                .debug_struct("Meter")
+                .field("id", id)
+                .field("value", value)
+                .field("min", min)
+                .field("max", max)
+                .field("low", low)
+                .field("high", high)
+                .field("optimum", optimum)
+                .finish(),
+            UiElement::FileUpload { id, accept, multiple, max_size, .. } => f
+                .debug_struct("FileUpload")
+                .field("id", id)
+                .field("accept", accept)
+                .field("multiple", multiple)
+                .field("max_size", max_size)
+                .field("on_upload", &"<handler>")
+                .finish(),
+            UiElement::Canvas { id, width, height, commands, .. } => f
+                .debug_struct("Canvas")
+                .field("id", id)
+                .field("width", width)
+                .field("height", height)
+                .field("commands", commands)
+                .field("on_pointer", &"<handler>")
+                .finish(),
+            UiElement::Chart { id, chart_type, series, x_label, y_label, max_points } => f
+                .debug_struct("Chart")
+                .field("id", id)
+                .field("chart_type", chart_type)
+                .field("series", series)
+                .field("x_label", x_label)
+                .field("y_label", y_label)
+                .field("max_points", max_points)
+                .finish(),
+            UiElement::Tabs { id, tabs, active, .. } => f
+                .debug_struct("Tabs")
+                .field("id", id)
+                .field("tabs", tabs)
+                .field("active", active)
+                .field("on_change", &"<handler>")
+                .finish(),
+            UiElement::Tree { id, nodes, selected, .. } => f
+                .debug_struct("Tree")
+                .field("id", id)
+                .field("nodes", nodes)
+                .field("selected", selected)
+                .field("on_expand", &"<handler>")
+                .field("on_select", &"<handler>")
+                .finish(),
+            UiElement::DateTime { id, mode, value, min, max, .. } => f
+                .debug_struct("DateTime")
+                .field("id", id)
+                .field("mode", mode)
+                .field("value", value)
+                .field("min", min)
+                .field("max", max)
+                .field("on_change", &"<handler>")
+                .finish(),
+            UiElement::Color { id, value, alpha, presets, .. } => f
+                .debug_struct("Color")
+                .field("id", id)
+                .field("value", value)
+                .field("alpha", alpha)
+                .field("presets", presets)
+                .field("on_change", &"<handler>")
+                .finish(),
+            UiElement::Markdown { id, markdown, .. } => f
+                .debug_struct("Markdown")
+                .field("id", id)
+                .field("markdown", markdown)
+                .field("on_link_click", &"<handler>")
+                .finish(),
+            UiElement::Log { id, lines, max_lines } => f
+                .debug_struct("Log")
+                .field("id", id)
+                .field("lines", lines)
+                .field("max_lines", max_lines)
+                .finish(),
+            UiElement::Combobox { id, value, .. } => f
+                .debug_struct("Combobox")
+                .field("id", id)
+                .field("value", value)
+                .field("on_suggest", &"<handler>")
+                .field("on_select", &"<handler>")
+                .finish(),
+            UiElement::Custom { id, kind, props, .. } => f
+                .debug_struct("Custom")
+                .field("id", id)
+                .field("kind", kind)
+                .field("props", props)
+                .field("on_event", &"<handler>")
+                .finish(),
+            UiElement::Form { id, .. } => f
+                .debug_struct("Form")
+                .field("id", id)
+                .field("on_submit", &"<handler>")
+                .finish(),
+        }
+    }
+}
+
+/// Application state managing UI elements and event handlers.
+///
+/// The `AppState` is the core of the WebUI framework. It:
+/// - Stores all UI elements by ID (buttons and inputs include their handlers)
+/// - Broadcasts updates to all connected WebSocket clients
+/// - Supports scoped namespacing to prevent ID conflicts
+///
+/// # Thread Safety
+/// `AppState` is designed to be shared across multiple async tasks and cloned freely.
+/// All mutations are protected by internal locks.
+#[derive(Clone)]
+pub struct AppState {
+    elements: Arc<Mutex<HashMap<String, UiElement>>>,
+    element_states: Arc<Mutex<HashMap<String, ElementState>>>,
+    downloads: Arc<Mutex<HashMap<String, PendingDownload>>>,
+    dialogs: Arc<Mutex<HashMap<u64, PendingDialog>>>,
+    notifications: Arc<Mutex<HashMap<u64, PendingNotification>>>,
+    tree_loads: Arc<Mutex<HashSet<(String, String)>>>,
+    clients: Arc<AtomicUsize>,
+    connections: Arc<AtomicU64>,
+    update_tx: broadcast::Sender<ServerMessage>,
+    scope_path: String,
+}
+
+impl AppState {
+    /// Creates a new `AppState` instance.
+    ///
+    /// # Example
+    /// ```
+    /// use webui::AppState;
+    ///
+    /// let state = AppState::new();
+    /// ```
+    pub fn new() -> Self {
+        let (tx, _) = broadcast::channel(100);
+        Self {
+            elements: Arc::new(Mutex::new(HashMap::new())),
+            element_states: Arc::new(Mutex::new(HashMap::new())),
+            downloads: Arc::new(Mutex::new(HashMap::new())),
+            dialogs: Arc::new(Mutex::new(HashMap::new())),
+            notifications: Arc::new(Mutex::new(HashMap::new())),
+            tree_loads: Arc::new(Mutex::new(HashSet::new())),
+            clients: Arc::new(AtomicUsize::new(0)),
+            connections: Arc::new(AtomicU64::new(0)),
+            update_tx: tx,
+            scope_path: String::new(),
+        }
+    }
+
+    /// Creates a scoped child state with automatic ID prefixing.
+    ///
+    /// Elements added to a scoped state are automatically prefixed with the scope path.
+    /// This prevents ID conflicts when using the same local IDs in different contexts.
+    ///
+    /// # Arguments
+    /// - `name`: The scope name to add to the current path
+    ///
+    /// # Example
+    /// ```
+    /// use webui::{AppState, UiElement};
+    ///
+    /// let state = AppState::new();
+    /// let form_state = state.scope("form");
+    /// let modal_state = state.scope("modal");
+    ///
+    /// // Both use local ID "btn1", but stored as "form.btn1" and "modal.btn1"
+    /// form_state.add_element(UiElement::Button {
+    ///     id: "btn1".to_string(),
+    ///     text: "Submit Form".to_string(),
+    ///     on_click: None,
+    /// });
+    ///
+    /// modal_state.add_element(UiElement::Button {
+    ///     id: "btn1".to_string(),
+    ///     text: "Close Modal".to_string(),
+    ///     on_click: None,
+    /// });
+    /// ```
+    pub fn scope(&self, name: &str) -> Self {
+        let new_path = if self.scope_path.is_empty() {
+            name.to_string()
+        } else {
+            format!("{}.{}", self.scope_path, name)
+        };
+
+        Self {
+            elements: self.elements.clone(),
+            element_states: self.element_states.clone(),
+            downloads: self.downloads.clone(),
+            dialogs: self.dialogs.clone(),
+            notifications: self.notifications.clone(),
+            tree_loads: self.tree_loads.clone(),
+            clients: self.clients.clone(),
+            connections: self.connections.clone(),
+            update_tx: self.update_tx.clone(),
+            scope_path: new_path,
+        }
+    }
+
+    /// Builds a full ID by prepending the scope path.
+    fn full_id(&self, local_id: &str) -> String {
+        if self.scope_path.is_empty() {
+            local_id.to_string()
+        } else {
+            format!("{}.{}", self.scope_path, local_id)
+        }
+    }
+
+    /// Adds a UI element to the application.
+    ///
+    /// The element's ID is automatically prefixed with the current scope path.
+    /// If an element with the same full ID already exists, it will be replaced.
+    ///
+    /// # Example
+    /// ```
+    /// use webui::{AppState, UiElement};
+    ///
+    /// let state = AppState::new();
+    /// state.add_element(UiElement::Button {
+    ///     id: "btn1".to_string(),
+    ///     text: "Click Me!".to_string(),
+    ///     on_click: None,
+    /// });
+    ///
+    /// // Using scopes to avoid ID conflicts
+    /// let form = state.scope("form");
+    /// form.add_element(UiElement::Button {
+    ///     id: "btn1".to_string(),  // Stored as "form.btn1"
+    ///     text: "Submit".to_string(),
+    ///     on_click: None,
+    /// });
+    /// ```
+    pub fn add_element(&self, element: UiElement) {
+        let full_id = self.full_id(element.id());
+        if self.store_element(&full_id, element.normalized()).error.is_some() {
+            // The error was shown for the previous value
+            self.validate_element(&full_id);
+        }
+    }
+
+    /// Updates an existing element and broadcasts the change to all connected clients.
+    ///
+    /// The local ID is automatically prefixed with the current scope path.
+    ///
+    /// # Example
+    /// ```
+    /// # use webui::{AppState, UiElement};
+    /// # let state = AppState::new();
+    /// state.update_element(
+    ///     "status",
+    ///     UiElement::Text {
+    ///         id: "status".to_string(),
+    ///         text: "Updated!".to_string(),
+    ///     },
+    /// );
+    ///
+    /// // With scopes
+    /// let form = state.scope("form");
+    /// form.update_element(
+    ///     "status",  // Updates "form.status"
+    ///     UiElement::Text {
+    ///         id: "status".to_string(),
+    ///         text: "Form submitted!".to_string(),
+    ///     },
+    /// );
+    /// ```
+    pub fn update_element(&self, id: &str, element: UiElement) {
+        let full_id = self.full_id(id);
+        if self.store_element(&full_id, element.normalized()).error.is_some() {
+            // The error was shown for the previous value
+            self.validate_element(&full_id);
+        }
+        self.send_element(&full_id);
+    }
+
+    /// Enables or disables an element in all clients.
+    ///
+    /// Disabled elements cannot be used, and user events reported for them are
+    /// ignored. Does nothing if `id` does not refer to an element.
+    ///
+    /// # Example
+    /// ```
+    /// # use webui::{AppState, UiElement};
+    /// # let state = AppState::new();
+    /// state.add_element(UiElement::Button {
+    ///     id: "start".to_string(),
+    ///     text: "Start".to_string(),
+    ///     on_click: None,
+    /// });
+    ///
+    /// // While a job runs
+    /// state.set_enabled("start", false);
+    /// ```
+    pub fn set_enabled(&self, id: &str, enabled: bool) {
+        let full_id = self.full_id(id);
+        if !self.update_element_state(&full_id, |state| std::mem::replace(&mut state.enabled, enabled) != enabled) {
+            return;
+        }
+        let _ = self.update_tx.send(ServerMessage::Enabled { id: full_id, enabled });
+    }
+
+    /// Shows or hides an element in all clients.
+    ///
+    /// Does nothing if `id` does not refer to an element.
+    ///
+    /// # Example
+    /// ```
+    /// # use webui::{AppState, UiElement};
+    /// # let state = AppState::new();
+    /// state.add_element(UiElement::Input {
+    ///     id: "proxy".to_string(),
+    ///     value: String::new(),
+    ///     label: None,
+    ///     placeholder: None,
+    ///     tooltip: None,
+    ///     on_input: None,
+    /// });
+    ///
+    /// state.set_visible("proxy", false);
+    /// ```
+    pub fn set_visible(&self, id: &str, visible: bool) {
+        let full_id = self.full_id(id);
+        if !self.update_element_state(&full_id, |state| std::mem::replace(&mut state.visible, visible) != visible) {
+            return;
+        }
+        let _ = self.update_tx.send(ServerMessage::Visible { id: full_id, visible });
+    }
+
+    /// Adds a CSS class to an element in all clients.
+    ///
+    /// The class is set on the `<ui-*>` element itself. Several classes can be
+    /// given separated by whitespace. Does nothing if `id` does not refer to an
+    /// element.
+    ///
+    /// # Example
+    /// ```
+    /// # use webui::{AppState, UiElement};
+    /// # let state = AppState::new();
+    /// state.add_element(UiElement::Text {
+    ///     id: "status".to_string(),
+    ///     text: "Build failed".to_string(),
+    /// });
+    ///
+    /// state.add_class("status", "error");
+    /// ```
+    pub fn add_class(&self, id: &str, class: &str) {
+        let full_id = self.full_id(id);
+        let mut added = Vec::new();
+        self.update_element_state(&full_id, |state| {
+            for class in class.split_whitespace() {
+                if !state.classes.iter().any(|c| c == class) {
+                    state.classes.push(class.to_string());
+                    added.push(class.to_string());
+                }
+            }
+            !added.is_empty()
+        });
+        for class in added {
+            let _ = self.update_tx.send(ServerMessage::AddClass { id: full_id.clone(), class });
+        }
+    }
+
+    /// Removes CSS classes added with [`add_class`](Self::add_class) from an element in all clients.
+    pub fn remove_class(&self, id: &str, class: &str) {
+        let full_id = self.full_id(id);
+        let mut removed = Vec::new();
+        self.update_element_state(&full_id, |state| {
+            for class in class.split_whitespace() {
+                if let Some(index) = state.classes.iter().position(|c| c == class) {
+                    removed.push(state.classes.remove(index));
+                }
+            }
+            !removed.is_empty()
+        });
+        for class in removed {
+            let _ = self.update_tx.send(ServerMessage::RemoveClass { id: full_id.clone(), class });
+        }
+    }
+
+    /// Adds CSS classes to an element if it does not have all of them, and removes them otherwise.
+    ///
+    /// Returns whether the element has the classes afterwards.
+    ///
+    /// # Example
+    /// ```
+    /// # use webui::{AppState, UiElement};
+    /// # let state = AppState::new();
+    /// state.add_element(UiElement::Button {
+    ///     id: "card-1".to_string(),
+    ///     text: "Card 1".to_string(),
+    ///     on_click: None,
+    /// });
+    ///
+    /// assert!(state.toggle_class("card-1", "selected"));
+    /// assert!(!state.toggle_class("card-1", "selected"));
+    /// ```
+    pub fn toggle_class(&self, id: &str, class: &str) -> bool {
+        let full_id = self.full_id(id);
+        let mut present = false;
+        let mut changed = Vec::new();
+        self.update_element_state(&full_id, |state| {
+            present = !class
+                .split_whitespace()
+                .all(|class| state.classes.iter().any(|c| c == class));
+            for class in class.split_whitespace() {
+                if present && !state.classes.iter().any(|c| c == class) {
+                    state.classes.push(class.to_string());
+                    changed.push(class.to_string());
+                } else if let Some(index) = state.classes.iter().position(|c| c == class).filter(|_| !present) {
+                    changed.push(state.classes.remove(index));
+                }
+            }
+            !changed.is_empty()
+        });
+        for class in changed {
+            let id = full_id.clone();
+            let message = if present {
+                ServerMessage::AddClass { id, class }
+            } else {
+                ServerMessage::RemoveClass { id, class }
+            };
+            let _ = self.update_tx.send(message);
+        }
+        present
+    }
+
+    /// Sets an inline CSS property on an element in all clients.
+    ///
+    /// `property` uses CSS syntax, e.g. `"background-color"`. An empty `value`
+    /// removes the property. Does nothing if `id` does not refer to an element.
+    ///
+    /// # Example
+    /// ```
+    /// # use webui::{AppState, UiElement};
+    /// # let state = AppState::new();
+    /// state.add_element(UiElement::Text {
+    ///     id: "status".to_string(),
+    ///     text: "Build failed".to_string(),
+    /// });
+    ///
+    /// state.set_style("status", "color", "red");
+    /// ```
+    pub fn set_style(&self, id: &str, property: &str, value: &str) {
+        let full_id = self.full_id(id);
+        let changed = self.update_element_state(&full_id, |state| {
+            if value.is_empty() {
+                state.style.remove(property).is_some()
+            } else {
+                state.style.insert(property.to_string(), value.to_string()).as_deref() != Some(value)
+            }
+        });
+        if changed {
+            let _ = self.update_tx.send(ServerMessage::Style {
+                id: full_id,
+                property: property.to_string(),
+                value: value.to_string(),
+            });
+        }
+    }
+
+    /// Sets the validators of an input-like element.
+    ///
+    /// Validators run on the server whenever a client changes the value, before the
+    /// element's handler is called. The first error is shown next to the element until
+    /// the value becomes valid. Handlers are still called for invalid values; use
+    /// [`is_valid`](Self::is_valid) before acting on them.
+    ///
+    /// # Example
+    /// ```
+    /// # use webui::{AppState, UiElement, Validator};
+    /// # let state = AppState::new();
+    /// state.add_element(UiElement::Input {
+    ///     id: "email".to_string(),
+    ///     value: String::new(),
+    ///     label: Some("Email".to_string()),
+    ///     placeholder: None,
+    ///     tooltip: None,
+    ///     on_input: None,
+    /// });
+    ///
+    /// state.set_validators("email", vec![
+    ///     Validator::Required,
+    ///     Validator::pattern(r"^[^@\s]+@[^@\s]+$", "Not an email address").unwrap(),
+    /// ]);
+    /// assert!(!state.is_valid());
+    /// ```
+    pub fn set_validators(&self, id: &str, validators: Vec<Validator>) {
+        let full_id = self.full_id(id);
+        let cleared = self.update_element_state(&full_id, |state| {
+            state.validators = validators;
+            state.error.take().is_some()
+        });
+        if cleared {
+            let _ = self.update_tx.send(ServerMessage::Validation { id: full_id, error: None });
+        }
+    }
+
+    /// Returns whether all enabled elements in this scope pass their validators.
+    ///
+    /// Values are checked as they are now, so elements the user has not changed yet
+    /// are included. Errors are not shown in clients.
+    pub fn is_valid(&self) -> bool {
+        let prefix = format!("{}.", self.scope_path);
+        let checks: Vec<(Vec<Validator>, serde_json::Value)> = {
+            let elements = self.elements.lock().unwrap();
+            let states = self.element_states.lock().unwrap();
+            states
+                .iter()
+                .filter(|(full_id, state)| {
+                    state.enabled
+                        && !state.validators.is_empty()
+                        && (self.scope_path.is_empty() || full_id.starts_with(&prefix))
+                })
+                .filter_map(|(full_id, state)| {
+                    let value = validated_value(&elements, full_id)?;
+                    Some((state.validators.clone(), value))
+                })
+                .collect()
+        };
+        // Custom validators run without holding the locks
+        checks.iter().all(|(validators, value)| validation_error(validators, value).is_none())
+    }
+
+    /// Runs the validators of an element on its current value and sends the error if it changed.
+    ///
+    /// Returns whether the value is valid.
+    fn validate_element(&self, full_id: &str) -> bool {
+        let Some(value) = validated_value(&self.elements.lock().unwrap(), full_id) else {
+            return true;
+        };
+        let validators = match self.element_states.lock().unwrap().get(full_id) {
+            Some(state) if !state.validators.is_empty() => state.validators.clone(),
+            _ => return true,
+        };
+        let error = validation_error(&validators, &value);
+        let valid = error.is_none();
+        {
+            let mut states = self.element_states.lock().unwrap();
+            let Some(state) = states.get_mut(full_id) else {
+                return valid;
+            };
+            if state.error == error {
+                return valid;
+            }
+            state.error = error.clone();
+        }
+        let _ = self.update_tx.send(ServerMessage::Validation { id: full_id.to_string(), error });
+        valid
+    }
+
+    /// Current values of all input-like elements, by full ID.
+    fn element_values(&self) -> HashMap<String, serde_json::Value> {
+        self.elements
+            .lock()
+            .unwrap()
+            .iter()
+            .filter_map(|(full_id, element)| Some((full_id.clone(), element.value()?)))
+            .collect()
+    }
+
+    /// Sends a stored element with its state to all clients.
+    fn send_element(&self, full_id: &str) {
+        let message = {
+            let elements = self.elements.lock().unwrap();
+            let Some(element) = elements.get(full_id) else {
+                return;
+            };
+            let mut element = element.clone();
+            *element.id_mut() = full_id.to_string();
+            let state = self.element_states.lock().unwrap().get(full_id).cloned().unwrap_or_default();
+            ServerMessage::Update {
+                id: full_id.to_string(),
+                element: Box::new(ClientElement { element, state }),
+            }
+        };
+        let _ = self.update_tx.send(message);
+    }
+
+    /// Stores an element, returning its state.
+    fn store_element(&self, full_id: &str, element: UiElement) -> ElementState {
+        let mut elements = self.elements.lock().unwrap();
+        elements.insert(full_id.to_string(), element);
+        self.element_states.lock().unwrap().get(full_id).cloned().unwrap_or_default()
+    }
+
+    /// Applies `update` to the common state of an existing element and returns its result.
+    fn update_element_state(&self, full_id: &str, update: impl FnOnce(&mut ElementState) -> bool) -> bool {
+        let elements = self.elements.lock().unwrap();
+        if !elements.contains_key(full_id) {
+            return false;
+        }
+        let mut states = self.element_states.lock().unwrap();
+        update(states.entry(full_id.to_string()).or_default())
+    }
+
+    fn is_enabled(&self, full_id: &str) -> bool {
+        self.element_states
+            .lock()
+            .unwrap()
+            .get(full_id)
+            .is_none_or(|state| state.enabled)
+    }
+
+    /// Replaces the option list of a [`UiElement::Select`] and broadcasts the change.
+    ///
+    /// The current selection is kept if its value is still among the new options,
+    /// otherwise it is cleared. Does nothing if `id` does not refer to a select element.
+    ///
+    /// # Example
+    /// ```
+    /// # use webui::{AppState, UiElement};
+    /// # let state = AppState::new();
+    /// state.add_element(UiElement::Select {
+    ///     id: "color".to_string(),
+    ///     options: vec![("red".to_string(), "Red".to_string())],
+    ///     selected: Some("red".to_string()),
+    ///     on_change: None,
+    /// });
+    ///
+    /// state.set_select_options(
+    ///     "color",
+    ///     vec![
+    ///         ("red".to_string(), "Red".to_string()),
+    ///         ("blue".to_string(), "Blue".to_string()),
+    ///     ],
+    /// );
+    /// ```
+    pub fn set_select_options(&self, id: &str, options: Vec<(String, String)>) {
+        let full_id = self.full_id(id);
+        let element = {
+            let mut elements = self.elements.lock().unwrap();
+            let Some(element) = elements.get_mut(&full_id) else {
+                return;
+            };
+            let UiElement::Select { options: current, selected, .. } = element else {
+                return;
+            };
+            if selected
+                .as_ref()
+                .is_some_and(|value| !options.iter().any(|(v, _)| v == value))
+            {
+                *selected = None;
+            }
+            *current = options;
+            element.clone()
+        };
+        self.update_element(id, element);
+    }
+
+    /// Replaces the props of a [`UiElement::Custom`] and broadcasts the change.
+    ///
+    /// Unlike [`AppState::update_element`], the element's event handler is kept.
+    /// Does nothing if `id` does not refer to a custom element.
+    ///
+    /// # Example
+    /// ```
+    /// # use webui::{AppState, UiElement};
+    /// # use serde_json::json;
+    /// # let state = AppState::new();
+    /// state.add_element(UiElement::Custom {
+    ///     id: "gauge".to_string(),
+    ///     kind: "gauge".to_string(),
+    ///     props: json!({ "value": 0 }),
+    ///     on_event: None,
+    /// });
+    ///
+    /// state.set_custom_props("gauge", json!({ "value": 75 }));
+    /// ```
+    pub fn set_custom_props(&self, id: &str, props: serde_json::Value) {
+        let full_id = self.full_id(id);
+        let element = {
+            let mut elements = self.elements.lock().unwrap();
+            let Some(element) = elements.get_mut(&full_id) else {
+                return;
+            };
+            let UiElement::Custom { props: current, .. } = element else {
+                return;
+            };
+            *current = props;
+            element.clone()
+        };
+        self.update_element(id, element);
+    }
+
+    /// Switches the visible tab of a [`UiElement::Tabs`] in all clients.
+    ///
+    /// The `on_change` handler is not called. Does nothing if `id` does not refer
+    /// to a tabs element or `tab` is not one of its tabs.
+    ///
+    /// # Example
+    /// ```
+    /// # use webui::{AppState, UiElement};
+    /// # let state = AppState::new();
+    /// state.add_element(UiElement::Tabs {
+    ///     id: "sections".to_string(),
+    ///     tabs: vec![
+    ///         ("general".to_string(), "General".to_string()),
+    ///         ("advanced".to_string(), "Advanced".to_string()),
+    ///     ],
+    ///     active: "general".to_string(),
+    ///     on_change: None,
+    /// });
+    ///
+    /// state.set_active_tab("sections", "advanced");
+    /// assert_eq!(state.active_tab("sections").as_deref(), Some("advanced"));
+    /// ```
+    pub fn set_active_tab(&self, id: &str, tab: &str) {
+        let full_id = self.full_id(id);
+        let element = {
+            let mut elements = self.elements.lock().unwrap();
+            let Some(element) = elements.get_mut(&full_id) else {
+                return;
+            };
+            let UiElement::Tabs { tabs, active, .. } = element else {
+                return;
+            };
+            if !tabs.iter().any(|(t, _)| t == tab) {
+                return;
+            }
+            *active = tab.to_string();
+            element.clone()
+        };
+        self.update_element(id, element);
+    }
+
+    /// Returns the ID of the visible tab of a [`UiElement::Tabs`].
+    ///
+    /// Returns `None` if `id` does not refer to a tabs element.
+    pub fn active_tab(&self, id: &str) -> Option<String> {
+        let elements = self.elements.lock().unwrap();
+        match elements.get(&self.full_id(id)) {
+            Some(UiElement::Tabs { active, .. }) => Some(active.clone()),
+            _ => None,
+        }
+    }
+
+    /// Appends a row to a [`UiElement::Table`] and sends only the new row to clients.
+    ///
+    /// Does nothing if `id` does not refer to a table element.
+    ///
+    /// # Example
+    /// ```
+    /// # use webui::{AppState, TableColumn, TableRow, UiElement};
+    /// # use serde_json::json;
+    /// # let state = AppState::new();
+    /// state.add_element(UiElement::Table {
+    ///     id: "jobs".to_string(),
+    ///     columns: vec![TableColumn {
+    ///         key: "name".to_string(),
+    ///         label: "Name".to_string(),
+    ///         sortable: true,
+    ///     }],
+    ///     rows: vec![],
+    ///     on_row_click: None,
+    ///     on_sort: None,
+    /// });
+    ///
+    /// state.append_table_row("jobs", TableRow {
+    ///     key: "job-1".to_string(),
+    ///     cells: vec![json!("Backup")],
+    /// });
+    /// ```
+    pub fn append_table_row(&self, id: &str, row: TableRow) {
+        let full_id = self.full_id(id);
+        {
+            let mut elements = self.elements.lock().unwrap();
+            let Some(UiElement::Table { rows, .. }) = elements.get_mut(&full_id) else {
+                return;
+            };
+            rows.push(row.clone());
+        }
+        let _ = self.update_tx.send(ServerMessage::TableAppend { id: full_id, row });
+    }
+
+    /// Replaces the row with the same key in a [`UiElement::Table`] and sends only that row to clients.
+    ///
+    /// Does nothing if `id` does not refer to a table element or no row has the given key.
+    pub fn update_table_row(&self, id: &str, row: TableRow) {
+        let full_id = self.full_id(id);
+        {
+            let mut elements = self.elements.lock().unwrap();
+            let Some(UiElement::Table { rows, .. }) = elements.get_mut(&full_id) else {
+                return;
+            };
+            let Some(existing) = rows.iter_mut().find(|r| r.key == row.key) else {
+                return;
+            };
+            *existing = row.clone();
+        }
+        let _ = self.update_tx.send(ServerMessage::TableUpdate { id: full_id, row });
+    }
+
+    /// Removes the row with the given key from a [`UiElement::Table`].
+    ///
+    /// Does nothing if `id` does not refer to a table element or no row has the given key.
+    pub fn delete_table_row(&self, id: &str, key: &str) {
+        let full_id = self.full_id(id);
+        {
+            let mut elements = self.elements.lock().unwrap();
+            let Some(UiElement::Table { rows, .. }) = elements.get_mut(&full_id) else {
+                return;
+            };
+            let len = rows.len();
+            rows.retain(|r| r.key != key);
+            if rows.len() == len {
+                return;
+            }
+        }
+        let _ = self.update_tx.send(ServerMessage::TableDelete {
+            id: full_id,
+            key: key.to_string(),
+        });
+    }
+
+    /// Inserts an item into a [`UiElement::List`] and sends only the new item to clients.
+    ///
+    /// An `index` past the end of the list appends the item. Does nothing if `id`
+    /// does not refer to a list element or an item with the same key exists.
+    ///
+    /// # Example
+    /// ```
+    /// # use webui::{AppState, ListItem, UiElement};
+    /// # use serde_json::json;
+    /// # let state = AppState::new();
+    /// state.add_element(UiElement::List {
+    ///     id: "queue".to_string(),
+    ///     items: vec![],
+    ///     on_item_click: None,
+    /// });
+    ///
+    /// state.insert_list_item("queue", 0, ListItem {
+    ///     key: "job-1".to_string(),
+    ///     value: json!({ "name": "Backup", "status": "queued" }),
+    /// });
+    /// state.move_list_item("queue", "job-1", 0);
+    /// state.remove_list_item("queue", "job-1");
+    /// ```
+    pub fn insert_list_item(&self, id: &str, index: usize, item: ListItem) {
+        let full_id = self.full_id(id);
+        let index = {
+            let mut elements = self.elements.lock().unwrap();
+            let Some(UiElement::List { items, .. }) = elements.get_mut(&full_id) else {
+                return;
+            };
+            // Keys identify items in clients, so they must stay unique
+            if items.iter().any(|existing| existing.key == item.key) {
+                return;
+            }
+            let index = index.min(items.len());
+            items.insert(index, item.clone());
+            index
+        };
+        let _ = self.update_tx.send(ServerMessage::ListInsert { id: full_id, index, item });
+    }
+
+    /// Removes the item with the given key from a [`UiElement::List`].
+    ///
+    /// Does nothing if `id` does not refer to a list element or no item has the given key.
+    pub fn remove_list_item(&self, id: &str, key: &str) {
+        let full_id = self.full_id(id);
+        {
+            let mut elements = self.elements.lock().unwrap();
+            let Some(UiElement::List { items, .. }) = elements.get_mut(&full_id) else {
+                return;
+            };
+            let Some(position) = items.iter().position(|item| item.key == key) else {
+                return;
+            };
+            items.remove(position);
+        }
+        let _ = self.update_tx.send(ServerMessage::ListRemove {
+            id: full_id,
+            key: key.to_string(),
+        });
+    }
+
+    /// Moves the item with the given key to a new position in a [`UiElement::List`].
+    ///
+    /// `index` is the position after the item has been taken out of the list; an
+    /// index past the end moves the item to the end.
+    /// Does nothing if `id` does not refer to a list element or no item has the given key.
+    pub fn move_list_item(&self, id: &str, key: &str, index: usize) {
+        let full_id = self.full_id(id);
+        let index = {
+            let mut elements = self.elements.lock().unwrap();
+            let Some(UiElement::List { items, .. }) = elements.get_mut(&full_id) else {
+                return;
+            };
+            let Some(position) = items.iter().position(|item| item.key == key) else {
+                return;
+            };
+            let item = items.remove(position);
+            let index = index.min(items.len());
+            items.insert(index, item);
+            index
+        };
+        let _ = self.update_tx.send(ServerMessage::ListMove {
+            id: full_id,
+            key: key.to_string(),
+            index,
+        });
+    }
+
+    /// Sets the value of a [`UiElement::Progress`], sending only the new value to clients.
+    ///
+    /// `None` switches the progress bar to indeterminate mode. Setting the current
+    /// value again sends nothing, so this is cheap to call many times per second.
+    /// Does nothing if `id` does not refer to a progress element.
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use webui::{AppState, UiElement};
+    /// # async fn example(state: AppState) {
+    /// state.add_element(UiElement::Progress {
+    ///     id: "job".to_string(),
+    ///     value: Some(0.0),
+    ///     max: 100.0,
+    /// });
+    ///
+    /// let state_for_task = state.clone();
+    /// tokio::spawn(async move {
+    ///     for i in 0..=100 {
+    ///         state_for_task.set_progress("job", Some(i as f64));
+    ///         tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+    ///     }
+    /// });
+    /// # }
+    /// ```
+    pub fn set_progress(&self, id: &str, value: Option<f64>) {
+        let full_id = self.full_id(id);
+        {
+            let mut elements = self.elements.lock().unwrap();
+            let Some(UiElement::Progress { value: current, .. }) = elements.get_mut(&full_id) else {
+                return;
+            };
+            if *current == value {
+                return;
+            }
+            *current = value;
+        }
+        let _ = self.update_tx.send(ServerMessage::Value { id: full_id, value });
+    }
+
+    /// Sets the value of a [`UiElement::Meter`], sending only the new value to clients.
+    ///
+    /// Setting the current value again sends nothing.
+    /// Does nothing if `id` does not refer to a meter element.
+    pub fn set_meter(&self, id: &str, value: f64) {
+        let full_id = self.full_id(id);
+        {
+            let mut elements = self.elements.lock().unwrap();
+            let Some(UiElement::Meter { value: current, .. }) = elements.get_mut(&full_id) else {
+                return;
+            };
+            if *current == value {
+                return;
+            }
+            *current = value;
+        }
+        let _ = self.update_tx.send(ServerMessage::Value {
+            id: full_id,
+            value: Some(value),
+        });
+    }
+
+    /// Appends drawing commands to a [`UiElement::Canvas`] and sends only the new commands to clients.
+    ///
+    /// A [`DrawCommand::Clear`] discards all previously stored commands, so canvases that
+    /// are redrawn periodically do not grow without bound.
+    /// Does nothing if `id` does not refer to a canvas element.
+    ///
+    /// # Example
+    /// ```
+    /// # use webui::{AppState, DrawCommand, UiElement};
+    /// # let state = AppState::new();
+    /// state.add_element(UiElement::Canvas {
+    ///     id: "plot".to_string(),
+    ///     width: 300,
+    ///     height: 200,
+    ///     commands: vec![],
+    ///     on_pointer: None,
+    /// });
+    ///
+    /// state.draw("plot", vec![
+    ///     DrawCommand::StrokeStyle { color: "#2196f3".to_string() },
+    ///     DrawCommand::Line { x1: 0.0, y1: 100.0, x2: 300.0, y2: 50.0 },
+    /// ]);
+    /// ```
+    pub fn draw(&self, id: &str, commands: Vec<DrawCommand>) {
+        let full_id = self.full_id(id);
+        {
+            let mut elements = self.elements.lock().unwrap();
+            let Some(UiElement::Canvas { commands: current, .. }) = elements.get_mut(&full_id) else {
+                return;
+            };
+            if let Some(last_clear) = commands.iter().rposition(|c| *c == DrawCommand::Clear) {
+                current.clear();
+                current.extend_from_slice(&commands[last_clear..]);
+            } else {
+                current.extend_from_slice(&commands);
+            }
+        }
+        let _ = self.update_tx.send(ServerMessage::CanvasDraw { id: full_id, commands });
+    }
+
+    /// Appends points to a series of a [`UiElement::Chart`] and sends only the new points to clients.
+    ///
+    /// If the chart has `max_points` set, the oldest points of the series are dropped
+    /// to stay within the window.
+    /// Does nothing if `id` does not refer to a chart element or it has no such series.
+    ///
+    /// # Example
+    /// ```
+    /// # use webui::{AppState, ChartSeries, ChartType, UiElement};
+    /// # let state = AppState::new();
+    /// state.add_element(UiElement::Chart {
+    ///     id: "cpu".to_string(),
+    ///     chart_type: ChartType::Line,
+    ///     series: vec![ChartSeries {
+    ///         name: "load".to_string(),
+    ///         color: None,
+    ///         points: vec![],
+    ///     }],
+    ///     x_label: Some("seconds".to_string()),
+    ///     y_label: Some("%".to_string()),
+    ///     max_points: Some(60),
+    /// });
+    ///
+    /// state.append_chart_points("cpu", "load", vec![(0.0, 12.5), (1.0, 20.0)]);
+    /// ```
+    pub fn append_chart_points(&self, id: &str, series: &str, points: Vec<(f64, f64)>) {
+        let full_id = self.full_id(id);
+        {
+            let mut elements = self.elements.lock().unwrap();
+            let Some(UiElement::Chart { series: all_series, max_points, .. }) = elements.get_mut(&full_id) else {
+                return;
+            };
+            let Some(target) = all_series.iter_mut().find(|s| s.name == series) else {
+                return;
+            };
+            target.points.extend_from_slice(&points);
+            if let Some(max_points) = *max_points
+                && target.points.len() > max_points
+            {
+                let excess = target.points.len() - max_points;
+                target.points.drain(..excess);
+            }
+        }
+        let _ = self.update_tx.send(ServerMessage::ChartAppend {
+            id: full_id,
+            series: series.to_string(),
+            points,
+        });
+    }
+
+    /// Appends output to a [`UiElement::Log`] and sends only the new lines to clients.
+    ///
+    /// Text containing newlines is split into several lines of the same level; a single
+    /// trailing newline is ignored. Lines beyond the log's `max_lines` are dropped,
+    /// oldest first. Does nothing if `id` does not refer to a log element.
+    ///
+    /// # Example
+    /// ```
+    /// # use webui::{AppState, LogLevel, LogLine, UiElement};
+    /// # let state = AppState::new();
+    /// state.add_element(UiElement::Log {
+    ///     id: "build".to_string(),
+    ///     lines: vec![],
+    ///     max_lines: 1000,
+    /// });
+    ///
+    /// state.append_log("build", "Compiling webui v0.1.0");
+    /// state.append_log("build", LogLine::new(LogLevel::Error, "error[E0308]: mismatched types"));
+    /// ```
+    pub fn append_log(&self, id: &str, line: impl Into<LogLine>) {
+        let line = line.into();
+        let text = line.text.strip_suffix('\n').unwrap_or(&line.text);
+        let new_lines: Vec<LogLine> = text
+            .split('\n')
+            .map(|text| LogLine::new(line.level, text.strip_suffix('\r').unwrap_or(text)))
+            .collect();
+
+        let full_id = self.full_id(id);
+        {
+            let mut elements = self.elements.lock().unwrap();
+            let Some(UiElement::Log { lines, max_lines, .. }) = elements.get_mut(&full_id) else {
+                return;
+            };
+            lines.extend_from_slice(&new_lines);
+            if lines.len() > *max_lines {
+                let excess = lines.len() - *max_lines;
+                lines.drain(..excess);
+            }
+        }
+        let _ = self.update_tx.send(ServerMessage::LogAppend {
+            id: full_id,
+            lines: new_lines,
+        });
+    }
+
+    /// Replaces a node of a [`UiElement::Tree`] and sends only that node to clients.
+    ///
+    /// The node with the same ID is replaced together with its children, wherever
+    /// it is in the tree. Does nothing if `id` does not refer to a tree element or
+    /// no node has that ID.
+    ///
+    /// # Example
+    /// ```
+    /// # use webui::{AppState, TreeNode, UiElement};
+    /// # let state = AppState::new();
+    /// state.add_element(UiElement::Tree {
+    ///     id: "files".to_string(),
+    ///     nodes: vec![TreeNode::branch("/home", "home")],
+    ///     selected: None,
+    ///     on_expand: None,
+    ///     on_select: None,
+    /// });
+    ///
+    /// let mut home = TreeNode::branch("/home", "home (2 items)");
+    /// home.expanded = true;
+    /// home.children = Some(vec![
+    ///     TreeNode::leaf("/home/a.txt", "a.txt"),
+    ///     TreeNode::leaf("/home/b.txt", "b.txt"),
+    /// ]);
+    /// state.update_tree_node("files", home);
+    /// ```
+    pub fn update_tree_node(&self, id: &str, node: TreeNode) {
+        let full_id = self.full_id(id);
+        {
+            let mut elements = self.elements.lock().unwrap();
+            let Some(UiElement::Tree { nodes, .. }) = elements.get_mut(&full_id) else {
+                return;
+            };
+            let Some(existing) = find_tree_node_mut(nodes, &node.id) else {
+                return;
+            };
+            *existing = node.clone();
+        }
+        let _ = self.update_tx.send(ServerMessage::TreeNode { id: full_id, node });
+    }
+
+    /// Returns the browser whose event is being handled.
+    ///
+    /// Returns `None` outside event handlers. Tasks spawned by a handler do not
+    /// inherit the client, so get it before spawning.
+    pub fn current_client(&self) -> Option<ClientHandle> {
+        CURRENT_CLIENT.try_with(ClientHandle::clone).ok()
+    }
+
+    /// Sends a file as a download to the browser whose event is being handled.
+    ///
+    /// The data is registered under a one-time URL served by the router at
+    /// `/download/{token}`, and the browser is told to start the download. Each
+    /// download can be fetched once and expires after 60 seconds.
+    ///
+    /// Returns `false` without registering the file when called outside an event
+    /// handler; use [`download_to`](Self::download_to) with a client from
+    /// [`current_client`](Self::current_client) there.
+    ///
+    /// # Example
+    /// ```
+    /// # use std::sync::Arc;
+    /// # use webui::{AppState, UiElement};
+    /// # let state = AppState::new();
+    /// let state_for_export = state.clone();
+    /// state.add_element(UiElement::Button {
+    ///     id: "export".to_string(),
+    ///     text: "Export".to_string(),
+    ///     on_click: Some(Arc::new(Box::new(move || {
+    ///         let csv = "name,count\napples,3\n";
+    ///         state_for_export.download(csv, "report.csv", "text/csv");
+    ///     }))),
+    /// });
+    /// ```
+    pub fn download(
+        &self,
+        data: impl Into<Vec<u8>>,
+        file_name: impl Into<String>,
+        mime_type: impl Into<String>,
+    ) -> bool {
+        let Some(client) = self.current_client() else {
+            return false;
+        };
+        self.download_to(&client, data, file_name, mime_type);
+        true
+    }
+
+    /// Sends a file to one browser as a download, see [`download`](Self::download).
+    ///
+    /// # Example
+    /// ```
+    /// # use std::sync::Arc;
+    /// # use webui::{AppState, UiElement};
+    /// # let state = AppState::new();
+    /// let state_for_export = state.clone();
+    /// state.add_element(UiElement::Button {
+    ///     id: "export".to_string(),
+    ///     text: "Export".to_string(),
+    ///     on_click: Some(Arc::new(Box::new(move || {
+    ///         let state = state_for_export.clone();
+    ///         let Some(client) = state.current_client() else {
+    ///             return;
+    ///         };
+    ///         tokio::spawn(async move {
+    ///             let report = "slow,report\n".to_string();
+    ///             state.download_to(&client, report, "report.csv", "text/csv");
+    ///         });
+    ///     }))),
+    /// });
+    /// ```
+    pub fn download_to(
+        &self,
+        client: &ClientHandle,
+        data: impl Into<Vec<u8>>,
+        file_name: impl Into<String>,
+        mime_type: impl Into<String>,
+    ) {
+        let message = self.register_download(data.into(), file_name.into(), mime_type.into());
+        let _ = client.reply_tx.send(message);
+    }
+
+    /// Registers a download and returns the message that starts it in a browser.
+    fn register_download(&self, data: Vec<u8>, file_name: String, mime_type: String) -> ServerMessage {
+        let now = Instant::now();
+        let token = download_token();
+        {
+            let mut downloads = self.downloads.lock().unwrap();
+            downloads.retain(|_, download| now.duration_since(download.created) < DOWNLOAD_TTL);
+            downloads.insert(token.clone(), PendingDownload {
+                data,
+                file_name: file_name.clone(),
+                mime_type,
+                created: now,
+            });
+        }
+
+        ServerMessage::Download {
+            url: format!("/download/{}", token),
+            file_name,
+        }
+    }
+
+    /// Removes and returns a download if it has not expired, dropping expired entries.
+    fn take_download(&self, token: &str, now: Instant) -> Option<PendingDownload> {
+        let mut downloads = self.downloads.lock().unwrap();
+        downloads.retain(|_, download| now.duration_since(download.created) < DOWNLOAD_TTL);
+        downloads.remove(token)
+    }
+
+    /// Asks the user to confirm something and waits for the answer.
+    ///
+    /// Shows a modal dialog with OK and Cancel buttons in all connected browsers;
+    /// the first answer closes it everywhere. Resolves to `false` if the user cancels,
+    /// no browser is connected, the last browser disconnects and none reconnects within
+    /// a few seconds, or no answer arrives within [`DIALOG_TIMEOUT`]. Dropping the
+    /// future closes the dialog.
+    ///
+    /// Event handlers run on the connection's message loop, so spawn a task to
+    /// await the answer instead of blocking in the handler.
+    ///
+    /// # Example
+    /// ```
+    /// # use webui::{AppState, UiElement};
+    /// # use std::sync::Arc;
+    /// # let state = AppState::new();
+    /// let state_for_btn = state.clone();
+    /// state.add_element(UiElement::Button {
+    ///     id: "delete".to_string(),
+    ///     text: "Delete All".to_string(),
+    ///     on_click: Some(Arc::new(Box::new(move || {
+    ///         let state = state_for_btn.clone();
+    ///         tokio::spawn(async move {
+    ///             if state.confirm("Delete all?", "This cannot be undone.").await {
+    ///                 println!("Deleting...");
+    ///             }
+    ///         });
+    ///     }))),
+    /// });
+    /// ```
+    pub async fn confirm(&self, title: impl Into<String>, message: impl Into<String>) -> bool {
+        self.show_dialog(DialogType::Confirm, title.into(), message.into(), None)
+            .await
+            .and_then(|value| value.as_bool())
+            .unwrap_or(false)
+    }
+
+    /// Asks the user to enter a line of text and waits for the answer.
+    ///
+    /// Shows a modal dialog with a text field prefilled with `default_value`. Resolves
+    /// to `None` in the same cases where [`AppState::confirm`] resolves to `false`.
+    pub async fn prompt(
+        &self,
+        title: impl Into<String>,
+        message: impl Into<String>,
+        default_value: impl Into<String>,
+    ) -> Option<String> {
+        self.show_dialog(
+            DialogType::Prompt,
+            title.into(),
+            message.into(),
+            Some(default_value.into()),
+        )
+        .await
+        .and_then(|value| value.as_str().map(str::to_string))
+    }
+
+    async fn show_dialog(
+        &self,
+        dialog_type: DialogType,
+        title: String,
+        message: String,
+        default_value: Option<String>,
+    ) -> Option<serde_json::Value> {
+        static NEXT_DIALOG_ID: AtomicU64 = AtomicU64::new(1);
+
+        if self.clients.load(Ordering::SeqCst) == 0 {
+            return None;
+        }
+
+        let dialog_id = NEXT_DIALOG_ID.fetch_add(1, Ordering::Relaxed);
+        let message = ServerMessage::Dialog {
+            dialog_id,
+            dialog_type,
+            title,
+            message,
+            default_value,
+        };
+        let (responder, response) = oneshot::channel();
+        self.dialogs.lock().unwrap().insert(dialog_id, PendingDialog {
+            message: message.clone(),
+            responder,
+        });
+        let _guard = DialogGuard {
+            state: self.clone(),
+            dialog_id,
+        };
+        let _ = self.update_tx.send(message);
+
+        match tokio::time::timeout(DIALOG_TIMEOUT, response).await {
+            Ok(Ok(value)) => Some(value),
+            // Timed out, or cancelled because all clients disconnected
+            _ => None,
+        }
+    }
+
+    /// Shows a toast notification in all connected browsers.
+    ///
+    /// The notification disappears after `duration`, or stays until dismissed if `None`.
+    ///
+    /// # Example
+    /// ```
+    /// # use webui::{AppState, NotificationLevel};
+    /// # use std::time::Duration;
+    /// # let state = AppState::new();
+    /// state.notify(NotificationLevel::Success, "Settings saved", Some(Duration::from_secs(3)));
+    /// ```
+    pub fn notify(&self, level: NotificationLevel, message: impl Into<String>, duration: Option<Duration>) {
+        self.notify_with_actions(level, message, duration, Vec::new());
+    }
+
+    /// Shows a toast notification with action buttons in all connected browsers.
+    ///
+    /// Clicking an action calls its handler and closes the notification everywhere.
+    /// At most 50 notifications with actions stay open; showing more closes the
+    /// oldest ones. Actions are ignored if no browser is connected.
+    ///
+    /// # Example
+    /// ```
+    /// # use webui::{AppState, NotificationAction, NotificationLevel};
+    /// # use std::sync::Arc;
+    /// # let state = AppState::new();
+    /// state.notify_with_actions(
+    ///     NotificationLevel::Warning,
+    ///     "File deleted",
+    ///     None,
+    ///     vec![NotificationAction {
+    ///         label: "Undo".to_string(),
+    ///         on_click: Some(Arc::new(Box::new(|| println!("Restoring file")))),
+    ///     }],
+    /// );
+    /// ```
+    pub fn notify_with_actions(
+        &self,
+        level: NotificationLevel,
+        message: impl Into<String>,
+        duration: Option<Duration>,
+        actions: Vec<NotificationAction>,
+    ) {
+        static NEXT_NOTIFICATION_ID: AtomicU64 = AtomicU64::new(1);
+
+        let notification_id = NEXT_NOTIFICATION_ID.fetch_add(1, Ordering::Relaxed);
+        let labels = actions.iter().map(|action| action.label.clone()).collect();
+        let mut closed = Vec::new();
+        // Nobody can answer when no browser is connected, so don't keep the handlers
+        if !actions.is_empty() && self.clients.load(Ordering::SeqCst) > 0 {
+            let now = Instant::now();
+            let mut notifications = self.notifications.lock().unwrap();
+            notifications.retain(|_, n| n.expires.is_none_or(|expires| expires > now));
+            notifications.insert(notification_id, PendingNotification {
+                actions: actions.into_iter().map(|action| action.on_click).collect(),
+                expires: duration.map(|duration| now + duration),
+            });
+            while notifications.len() > MAX_PENDING_NOTIFICATIONS {
+                let oldest = *notifications.keys().min().unwrap();
+                notifications.remove(&oldest);
+                closed.push(oldest);
+            }
+        }
+        for notification_id in closed {
+            let _ = self.update_tx.send(ServerMessage::NotificationClose { notification_id });
+        }
+        let _ = self.update_tx.send(ServerMessage::Notify {
+            notification_id,
+            level,
+            message: message.into(),
+            duration_ms: duration.map(|duration| duration.as_millis() as u64),
+            actions: labels,
+        });
+    }
+
+    /// Gets all UI elements.
+    ///
+    /// Returns a vector of cloned elements. Used internally when initializing new clients.
+    pub fn get_all_elements(&self) -> Vec<UiElement> {
+        self.elements.lock().unwrap().values().cloned().collect()
+    }
+
+    /// Gets all UI elements with their full scoped IDs for sending to clients.
+    ///
+    /// Returns elements with IDs rewritten to include scope paths,
+    /// matching what the client-side JavaScript has after auto-rewriting.
+    fn get_all_elements_for_client(&self) -> Vec<ClientElement> {
+        let elements = self.elements.lock().unwrap();
+        let states = self.element_states.lock().unwrap();
+        elements
+            .iter()
+            .map(|(full_id, element)| {
+                // Clone the element and rewrite its ID to the full scoped ID
+                let mut element = element.clone();
+                *element.id_mut() = full_id.clone();
+                ClientElement {
+                    element,
+                    state: states.get(full_id).cloned().unwrap_or_default(),
+                }
+            })
+            .collect()
+    }
+
+    /// Dispatches a message received on one client connection.
+    ///
+    /// Events for disabled elements are ignored.
+    fn handle_message(
+        &self,
+        msg: ClientMessage,
+        reply_tx: &mpsc::UnboundedSender<ServerMessage>,
+        pending_suggestions: &mut PendingSuggestions,
+    ) {
+        if msg.element_id().is_some_and(|id| !self.is_enabled(id)) {
+            return;
+        }
+        match msg {
+            ClientMessage::Click { id } => {
+                self.handle_click(&id);
+            }
+            ClientMessage::Input { id, value } => {
+                self.handle_input(&id, &value);
+            }
+            ClientMessage::Change { id, value } => {
+                self.handle_change(&id, value);
+            }
+            ClientMessage::Table { id, event } => {
+                self.handle_table_event(&id, event);
+            }
+            ClientMessage::Tree { id, event } => {
+                self.handle_tree_event(&id, event);
+            }
+            ClientMessage::LinkClick { id, href } => {
+                self.handle_link_click(&id, &href);
+            }
+            ClientMessage::CustomEvent { id, payload } => {
+                self.handle_custom_event(&id, payload);
+            }
+            ClientMessage::Submit { id, values } => {
+                self.handle_submit(&id, values);
+            }
+            ClientMessage::Suggest { id, query, request_id } => {
+                self.handle_suggest(&id, query, request_id, reply_tx, pending_suggestions);
+            }
+            ClientMessage::ItemClick { id, key } => {
+                self.handle_item_click(&id, &key);
+            }
+            ClientMessage::Pointer { id, kind, x, y } => {
+                self.handle_pointer(&id, PointerEvent { kind, x, y });
+            }
+            ClientMessage::DialogResponse { dialog_id, value } => {
+                self.handle_dialog_response(dialog_id, value);
+            }
+            ClientMessage::Notification { notification_id, action } => {
+                self.handle_notification(notification_id, action);
+            }
+        }
+    }
+
+    fn handle_click(&self, id: &str) {
+        let handler = {
+            let elements = self.elements.lock().unwrap();
+            if let Some(UiElement::Button { on_click: Some(handler), .. }) = elements.get(id) {
+                Some(handler.clone())
+            } else {
+                None
+            }
+        };
+        if let Some(handler) = handler {
+            handler();
+        }
+    }
+
+    fn handle_input(&self, id: &str, value: &str) {
+        let handler = {
+            let mut elements = self.elements.lock().unwrap();
+            if let Some(
+                UiElement::Input { value: current, on_input, .. }
+                | UiElement::TextArea { value: current, on_input, .. },
+            ) = elements.get_mut(id)
+            {
+                // Keep the stored value in sync for validation
+                *current = value.to_string();
+                on_input.clone()
+            } else {
+                None
+            }
+        };
+        self.validate_element(id);
+        if let Some(handler) = handler {
+            handler(value);
+        }
+    }
+
+    fn handle_change(&self, id: &str, value: serde_json::Value) {
+        #[allow(clippy::type_complexity)]
+        enum HandlerCall {
+            Bool(Arc<Box<dyn Fn(bool) + Send + Sync + 'static>>, bool),
+            Number(Arc<Box<dyn Fn(f64) + Send + Sync + 'static>>, f64),
+            String(Arc<Box<dyn Fn(&str) + Send + Sync + 'static>>, String),
+            DateTime(Arc<Box<dyn Fn(Option<DateTimeValue>) + Send + Sync + 'static>>, Option<DateTimeValue>),
+        }
+
+        let (handler_call, siblings) = {
+            let mut elements = self.elements.lock().unwrap();
+            let handler_call = match elements.get_mut(id) {
+                Some(UiElement::Tabs { tabs, active, on_change, .. }) => match value.as_str() {
+                    Some(value) if tabs.iter().any(|(tab, _)| tab == value) => {
+                        *active = value.to_string();
+                        on_change
+                            .as_ref()
+                            .map(|handler| HandlerCall::String(handler.clone(), value.to_string()))
+                    }
+                    _ => None,
+                },
+                // Stored values are kept in sync for validation
+                Some(element) => {
+                    if element.set_value(&value) {
+                        match element {
+                            UiElement::Checkbox { checked, on_change: Some(handler), .. }
+                            | UiElement::Radio { checked, on_change: Some(handler), .. } => {
+                                Some(HandlerCall::Bool(handler.clone(), *checked))
+                            }
+                            UiElement::Slider { value, on_change: Some(handler), .. }
+                            | UiElement::NumberInput { value, on_change: Some(handler), .. } => {
+                                Some(HandlerCall::Number(handler.clone(), *value))
+                            }
+                            UiElement::Select { selected: Some(selected), on_change: Some(handler), .. } => {
+                                Some(HandlerCall::String(handler.clone(), selected.clone()))
+                            }
+                            UiElement::DateTime { value, on_change: Some(handler), .. } => {
+                                Some(HandlerCall::DateTime(handler.clone(), *value))
+                            }
+                            UiElement::Color { value, on_change: Some(handler), .. }
+                            | UiElement::Combobox { value, on_select: Some(handler), .. } => {
+                                Some(HandlerCall::String(handler.clone(), value.clone()))
+                            }
+                            _ => None,
+                        }
+                    } else {
+                        None
+                    }
+                }
+                None => None,
+            };
+            let siblings = uncheck_radio_siblings(&mut elements, id);
+            (handler_call, siblings)
+        };
+
+        // Radio validators check the whole group, so the siblings may have become valid
+        self.validate_element(id);
+        for sibling in siblings {
+            self.validate_element(&sibling);
+        }
+        if let Some(handler_call) = handler_call {
+            match handler_call {
+                HandlerCall::Bool(handler, value) => handler(value),
+                HandlerCall::Number(handler, value) => handler(value),
+                HandlerCall::String(handler, value) => handler(&value),
+                HandlerCall::DateTime(handler, value) => handler(value),
+            }
+        }
+    }
+
+    fn handle_item_click(&self, id: &str, key: &str) {
+        let handler = {
+            let elements = self.elements.lock().unwrap();
+            if let Some(UiElement::List { on_item_click: Some(handler), .. }) = elements.get(id) {
+                Some(handler.clone())
+            } else {
+                None
+            }
+        };
+        if let Some(handler) = handler {
+            handler(key);
+        }
+    }
+
+    /// Runs the suggestion provider of a [`UiElement::Combobox`] for one connection.
+    ///
+    /// `pending` holds the running request of each combobox on the connection; it is
+    /// aborted when a newer query arrives. Results are sent to `reply_tx` only.
+    fn handle_suggest(
+        &self,
+        id: &str,
+        query: String,
+        request_id: u64,
+        reply_tx: &mpsc::UnboundedSender<ServerMessage>,
+        pending: &mut PendingSuggestions,
+    ) {
+        if let Some(stale) = pending.0.remove(id) {
+            stale.abort();
+        }
+        let provider = {
+            let elements = self.elements.lock().unwrap();
+            if let Some(UiElement::Combobox { on_suggest: Some(provider), .. }) = elements.get(id) {
+                Some(provider.clone())
+            } else {
+                None
+            }
+        };
+        let Some(provider) = provider else {
+            return;
+        };
+
+        let id = id.to_string();
+        let reply_tx = reply_tx.clone();
+        let task = tokio::spawn({
+            let id = id.clone();
+            async move {
+                let suggestions = provider(query).await;
+                let _ = reply_tx.send(ServerMessage::Suggestions { id, request_id, suggestions });
+            }
+        });
+        pending.0.insert(id, task.abort_handle());
+    }
+
+    fn handle_custom_event(&self, id: &str, payload: serde_json::Value) {
+        let handler = {
+            let elements = self.elements.lock().unwrap();
+            if let Some(UiElement::Custom { on_event: Some(handler), .. }) = elements.get(id) {
+                Some(handler.clone())
+            } else {
+                None
+            }
+        };
+        if let Some(handler) = handler {
+            handler(payload);
+        }
+    }
+
+    fn handle_submit(&self, id: &str, submitted: HashMap<String, serde_json::Value>) {
+        let prefix = format!("{}.", id);
+        if !matches!(self.elements.lock().unwrap().get(id), Some(UiElement::Form { .. })) {
+            return;
+        }
+
+        // Take over what the user sees, as the client may not have reported every change
+        // yet. Changes go through the same path as change events so that handlers run.
+        let values_before = self.element_values();
+        let mut keys: Vec<&String> = submitted.keys().collect();
+        keys.sort();
+        for key in keys {
+            let value = &submitted[key];
+            let full_id = format!("{}{}", prefix, key);
+            let (changed, is_text) = match self.elements.lock().unwrap().get(&full_id) {
+                Some(element) => {
+                    let mut updated = element.clone();
+                    let changed = updated.set_value(value) && updated.value() != element.value();
+                    (changed, matches!(element, UiElement::Input { .. } | UiElement::TextArea { .. }))
+                }
+                None => (false, false),
+            };
+            if !changed || !self.is_enabled(&full_id) {
+                continue;
+            }
+            match value.as_str() {
+                Some(text) if is_text => self.handle_input(&full_id, text),
+                _ => self.handle_change(&full_id, value.clone()),
+            }
+        }
+
+        // Other clients show the submitted values too
+        for (full_id, value) in self.element_values() {
+            if values_before.get(&full_id) != Some(&value) {
+                self.send_element(&full_id);
+            }
+        }
+
+        let (handler, full_ids) = {
+            let elements = self.elements.lock().unwrap();
+            let Some(UiElement::Form { on_submit, .. }) = elements.get(id) else {
+                return;
+            };
+            let handler = on_submit.clone();
+
+            let full_ids: Vec<String> = elements
+                .iter()
+                .filter(|(full_id, element)| {
+                    full_id.starts_with(&prefix) && element.value().is_some() && self.is_enabled(full_id)
+                })
+                .map(|(full_id, _)| full_id.clone())
+                .collect();
+            (handler, full_ids)
+        };
+
+        // Validate every element so that all errors are shown
+        let results: Vec<bool> = full_ids.iter().map(|full_id| self.validate_element(full_id)).collect();
+        let valid = results.into_iter().all(|valid| valid);
+        let Some(handler) = handler.filter(|_| valid) else {
+            return;
+        };
+
+        let mut values = HashMap::new();
+        {
+            let elements = self.elements.lock().unwrap();
+            for full_id in &full_ids {
+                match elements.get(full_id) {
+                    // A radio group is reported under its name, with the value of its checked button
+                    Some(UiElement::Radio { name, value, checked, .. }) => {
+                        let entry = values.entry(name.clone()).or_insert(serde_json::Value::Null);
+                        if *checked {
+                            *entry = serde_json::Value::from(value.as_str());
+                        }
+                    }
+                    Some(element) => {
+                        if let Some(value) = element.value() {
+                            values.insert(full_id[prefix.len()..].to_string(), value);
+                        }
+                    }
+                    None => {}
+                }
+            }
+        }
+        handler(values);
+    }
+
+    fn handle_link_click(&self, id: &str, href: &str) {
+        let handler = {
+            let elements = self.elements.lock().unwrap();
+            if let Some(UiElement::Markdown { on_link_click: Some(handler), .. }) = elements.get(id) {
+                Some(handler.clone())
+            } else {
+                None
+            }
+        };
+        if let Some(handler) = handler {
+            handler(href);
+        }
+    }
+
+    fn handle_tree_event(&self, id: &str, event: TreeEvent) {
+        match event {
+            TreeEvent::Toggle { node: node_id, expanded } => {
+                let (node, loader) = {
+                    let mut elements = self.elements.lock().unwrap();
+                    let Some(UiElement::Tree { nodes, on_expand, .. }) = elements.get_mut(id) else {
+                        return;
+                    };
+                    let Some(node) = find_tree_node_mut(nodes, &node_id) else {
+                        return;
+                    };
+                    node.expanded = expanded;
+                    let needs_children = expanded && node.has_children && node.children.is_none();
+                    (node.clone(), on_expand.clone().filter(|_| needs_children))
+                };
+                let _ = self.update_tx.send(ServerMessage::TreeNode { id: id.to_string(), node });
+
+                // Children are loaded in the background so the connection keeps
+                // processing events while the loader runs. A node expanded again
+                // before its children arrive is loaded only once.
+                let load = (id.to_string(), node_id.clone());
+                if let Some(loader) = loader
+                    && self.tree_loads.lock().unwrap().insert(load.clone())
+                {
+                    let guard = TreeLoadGuard {
+                        loads: self.tree_loads.clone(),
+                        load,
+                    };
+                    let state = self.clone();
+                    let id = id.to_string();
+                    tokio::spawn(async move {
+                        let children = loader(node_id.clone()).await;
+                        drop(guard);
+                        let node = {
+                            let mut elements = state.elements.lock().unwrap();
+                            let Some(UiElement::Tree { nodes, .. }) = elements.get_mut(&id) else {
+                                return;
+                            };
+                            let Some(node) = find_tree_node_mut(nodes, &node_id) else {
+                                return;
+                            };
+                            node.has_children = !children.is_empty();
+                            node.children = Some(children);
+                            node.clone()
+                        };
+                        let _ = state.update_tx.send(ServerMessage::TreeNode { id, node });
+                    });
+                }
+            }
+            TreeEvent::Select { node: node_id } => {
+                let handler = {
+                    let mut elements = self.elements.lock().unwrap();
+                    let Some(UiElement::Tree { nodes, selected, on_select, .. }) = elements.get_mut(id) else {
+                        return;
+                    };
+                    if find_tree_node_mut(nodes, &node_id).is_none() {
+                        return;
+                    }
+                    *selected = Some(node_id.clone());
+                    on_select.clone()
+                };
+                if let Some(handler) = handler {
+                    handler(&node_id);
+                }
+            }
+        }
+    }
+
+    fn handle_pointer(&self, id: &str, event: PointerEvent) {
+        let handler = {
+            let elements = self.elements.lock().unwrap();
+            if let Some(UiElement::Canvas { on_pointer: Some(handler), .. }) = elements.get(id) {
+                Some(handler.clone())
+            } else {
+                None
+            }
+        };
+        if let Some(handler) = handler {
+            handler(event);
+        }
+    }
+
+    fn handle_dialog_response(&self, dialog_id: u64, value: serde_json::Value) {
+        let dialog = self.dialogs.lock().unwrap().remove(&dialog_id);
+        if let Some(dialog) = dialog {
+            let _ = dialog.responder.send(value);
+        }
+    }
+
+    fn handle_notification(&self, notification_id: u64, action: Option<usize>) {
+        let Some(notification) = self.notifications.lock().unwrap().remove(&notification_id) else {
+            return;
+        };
+        let _ = self.update_tx.send(ServerMessage::NotificationClose { notification_id });
+        if let Some(Some(handler)) = action.and_then(|index| notification.actions.get(index)) {
+            handler();
+        }
+    }
+
+    /// Messages for dialogs that are still open, for showing them to newly connected clients.
+    fn pending_dialog_messages(&self) -> Vec<ServerMessage> {
+        self.dialogs
+            .lock()
+            .unwrap()
+            .values()
+            .map(|dialog| dialog.message.clone())
+            .collect()
+    }
+
+    fn client_connected(&self) {
+        self.connections.fetch_add(1, Ordering::SeqCst);
+        self.clients.fetch_add(1, Ordering::SeqCst);
+    }
+
+    fn client_disconnected(&self) {
+        if self.clients.fetch_sub(1, Ordering::SeqCst) != 1 {
+            return;
+        }
+
+        // Nobody is left to answer open dialogs, so cancel them unless a browser
+        // reconnects in time, as it does when the page is reloaded
+        let connections = self.connections.load(Ordering::SeqCst);
+        let state = self.clone();
+        let cancel_if_abandoned = move || {
+            if state.clients.load(Ordering::SeqCst) == 0
+                && state.connections.load(Ordering::SeqCst) == connections
+            {
+                state.dialogs.lock().unwrap().clear();
+            }
+        };
+        match tokio::runtime::Handle::try_current() {
+            Ok(runtime) => {
+                runtime.spawn(async move {
+                    tokio::time::sleep(DIALOG_RECONNECT_GRACE).await;
+                    cancel_if_abandoned();
+                });
+            }
+            Err(_) => cancel_if_abandoned(),
+        }
+    }
+
+    fn handle_table_event(&self, id: &str, event: TableEvent) {
+        #[allow(clippy::type_complexity)]
+        enum HandlerCall {
+            RowClick(Arc<Box<dyn Fn(&str) + Send + Sync + 'static>>, String),
+            Sort(Arc<Box<dyn Fn(&str, bool) + Send + Sync + 'static>>, String, bool),
+        }
+
+        let handler_call = {
+            let elements = self.elements.lock().unwrap();
+            if let Some(UiElement::Table { on_row_click, on_sort, .. }) = elements.get(id) {
+                match event {
+                    TableEvent::RowClick { row } => {
+                        on_row_click.clone().map(|handler| HandlerCall::RowClick(handler, row))
+                    }
+                    TableEvent::Sort { column, ascending } => {
+                        on_sort.clone().map(|handler| HandlerCall::Sort(handler, column, ascending))
+                    }
+                }
+            } else {
+                None
+            }
+        };
+
+        if let Some(handler_call) = handler_call {
+            match handler_call {
+                HandlerCall::RowClick(handler, row) => handler(&row),
+                HandlerCall::Sort(handler, column, ascending) => handler(&column, ascending),
+            }
+        }
+    }
+}
+
+impl Default for AppState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+async fn websocket_handler(
+    ws: WebSocketUpgrade,
+    State(state): State<AppState>,
+) -> impl IntoResponse {
+    ws.on_upgrade(|socket| websocket(socket, state))
+}
+
+async fn websocket(stream: WebSocket, state: AppState) {
+    use futures_util::sink::SinkExt;
+    use futures_util::stream::StreamExt;
+
+    let (mut sender, mut receiver) = stream.split();
+
+    // Subscribe to updates from the app state before taking the initial state and
+    // open dialogs, so changes made meanwhile are not missed
+    let mut update_rx = state.update_tx.subscribe();
+    state.client_connected();
+
+    // Send initial UI state
+    let init_msg = ServerMessage::Init {
+        elements: state.get_all_elements_for_client(),
+    };
+    let json = serde_json::to_string(&init_msg).unwrap();
+    if sender.send(Message::Text(json.into())).await.is_err() {
+        state.client_disconnected();
+        return;
+    }
+
+    // Show dialogs that are still waiting for an answer
+    for msg in state.pending_dialog_messages() {
+        let json = serde_json::to_string(&msg).unwrap();
+        if sender.send(Message::Text(json.into())).await.is_err() {
+            state.client_disconnected();
+            return;
+        }
+    }
+
+    // Replies meant for this client only
+    let (reply_tx, mut reply_rx) = mpsc::unbounded_channel::<ServerMessage>();
+
+    // Spawn task to forward updates to this client
+    let state_for_send = state.clone();
+    let mut send_task = tokio::spawn(async move {
+        loop {
+            let msg = tokio::select! {
+                result = update_rx.recv() => match result {
+                    Ok(msg) => msg,
+                    // The client fell behind and missed updates, so resend the full state
+                    Err(broadcast::error::RecvError::Lagged(_)) => ServerMessage::Init {
+                        elements: state_for_send.get_all_elements_for_client(),
+                    },
+                    Err(broadcast::error::RecvError::Closed) => break,
+                },
+                Some(msg) = reply_rx.recv() => msg,
+            };
+            let json = serde_json::to_string(&msg).unwrap();
+            if sender.send(Message::Text(json.into())).await.is_err() {
+                break;
+            }
+        }
+    });
+
+    // Handle incoming messages
+    let state_clone = state.clone();
+    let client = ClientHandle { reply_tx: reply_tx.clone() };
+    let mut recv_task = tokio::spawn(async move {
+        let mut pending_suggestions = PendingSuggestions::default();
+        while let Some(Ok(msg)) = receiver.next().await {
+            if let Message::Text(text) = msg
+                && let Ok(client_msg) = serde_json::from_str::<ClientMessage>(&text) {
+                // Handlers can find out which browser sent the event
+                CURRENT_CLIENT.sync_scope(client.clone(), || {
+                    state_clone.handle_message(client_msg, &reply_tx, &mut pending_suggestions);
+                });
+            }
+        }
+    });
+
+    // Wait for either task to finish
+    tokio::select! {
+        _ = (&mut send_task) => recv_task.abort(),
+        _ = (&mut recv_task) => send_task.abort(),
+    }
+
+    state.client_disconnected();
+}
+
+async fn image_handler(
+    Path(id): Path<String>,
+    headers: HeaderMap,
+    State(state): State<AppState>,
+) -> Response {
+    let source = match state.elements.lock().unwrap().get(&id) {
+        Some(UiElement::Image { source, .. }) => source.clone(),
+        _ => return StatusCode::NOT_FOUND.into_response(),
+    };
+
+    // Files may have changed since the source was created
+    let version = match &source.data {
+        ImageData::Bytes { .. } => source.version,
+        ImageData::Path(path) => file_version(path, tokio::fs::metadata(path).await.and_then(|m| m.modified()).ok()),
+    };
+    let etag = format!("\"{}\"", version);
+    if headers
+        .get(header::IF_NONE_MATCH)
+        .is_some_and(|value| value.as_bytes() == etag.as_bytes())
+    {
+        return StatusCode::NOT_MODIFIED.into_response();
+    }
+
+    let (data, mime_type) = match source.data {
+        ImageData::Bytes { data, mime_type } => (data, mime_type),
+        ImageData::Path(path) => match tokio::fs::read(&path).await {
+            Ok(data) => (Bytes::from(data), image_mime_type(&path).to_string()),
+            Err(_) => return StatusCode::NOT_FOUND.into_response(),
+        },
+    };
+
+    (
+        [
+            (header::CONTENT_TYPE, mime_type),
+            (header::ETAG, etag),
+            (header::CACHE_CONTROL, "no-cache".to_string()),
+        ],
+        data,
+    )
+        .into_response()
+}
+
+#[derive(Deserialize)]
+struct UploadParams {
+    name: String,
+}
+
+async fn upload_handler(
+    Path(id): Path<String>,
+    Query(params): Query<UploadParams>,
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    body: Body,
+) -> Response {
+    let (handler, max_size) = match state.elements.lock().unwrap().get(&id) {
+        Some(UiElement::FileUpload { on_upload: Some(handler), max_size, .. }) => {
+            (handler.clone(), max_size.unwrap_or(DEFAULT_MAX_UPLOAD_SIZE))
+        }
+        _ => return StatusCode::NOT_FOUND.into_response(),
+    };
+    if !state.is_enabled(&id) {
+        return StatusCode::FORBIDDEN.into_response();
+    }
+
+    let Some(size) = headers
+        .get(header::CONTENT_LENGTH)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse::<u64>().ok())
+    else {
+        return StatusCode::LENGTH_REQUIRED.into_response();
+    };
+    if size > max_size {
+        return (
+            StatusCode::PAYLOAD_TOO_LARGE,
+            format!("File is larger than the maximum of {} bytes", max_size),
+        )
+            .into_response();
+    }
+
+    let mime_type = headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or("application/octet-stream")
+        .to_string();
+
+    let file = UploadedFile {
+        name: params.name,
+        size,
+        mime_type,
+        body: body.into_data_stream(),
+        received: 0,
+    };
+
+    match handler(file).await {
+        Ok(()) => StatusCode::OK.into_response(),
+        Err(message) => (StatusCode::UNPROCESSABLE_ENTITY, message).into_response(),
+    }
+}
+
+async fn download_handler(Path(token): Path<String>, State(state): State<AppState>) -> Response {
+    let Some(download) = state.take_download(&token, Instant::now()) else {
+        return StatusCode::NOT_FOUND.into_response();
+    };
+
+    // Plain ASCII fallback for old browsers, plus the exact name as RFC 5987 UTF-8
+    let fallback: String = download
+        .file_name
+        .chars()
+        .map(|c| if c == ' ' || (c.is_ascii_graphic() && c != '"' && c != '\\') { c } else { '_' })
+        .collect();
+    let disposition = format!(
+        "attachment; filename=\"{}\"; filename*=UTF-8''{}",
+        fallback,
+        percent_encode(&download.file_name)
+    );
+
+    (
+        [
+            (header::CONTENT_TYPE, download.mime_type),
+            (header::CONTENT_DISPOSITION, disposition),
+            (header::CACHE_CONTROL, "no-store".to_string()),
+        ],
+        download.data,
+    )
+        .into_response()
+}
+
+// Generates a token for a download URL from the operating system's random source
+fn download_token() -> String {
+    let mut bytes = [0u8; 16];
+    getrandom::fill(&mut bytes).expect("no random source available");
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+// Percent-encodes everything except RFC 5987 attr-chars
+fn percent_encode(value: &str) -> String {
+    value
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9'
+            | b'!' | b'#' | b'$' | b'&' | b'+' | b'-' | b'.' | b'^' | b'_' | b'`' | b'|' | b'~' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+// Guesses an image MIME type from the file extension
+fn image_mime_type(path: &std::path::Path) -> &'static str {
+    let extension = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| ext.to_ascii_lowercase());
+    match extension.as_deref() {
+        Some("png") => "image/png",
+        Some("jpg" | "jpeg") => "image/jpeg",
+        Some("gif") => "image/gif",
+        Some("svg") => "image/svg+xml",
+        Some("webp") => "image/webp",
+        Some("bmp") => "image/bmp",
+        Some("ico") => "image/x-icon",
+        Some("avif") => "image/avif",
+        _ => "application/octet-stream",
+    }
+}
+
+// Default HTML template - wraps user content
+fn generate_html(title: &str, body_content: &str) -> String {
+    format!(r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+    <meta charset="UTF-8">
+    <meta name="viewport" content="width=device-width, initial-scale=1.0">
+    <title>{title}</title>
+    <link rel="stylesheet" href="/static/webui.css">
+</head>
+<body>
+{body_content}
+    <script src="/static/webui.js"></script>
+</body>
+</html>"#, title = title, body_content = body_content)
+}
+
+/// Configuration for creating a WebUI router
+pub struct RouterConfig {
+    /// Application state
+    pub state: AppState,
+    /// Path to static files directory
+    pub static_dir: String,
+    /// HTML page title
+    pub title: String,
+    /// HTML body content (the UI layout)
+    pub body_html: String,
+}
+
+impl RouterConfig {
+    /// Creates a new router configuration
+    pub fn new(state: AppState, body_html: impl Into<String>) -> Self {
+        Self {
+            state,
+            static_dir: "static".to_string(),
+            title: "WebUI App".to_string(),
+            body_html: body_html.into(),
+        }
+    }
+
+    /// Sets the page title
+    pub fn title(mut self, title: impl Into<String>) -> Self {
+        self.title = title.into();
+        self
+    }
+
+    /// Sets the static files directory
+    pub fn static_dir(mut self, dir: impl Into<String>) -> Self {
+        self.static_dir = dir.into();
+        self
+    }
+}
+
+/// Creates an Axum router configured for WebUI.
+///
+/// The router includes:
+/// - `/` - Serves the main HTML page with your custom UI layout
+/// - `/ws` - WebSocket endpoint for UI communication
+/// - `/image/{id}` - Serves the data of [`UiElement::Image`] elements
+/// - `/upload/{id}` - Receives files from [`UiElement::FileUpload`] elements
+/// - `/download/{token}` - Serves files sent with [`AppState::download`]
+/// - `/static` - Serves static files (webui.js, webui.css, etc.)
+///
+/// # Arguments
+/// - `config`: Router configuration with state and HTML content
+///
+/// # Example
+/// ```no_run
+/// use webui::{AppState, RouterConfig, create_router};
+///
+/// #[tokio::main]
+/// async fn main() {
+///     let state = AppState::new();
+///
+///     let html = r#"
+///         <div class="container">
+///             <h1>My App</h1>
+///             <ui-button id="btn1"></ui-button>
+///             <ui-text id="status"></ui-text>
+///         </div>
+///     "#;
+///
+///     let config = RouterConfig::new(state.clone(), html)
+///         .title("My WebUI App");
+///
+///     let app = create_router(config);
+///
+///     let listener = tokio::net::TcpListener::bind("127.0.0.1:3000")
+///         .await
+///         .unwrap();
+///     axum::serve(listener, app).await.unwrap();
+/// }
+/// ```
+pub fn create_router(config: RouterConfig) -> Router {
+    let html_content = generate_html(&config.title, &config.body_html);
+    let state = config.state.clone();
+
+    Router::new()
+        .route("/", get(move || async move {
+            Html(html_content)
+        }))
+        .route("/ws", get(websocket_handler))
+        .route("/image/{id}", get(image_handler))
+        .route("/upload/{id}", post(upload_handler))
+        .route("/download/{token}", get(download_handler))
+        .nest_service("/static", ServeDir::new(config.static_dir))
+        .with_state(state)
+}
+
+/// Convenience function to start a WebUI server.
+///
+/// This is a helper that combines creating a router and starting the server.
+///
+/// # Arguments
+/// - `state`: The application state
+/// - `html`: HTML body content for the UI layout
+/// - `title`: Page title (optional, defaults to "WebUI App")
+/// - `addr`: Address to bind to (e.g., "127.0.0.1:3000")
+///
+/// # Example
+/// ```no_run
+/// use webui::{AppState, UiElement, start_server};
+/// use std::sync::Arc;
+///
+/// #[tokio::main]
+/// async fn main() {
+///     let state = AppState::new();
+///
+///     state.add_element(UiElement::Button {
+///         id: "btn1".to_string(),
+///         text: "Click Me!".to_string(),
+///         on_click: Some(Arc::new(Box::new(|| {
+///             println!("Clicked!");
+///         }))),
+///     });
+///
+///     let html = r#"<ui-button id="btn1"></ui-button>"#;
+///
+///     start_server(state, html, "My App", "127.0.0.1:3000").await.unwrap();
+/// }
+/// ```
+pub async fn start_server(
+    state: AppState,
+    html: impl Into<String>,
+    title: impl Into<String>,
+    addr: impl AsRef<str>,
+) -> Result<(), std::io::Error> {
+    let config = RouterConfig::new(state, html).title(title);
+    let app = create_router(config);
+
+    let listener = tokio::net::TcpListener::bind(addr.as_ref()).await?;
+    println!("Server running on http://{}", addr.as_ref());
+
+    axum::serve(listener, app).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use headless_chrome::{Browser, Tab};
+    use std::sync::Arc;
+
+    #[test]
+    fn test_app_state_creation() {
+        let state = AppState::new();
+        state.add_element(UiElement::Button {
+            id: "btn1".to_string(),
+            text: "Test".to_string(),
+            on_click: None,
+        });
+
+        let elements = state.get_all_elements();
+        assert_eq!(elements.len(), 1);
+    }
+
+    #[test]
+    fn test_scoped_states() {
+        let state = AppState::new();
+
+        // Create scoped states
+        let form_state = state.scope("form");
+        let modal_state = state.scope("modal");
+
+        // Add elements with the same local ID to different scopes
+        form_state.add_element(UiElement::Button {
+            id: "btn1".to_string(),
+            text: "Form Button".to_string(),
+            on_click: None,
+        });
+
+        modal_state.add_element(UiElement::Button {
+            id: "btn1".to_string(),
+            text: "Modal Button".to_string(),
+            on_click: None,
+        });
+
+        // Both elements should exist with different full IDs
+        let elements = state.get_all_elements();
+        assert_eq!(elements.len(), 2, "Should have 2 elements with scoped IDs");
+
+        // Verify they're stored with full paths
+        let element_map = state.elements.lock().unwrap();
+        assert!(element_map.contains_key("form.btn1"), "Should contain form.btn1");
+        assert!(element_map.contains_key("modal.btn1"), "Should contain modal.btn1");
+    }
+
+    #[test]
+    fn test_nested_scopes() {
+        let state = AppState::new();
+
+        // Create nested scopes
+        let form_state = state.scope("form");
+        let inputs_state = form_state.scope("inputs");
+
+        // Add element in nested scope
+        inputs_state.add_element(UiElement::Input {
+            id: "name".to_string(),
+            value: "".to_string(),
+            label: None,
+            placeholder: None,
+            tooltip: None,
+            on_input: None,
+        });
+
+        // Should be stored with full nested path
+        let element_map = state.elements.lock().unwrap();
+        assert!(element_map.contains_key("form.inputs.name"),
+            "Should contain form.inputs.name");
+    }
+
+    #[test]
+    fn test_scope_update_element() {
+        let state = AppState::new();
+        let form_state = state.scope("form");
+
+        // Add an element
+        form_state.add_element(UiElement::Text {
+            id: "status".to_string(),
+            text: "Initial".to_string(),
+        });
+
+        // Update using scoped state
+        form_state.update_element(
+            "status",
+            UiElement::Text {
+                id: "status".to_string(),
+                text: "Updated".to_string(),
+            },
+        );
+
+        // Verify it's stored with full path
+        let element_map = state.elements.lock().unwrap();
+        if let Some(UiElement::Text { text, .. }) = element_map.get("form.status") {
+            assert_eq!(text, "Updated");
+        } else {
+            panic!("Element form.status not found or has wrong type");
+        }
+    }
+
+    #[test]
+    fn test_select_options_and_change() {
+        let state = AppState::new();
+        let form_state = state.scope("form");
+
+        let selected_value = Arc::new(Mutex::new(String::new()));
+        let selected_value_clone = selected_value.clone();
+
+        form_state.add_element(UiElement::Select {
+            id: "color".to_string(),
+            options: vec![
+                ("red".to_string(), "Red".to_string()),
+                ("green".to_string(), "Green".to_string()),
+            ],
+            selected: Some("red".to_string()),
+            on_change: Some(Arc::new(Box::new(move |value| {
+                *selected_value_clone.lock().unwrap() = value.to_string();
+            }))),
+        });
+
+        // Values not in the option list are ignored
+        state.handle_change("form.color", serde_json::json!("purple"));
+        assert!(selected_value.lock().unwrap().is_empty());
+
+        state.handle_change("form.color", serde_json::json!("green"));
+        assert_eq!(*selected_value.lock().unwrap(), "green");
+
+        // Replacing the options keeps a selection that is still available
+        form_state.set_select_options(
+            "color",
+            vec![
+                ("green".to_string(), "Green".to_string()),
+                ("blue".to_string(), "Blue".to_string()),
+            ],
+        );
+        {
+            let element_map = state.elements.lock().unwrap();
+            if let Some(UiElement::Select { options, selected, .. }) = element_map.get("form.color") {
+                assert_eq!(options.len(), 2);
+                assert_eq!(selected.as_deref(), Some("green"));
+            } else {
+                panic!("Element form.color not found or has wrong type");
+            }
+        }
+
+        // ...and clears one that is not
+        form_state.set_select_options("color", vec![("blue".to_string(), "Blue".to_string())]);
+        let element_map = state.elements.lock().unwrap();
+        if let Some(UiElement::Select { selected, .. }) = element_map.get("form.color") {
+            assert_eq!(*selected, None);
+        } else {
+            panic!("Element form.color not found or has wrong type");
+        }
+    }
+
+    #[test]
+    fn test_table_row_operations() {
+        let state = AppState::new();
+        let jobs_state = state.scope("jobs");
+
+        let sort_request = Arc::new(Mutex::new(None));
+        let sort_request_clone = sort_request.clone();
+
+        jobs_state.add_element(UiElement::Table {
+            id: "table".to_string(),
+            columns: vec![TableColumn {
+                key: "name".to_string(),
+                label: "Name".to_string(),
+                sortable: true,
+            }],
+            rows: vec![TableRow {
+                key: "a".to_string(),
+                cells: vec![serde_json::json!("Alpha")],
+            }],
+            on_row_click: None,
+            on_sort: Some(Arc::new(Box::new(move |column, ascending| {
+                *sort_request_clone.lock().unwrap() = Some((column.to_string(), ascending));
+            }))),
+        });
+
+        let mut update_rx = state.update_tx.subscribe();
+
+        jobs_state.append_table_row("table", TableRow {
+            key: "b".to_string(),
+            cells: vec![serde_json::json!("Beta")],
+        });
+        jobs_state.update_table_row("table", TableRow {
+            key: "a".to_string(),
+            cells: vec![serde_json::json!("Alpha 2")],
+        });
+        jobs_state.delete_table_row("table", "b");
+        // Unknown keys are ignored and not broadcast
+        jobs_state.delete_table_row("table", "missing");
+
+        let messages: Vec<String> = std::iter::from_fn(|| update_rx.try_recv().ok())
+            .map(|msg| serde_json::to_string(&msg).unwrap())
+            .collect();
+        assert_eq!(messages, vec![
+            r#"{"type":"table_append","id":"jobs.table","row":{"key":"b","cells":["Beta"]}}"#,
+            r#"{"type":"table_update","id":"jobs.table","row":{"key":"a","cells":["Alpha 2"]}}"#,
+            r#"{"type":"table_delete","id":"jobs.table","key":"b"}"#,
+        ]);
+
+        {
+            let element_map = state.elements.lock().unwrap();
+            if let Some(UiElement::Table { rows, .. }) = element_map.get("jobs.table") {
+                assert_eq!(rows.len(), 1);
+                assert_eq!(rows[0].cells, vec![serde_json::json!("Alpha 2")]);
+            } else {
+                panic!("Element jobs.table not found or has wrong type");
+            }
+        }
+
+        let event = serde_json::from_str::<ClientMessage>(
+            r#"{"type":"table","id":"jobs.table","event":{"kind":"sort","column":"name","ascending":false}}"#,
+        ).unwrap();
+        if let ClientMessage::Table { id, event } = event {
+            state.handle_table_event(&id, event);
+        }
+        assert_eq!(*sort_request.lock().unwrap(), Some(("name".to_string(), false)));
+    }
+
+    #[test]
+    fn test_list_item_operations() {
+        let state = AppState::new();
+
+        let item = |key: &str| ListItem {
+            key: key.to_string(),
+            value: serde_json::json!(key),
+        };
+        state.add_element(UiElement::List {
+            id: "queue".to_string(),
+            items: vec![item("a"), item("b")],
+            on_item_click: None,
+        });
+
+        let mut update_rx = state.update_tx.subscribe();
+
+        // Out of range indices are clamped to the end of the list
+        state.insert_list_item("queue", 10, item("c"));
+        // Duplicate keys are rejected
+        state.insert_list_item("queue", 0, item("b"));
+        state.move_list_item("queue", "c", 0);
+        state.remove_list_item("queue", "a");
+        state.remove_list_item("queue", "missing");
+
+        let messages: Vec<String> = std::iter::from_fn(|| update_rx.try_recv().ok())
+            .map(|msg| serde_json::to_string(&msg).unwrap())
+            .collect();
+        assert_eq!(messages, vec![
+            r#"{"type":"list_insert","id":"queue","index":2,"item":{"key":"c","value":"c"}}"#,
+            r#"{"type":"list_move","id":"queue","key":"c","index":0}"#,
+            r#"{"type":"list_remove","id":"queue","key":"a"}"#,
+        ]);
+
+        let element_map = state.elements.lock().unwrap();
+        if let Some(UiElement::List { items, .. }) = element_map.get("queue") {
+            let keys: Vec<&str> = items.iter().map(|item| item.key.as_str()).collect();
+            assert_eq!(keys, vec!["c", "b"]);
+        } else {
+            panic!("Element queue not found or has wrong type");
+        }
+    }
+
+    #[test]
+    fn test_progress_and_meter_values() {
+        let state = AppState::new();
+
+        state.add_element(UiElement::Progress {
+            id: "job".to_string(),
+            value: Some(0.0),
+            max: 10.0,
+        });
+        state.add_element(UiElement::Meter {
+            id: "cpu".to_string(),
+            value: 0.2,
+            min: 0.0,
+            max: 1.0,
+            low: Some(0.5),
+            high: Some(0.8),
+            optimum: Some(0.0),
+        });
+
+        let mut update_rx = state.update_tx.subscribe();
+
+        state.set_progress("job", Some(5.0));
+        // Repeating the current value is not broadcast
+        state.set_progress("job", Some(5.0));
+        state.set_progress("job", None);
+        state.set_meter("cpu", 0.9);
+        // Values for other element kinds are ignored
+        state.set_meter("job", 1.0);
+
+        let messages: Vec<String> = std::iter::from_fn(|| update_rx.try_recv().ok())
+            .map(|msg| serde_json::to_string(&msg).unwrap())
+            .collect();
+        assert_eq!(messages, vec![
+            r#"{"type":"value","id":"job","value":5.0}"#,
+            r#"{"type":"value","id":"job","value":null}"#,
+            r#"{"type":"value","id":"cpu","value":0.9}"#,
+        ]);
+
+        let element_map = state.elements.lock().unwrap();
+        if let Some(UiElement::Progress { value, .. }) = element_map.get("job") {
+            assert_eq!(*value, None);
+        } else {
+            panic!("Element job not found or has wrong type");
+        }
+    }
+
+    #[test]
+    fn test_canvas_draw_and_pointer() {
+        let state = AppState::new();
+
+        let pointer_events = Arc::new(Mutex::new(Vec::new()));
+        let pointer_events_clone = pointer_events.clone();
+
+        state.add_element(UiElement::Canvas {
+            id: "plot".to_string(),
+            width: 100,
+            height: 50,
+            commands: vec![DrawCommand::Line { x1: 0.0, y1: 0.0, x2: 10.0, y2: 10.0 }],
+            on_pointer: Some(Arc::new(Box::new(move |event| {
+                pointer_events_clone.lock().unwrap().push(event);
+            }))),
+        });
+
+        let mut update_rx = state.update_tx.subscribe();
+
+        state.draw("plot", vec![DrawCommand::FillStyle { color: "red".to_string() }]);
+        let message = serde_json::to_string(&update_rx.try_recv().unwrap()).unwrap();
+        assert_eq!(message, r#"{"type":"canvas_draw","id":"plot","commands":[{"op":"fill_style","color":"red"}]}"#);
+
+        // Commands before a clear are dropped from the stored list
+        state.draw("plot", vec![
+            DrawCommand::Clear,
+            DrawCommand::Rect { x: 1.0, y: 2.0, width: 3.0, height: 4.0, fill: true },
+        ]);
+        {
+            let element_map = state.elements.lock().unwrap();
+            if let Some(UiElement::Canvas { commands, .. }) = element_map.get("plot") {
+                assert_eq!(commands.len(), 2);
+                assert_eq!(commands[0], DrawCommand::Clear);
+            } else {
+                panic!("Element plot not found or has wrong type");
+            }
+        }
+
+        let message = serde_json::from_str::<ClientMessage>(
+            r#"{"type":"pointer","id":"plot","kind":"down","x":12.5,"y":30}"#,
+        ).unwrap();
+        if let ClientMessage::Pointer { id, kind, x, y } = message {
+            state.handle_pointer(&id, PointerEvent { kind, x, y });
+        }
+        assert_eq!(*pointer_events.lock().unwrap(), vec![
+            PointerEvent { kind: PointerKind::Down, x: 12.5, y: 30.0 },
+        ]);
+    }
+
+    #[test]
+    fn test_chart_append_points_window() {
+        let state = AppState::new();
+        let monitor_state = state.scope("monitor");
+
+        monitor_state.add_element(UiElement::Chart {
+            id: "cpu".to_string(),
+            chart_type: ChartType::Line,
+            series: vec![ChartSeries {
+                name: "load".to_string(),
+                color: Some("red".to_string()),
+                points: vec![(0.0, 1.0), (1.0, 2.0)],
+            }],
+            x_label: None,
+            y_label: None,
+            max_points: Some(3),
+        });
+
+        let mut update_rx = state.update_tx.subscribe();
+
+        monitor_state.append_chart_points("cpu", "load", vec![(2.0, 3.0), (3.0, 4.0)]);
+        // Unknown series are ignored
+        monitor_state.append_chart_points("cpu", "memory", vec![(0.0, 1.0)]);
+
+        let messages: Vec<String> = std::iter::from_fn(|| update_rx.try_recv().ok())
+            .map(|msg| serde_json::to_string(&msg).unwrap())
+            .collect();
+        assert_eq!(messages, vec![
+            r#"{"type":"chart_append","id":"monitor.cpu","series":"load","points":[[2.0,3.0],[3.0,4.0]]}"#,
+        ]);
+
+        // Only the most recent points are kept
+        let element_map = state.elements.lock().unwrap();
+        if let Some(UiElement::Chart { series, .. }) = element_map.get("monitor.cpu") {
+            assert_eq!(series[0].points, vec![(1.0, 2.0), (2.0, 3.0), (3.0, 4.0)]);
+        } else {
+            panic!("Element monitor.cpu not found or has wrong type");
+        }
+        drop(element_map);
+
+        // Initial points are limited as well
+        monitor_state.update_element("cpu", UiElement::Chart {
+            id: "cpu".to_string(),
+            chart_type: ChartType::Line,
+            series: vec![ChartSeries {
+                name: "load".to_string(),
+                color: None,
+                points: (0..5).map(|x| (x as f64, 0.0)).collect(),
+            }],
+            x_label: None,
+            y_label: None,
+            max_points: Some(3),
+        });
+        let message = serde_json::to_value(update_rx.try_recv().unwrap()).unwrap();
+        assert_eq!(message["element"]["series"][0]["points"], serde_json::json!([[2.0, 0.0], [3.0, 0.0], [4.0, 0.0]]));
+        let element_map = state.elements.lock().unwrap();
+        if let Some(UiElement::Chart { series, .. }) = element_map.get("monitor.cpu") {
+            assert_eq!(series[0].points.len(), 3);
+        } else {
+            panic!("Element monitor.cpu not found or has wrong type");
+        }
+    }
+
+    #[test]
+    fn test_tabs_active_tab() {
+        let state = AppState::new();
+        let settings_state = state.scope("settings");
+
+        let changes = Arc::new(Mutex::new(Vec::new()));
+        let changes_clone = changes.clone();
+
+        settings_state.add_element(UiElement::Tabs {
+            id: "sections".to_string(),
+            tabs: vec![
+                ("general".to_string(), "General".to_string()),
+                ("network".to_string(), "Network".to_string()),
+            ],
+            active: "general".to_string(),
+            on_change: Some(Arc::new(Box::new(move |tab| {
+                changes_clone.lock().unwrap().push(tab.to_string());
+            }))),
+        });
+
+        // Unknown tabs are ignored
+        state.handle_change("settings.sections", serde_json::json!("display"));
+        assert_eq!(settings_state.active_tab("sections").as_deref(), Some("general"));
+
+        state.handle_change("settings.sections", serde_json::json!("network"));
+        assert_eq!(settings_state.active_tab("sections").as_deref(), Some("network"));
+        assert_eq!(*changes.lock().unwrap(), vec!["network".to_string()]);
+
+        // Switching from the server updates clients without calling the handler
+        let mut update_rx = state.update_tx.subscribe();
+        settings_state.set_active_tab("sections", "general");
+        settings_state.set_active_tab("sections", "display");
+        assert_eq!(settings_state.active_tab("sections").as_deref(), Some("general"));
+        assert_eq!(changes.lock().unwrap().len(), 1);
+
+        let messages: Vec<serde_json::Value> = std::iter::from_fn(|| update_rx.try_recv().ok())
+            .map(|msg| serde_json::to_value(&msg).unwrap())
+            .collect();
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0]["id"], "settings.sections");
+        assert_eq!(messages[0]["element"]["active"], "general");
+
+        assert_eq!(settings_state.active_tab("missing"), None);
+    }
+
+    #[tokio::test]
+    async fn test_tree_lazy_loading() {
+        let state = AppState::new();
+        let browser_state = state.scope("browser");
+
+        let selected_node = Arc::new(Mutex::new(String::new()));
+        let selected_node_clone = selected_node.clone();
+        let loads = Arc::new(AtomicUsize::new(0));
+        let loads_clone = loads.clone();
+
+        browser_state.add_element(UiElement::Tree {
+            id: "files".to_string(),
+            nodes: vec![TreeNode::branch("/", "root")],
+            selected: None,
+            on_expand: Some(Arc::new(Box::new(move |node_id| {
+                loads_clone.fetch_add(1, Ordering::SeqCst);
+                Box::pin(async move {
+                    vec![
+                        TreeNode::branch(format!("{}etc", node_id), "etc"),
+                        TreeNode::leaf(format!("{}README", node_id), "README"),
+                    ]
+                })
+            }))),
+            on_select: Some(Arc::new(Box::new(move |node_id| {
+                *selected_node_clone.lock().unwrap() = node_id.to_string();
+            }))),
+        });
+
+        let mut update_rx = state.update_tx.subscribe();
+
+        // Expanding a node first sends its new state, then the loaded children
+        let event = serde_json::from_str::<ClientMessage>(
+            r#"{"type":"tree","id":"browser.files","event":{"kind":"toggle","node":"/","expanded":true}}"#,
+        ).unwrap();
+        if let ClientMessage::Tree { id, event } = event {
+            state.handle_tree_event(&id, event);
+        }
+        // Expanding again while the children load does not start another load
+        state.handle_tree_event("browser.files", TreeEvent::Toggle { node: "/".to_string(), expanded: true });
+        for _ in 0..2 {
+            let message = serde_json::to_value(update_rx.recv().await.unwrap()).unwrap();
+            assert_eq!(message["type"], "tree_node");
+            assert_eq!(message["node"]["expanded"], true);
+            assert_eq!(message["node"]["children"], serde_json::Value::Null);
+        }
+
+        let message = serde_json::to_value(update_rx.recv().await.unwrap()).unwrap();
+        assert_eq!(message["id"], "browser.files");
+        assert_eq!(message["node"]["children"][0]["id"], "/etc");
+        assert_eq!(message["node"]["children"][1]["has_children"], false);
+
+        // Loaded children are kept, so expanding again does not call the loader
+        state.handle_tree_event("browser.files", TreeEvent::Toggle { node: "/".to_string(), expanded: false });
+        state.handle_tree_event("browser.files", TreeEvent::Toggle { node: "/".to_string(), expanded: true });
+        let messages: Vec<serde_json::Value> = std::iter::from_fn(|| update_rx.try_recv().ok())
+            .map(|msg| serde_json::to_value(&msg).unwrap())
+            .collect();
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[1]["node"]["children"].as_array().unwrap().len(), 2);
+        assert_eq!(loads.load(Ordering::SeqCst), 1);
+
+        // Unknown nodes cannot be selected
+        state.handle_tree_event("browser.files", TreeEvent::Select { node: "/missing".to_string() });
+        assert!(selected_node.lock().unwrap().is_empty());
+        state.handle_tree_event("browser.files", TreeEvent::Select { node: "/README".to_string() });
+        assert_eq!(*selected_node.lock().unwrap(), "/README");
+
+        // Nested nodes can be patched individually
+        browser_state.update_tree_node("files", TreeNode::leaf("/etc", "etc (empty)"));
+        let message = serde_json::to_string(&update_rx.try_recv().unwrap()).unwrap();
+        assert_eq!(
+            message,
+            r#"{"type":"tree_node","id":"browser.files","node":{"id":"/etc","label":"etc (empty)","has_children":false,"expanded":false,"children":null}}"#,
+        );
+        let element_map = state.elements.lock().unwrap();
+        if let Some(UiElement::Tree { nodes, selected, .. }) = element_map.get("browser.files") {
+            assert_eq!(nodes[0].children.as_ref().unwrap()[0].label, "etc (empty)");
+            assert_eq!(selected.as_deref(), Some("/README"));
+        } else {
+            panic!("Element browser.files not found or has wrong type");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_tree_load_after_panic() {
+        let state = AppState::new();
+        let loads = Arc::new(AtomicUsize::new(0));
+        let loads_clone = loads.clone();
+
+        state.add_element(UiElement::Tree {
+            id: "files".to_string(),
+            nodes: vec![TreeNode::branch("/", "root")],
+            selected: None,
+            on_expand: Some(Arc::new(Box::new(move |_| {
+                let first = loads_clone.fetch_add(1, Ordering::SeqCst) == 0;
+                Box::pin(async move {
+                    if first {
+                        panic!("loader failed");
+                    }
+                    vec![TreeNode::leaf("/README", "README")]
+                })
+            }))),
+            on_select: None,
+        });
+
+        // A failed load does not keep the node from loading again
+        let expand = |expanded| TreeEvent::Toggle { node: "/".to_string(), expanded };
+        state.handle_tree_event("files", expand(true));
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(state.tree_loads.lock().unwrap().is_empty());
+
+        let mut update_rx = state.update_tx.subscribe();
+        state.handle_tree_event("files", expand(false));
+        state.handle_tree_event("files", expand(true));
+        for _ in 0..2 {
+            update_rx.recv().await.unwrap();
+        }
+        let message = serde_json::to_value(update_rx.recv().await.unwrap()).unwrap();
+        assert_eq!(message["node"]["children"][0]["id"], "/README");
+        assert_eq!(loads.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn test_datetime_parse_and_format() {
+        let parse = DateTimeValue::parse;
+
+        assert_eq!(parse(DateTimeMode::Date, "2024-02-29"), Some(DateTimeValue::Date { year: 2024, month: 2, day: 29 }));
+        assert_eq!(parse(DateTimeMode::Date, "1900-02-29"), None);
+        assert_eq!(parse(DateTimeMode::Date, "2024-04-31"), None);
+        assert_eq!(parse(DateTimeMode::Date, "2024-4-01"), None);
+        assert_eq!(parse(DateTimeMode::Date, "24-04-01"), None);
+        assert_eq!(parse(DateTimeMode::Date, "2024-04-01T10:00"), None);
+
+        assert_eq!(parse(DateTimeMode::Time, "23:59"), Some(DateTimeValue::Time { hour: 23, minute: 59, second: 0 }));
+        assert_eq!(parse(DateTimeMode::Time, "08:15:30.250"), Some(DateTimeValue::Time { hour: 8, minute: 15, second: 30 }));
+        assert_eq!(parse(DateTimeMode::Time, "24:00"), None);
+        assert_eq!(parse(DateTimeMode::Time, "12:00:"), None);
+
+        let value = parse(DateTimeMode::DateTimeLocal, "2024-12-31T18:30:05").unwrap();
+        assert_eq!(value.mode(), DateTimeMode::DateTimeLocal);
+        assert_eq!(value.to_string(), "2024-12-31T18:30:05");
+        assert_eq!(parse(DateTimeMode::DateTimeLocal, "2024-12-31T18:30").unwrap().to_string(), "2024-12-31T18:30");
+        assert_eq!(serde_json::to_string(&value).unwrap(), r#""2024-12-31T18:30:05""#);
+    }
+
+    #[test]
+    fn test_datetime_change_validation() {
+        let state = AppState::new();
+        let booking_state = state.scope("booking");
+
+        let changes = Arc::new(Mutex::new(Vec::new()));
+        let changes_clone = changes.clone();
+
+        let date = |s| DateTimeValue::parse(DateTimeMode::Date, s);
+        booking_state.add_element(UiElement::DateTime {
+            id: "day".to_string(),
+            mode: DateTimeMode::Date,
+            value: None,
+            min: date("2024-01-01"),
+            max: date("2024-12-31"),
+            on_change: Some(Arc::new(Box::new(move |value| {
+                changes_clone.lock().unwrap().push(value);
+            }))),
+        });
+
+        // Malformed, out-of-range and non-string values are ignored
+        state.handle_change("booking.day", serde_json::json!("next tuesday"));
+        state.handle_change("booking.day", serde_json::json!("2023-12-31"));
+        state.handle_change("booking.day", serde_json::json!("2025-01-01"));
+        state.handle_change("booking.day", serde_json::json!(20240101));
+        assert!(changes.lock().unwrap().is_empty());
+
+        state.handle_change("booking.day", serde_json::json!("2024-06-15"));
+        state.handle_change("booking.day", serde_json::json!(""));
+        assert_eq!(*changes.lock().unwrap(), vec![date("2024-06-15"), None]);
+
+        state.handle_change("booking.day", serde_json::json!("2024-12-31"));
+        let element_map = state.elements.lock().unwrap();
+        if let Some(UiElement::DateTime { value, .. }) = element_map.get("booking.day") {
+            assert_eq!(*value, date("2024-12-31"));
+        } else {
+            panic!("Element booking.day not found or has wrong type");
+        }
+    }
+
+    #[test]
+    fn test_color_change_normalization() {
+        assert_eq!(normalize_color("#ABC", false).as_deref(), Some("#aabbcc"));
+        assert_eq!(normalize_color("#12ab9F", false).as_deref(), Some("#12ab9f"));
+        assert_eq!(normalize_color("#12ab9f80", false), None);
+        assert_eq!(normalize_color("#12ab9f80", true).as_deref(), Some("#12ab9f80"));
+        assert_eq!(normalize_color("#abc8", true).as_deref(), Some("#aabbcc88"));
+        assert_eq!(normalize_color("#12ab9f", true).as_deref(), Some("#12ab9fff"));
+        assert_eq!(normalize_color("12ab9f", false), None);
+        assert_eq!(normalize_color("#12ab9g", false), None);
+        assert_eq!(normalize_color("red", false), None);
+
+        let state = AppState::new();
+        let chart_state = state.scope("chart");
+
+        let changes = Arc::new(Mutex::new(Vec::new()));
+        let changes_clone = changes.clone();
+
+        chart_state.add_element(UiElement::Color {
+            id: "series-color".to_string(),
+            value: "#2196f3".to_string(),
+            alpha: false,
+            presets: vec!["#f44336".to_string(), "#4caf50".to_string()],
+            on_change: Some(Arc::new(Box::new(move |value| {
+                changes_clone.lock().unwrap().push(value.to_string());
+            }))),
+        });
+
+        state.handle_change("chart.series-color", serde_json::json!("not a color"));
+        state.handle_change("chart.series-color", serde_json::json!("#F44336"));
+        assert_eq!(*changes.lock().unwrap(), vec!["#f44336".to_string()]);
+
+        let element_map = state.elements.lock().unwrap();
+        if let Some(UiElement::Color { value, .. }) = element_map.get("chart.series-color") {
+            assert_eq!(value, "#f44336");
+        } else {
+            panic!("Element chart.series-color not found or has wrong type");
+        }
+        drop(element_map);
+
+        // Initial colors are normalized the same way
+        chart_state.add_element(UiElement::Color {
+            id: "fill".to_string(),
+            value: "blue".to_string(),
+            alpha: true,
+            presets: vec!["#F44336".to_string(), "green".to_string(), "#abc8".to_string()],
+            on_change: None,
+        });
+        let elements = serde_json::to_value(state.get_all_elements_for_client()).unwrap();
+        let fill = elements.as_array().unwrap().iter().find(|e| e["id"] == "chart.fill").unwrap();
+        assert_eq!(fill["value"], "#000000ff");
+        assert_eq!(fill["presets"], serde_json::json!(["#f44336ff", "#aabbcc88"]));
+    }
+
+    #[test]
+    fn test_markdown_rendering_and_links() {
+        assert_eq!(render_markdown("**Ready** to [go](/next)"), "<p><strong>Ready</strong> to <a href=\"/next\" rel=\"noopener noreferrer\">go</a></p>\n");
+        // Raw HTML is sanitized against the allowlist
+        assert_eq!(
+            render_markdown("<script>alert(1)</script><b onclick=\"x()\">bold</b> <a href=\"javascript:x()\">link</a>"),
+            "bold <a rel=\"noopener noreferrer\">link</a>",
+        );
+        assert!(render_markdown("| a |\n|---|\n| 1 |").contains("<td>1</td>"));
+
+        let state = AppState::new();
+        let help_state = state.scope("help");
+
+        let clicked = Arc::new(Mutex::new(String::new()));
+        let clicked_clone = clicked.clone();
+
+        help_state.add_element(UiElement::Markdown {
+            id: "intro".to_string(),
+            markdown: "See [settings](settings)".into(),
+            on_link_click: Some(Arc::new(Box::new(move |href| {
+                *clicked_clone.lock().unwrap() = href.to_string();
+            }))),
+        });
+
+        let elements = serde_json::to_value(state.get_all_elements_for_client()).unwrap();
+        assert_eq!(elements[0]["html"], "<p>See <a href=\"settings\" rel=\"noopener noreferrer\">settings</a></p>\n");
+        assert_eq!(elements[0]["route_links"], true);
+        assert!(elements[0].get("markdown").is_none());
+
+        state.handle_link_click("help.intro", "settings");
+        assert_eq!(*clicked.lock().unwrap(), "settings");
+
+        // Updates are rendered again
+        let mut update_rx = state.update_tx.subscribe();
+        help_state.update_element("intro", UiElement::Markdown {
+            id: "intro".to_string(),
+            markdown: "**Done**".into(),
+            on_link_click: None,
+        });
+        let message = serde_json::to_value(update_rx.try_recv().unwrap()).unwrap();
+        assert_eq!(message["element"]["html"], "<p><strong>Done</strong></p>\n");
+        help_state.update_element("intro", UiElement::Text {
+            id: "intro".to_string(),
+            text: "Plain".to_string(),
+        });
+        let elements = serde_json::to_value(state.get_all_elements_for_client()).unwrap();
+        assert!(elements[0].get("html").is_none());
+    }
+
+    #[test]
+    fn test_log_append_bounded() {
+        let state = AppState::new();
+        let job_state = state.scope("job");
+
+        job_state.add_element(UiElement::Log {
+            id: "output".to_string(),
+            lines: vec![LogLine::from("started")],
+            max_lines: 3,
+        });
+
+        let mut update_rx = state.update_tx.subscribe();
+
+        job_state.append_log("output", "step 1\r\nstep 2\n");
+        job_state.append_log("output", LogLine::new(LogLevel::Error, "failed"));
+        // Unknown elements are ignored
+        job_state.append_log("missing", "lost");
+
+        let messages: Vec<String> = std::iter::from_fn(|| update_rx.try_recv().ok())
+            .map(|msg| serde_json::to_string(&msg).unwrap())
+            .collect();
+        assert_eq!(messages, vec![
+            r#"{"type":"log_append","id":"job.output","lines":[{"level":"info","text":"step 1"},{"level":"info","text":"step 2"}]}"#,
+            r#"{"type":"log_append","id":"job.output","lines":[{"level":"error","text":"failed"}]}"#,
+        ]);
+
+        // New clients get the retained lines
+        let elements = serde_json::to_value(state.get_all_elements_for_client()).unwrap();
+        assert_eq!(elements[0]["max_lines"], 3);
+        assert_eq!(elements[0]["lines"], serde_json::json!([
+            { "level": "info", "text": "step 1" },
+            { "level": "info", "text": "step 2" },
+            { "level": "error", "text": "failed" },
+        ]));
+
+        // Initial lines are limited as well
+        job_state.update_element("output", UiElement::Log {
+            id: "output".to_string(),
+            lines: ["a", "b", "c", "d"].into_iter().map(LogLine::from).collect(),
+            max_lines: 2,
+        });
+        let message = serde_json::to_value(update_rx.try_recv().unwrap()).unwrap();
+        assert_eq!(message["element"]["lines"], serde_json::json!([
+            { "level": "info", "text": "c" },
+            { "level": "info", "text": "d" },
+        ]));
+        let elements = serde_json::to_value(state.get_all_elements_for_client()).unwrap();
+        assert_eq!(elements[0]["lines"], message["element"]["lines"]);
+    }
+
+    #[tokio::test]
+    async fn test_combobox_suggestions() {
+        let state = AppState::new();
+        let search_state = state.scope("search");
+
+        let selected = Arc::new(Mutex::new(String::new()));
+        let selected_clone = selected.clone();
+
+        search_state.add_element(UiElement::Combobox {
+            id: "host".to_string(),
+            value: String::new(),
+            on_suggest: Some(Arc::new(Box::new(|query| {
+                Box::pin(async move {
+                    // Short queries are slow, so a newer query finishes first
+                    if query.len() < 3 {
+                        tokio::time::sleep(Duration::from_millis(200)).await;
+                    }
+                    ["web-1", "web-2", "db-1"]
+                        .into_iter()
+                        .filter(|host| host.starts_with(&query))
+                        .map(str::to_string)
+                        .collect()
+                })
+            }))),
+            on_select: Some(Arc::new(Box::new(move |value| {
+                *selected_clone.lock().unwrap() = value.to_string();
+            }))),
+        });
+
+        let (reply_tx, mut reply_rx) = mpsc::unbounded_channel();
+        let mut pending = PendingSuggestions::default();
+
+        // The stale request is cancelled and never answered
+        state.handle_suggest("search.host", "we".to_string(), 1, &reply_tx, &mut pending);
+        state.handle_suggest("search.host", "web".to_string(), 2, &reply_tx, &mut pending);
+        let reply = serde_json::to_string(&reply_rx.recv().await.unwrap()).unwrap();
+        assert_eq!(reply, r#"{"type":"suggestions","id":"search.host","request_id":2,"suggestions":["web-1","web-2"]}"#);
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        assert!(reply_rx.try_recv().is_err());
+
+        // Requests still running when the connection closes are cancelled
+        state.handle_suggest("search.host", "w".to_string(), 3, &reply_tx, &mut pending);
+        drop(pending);
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        assert!(reply_rx.try_recv().is_err());
+
+        state.handle_change("search.host", serde_json::json!("web-2"));
+        assert_eq!(*selected.lock().unwrap(), "web-2");
+        let element_map = state.elements.lock().unwrap();
+        if let Some(UiElement::Combobox { value, .. }) = element_map.get("search.host") {
+            assert_eq!(value, "web-2");
+        } else {
+            panic!("Element search.host not found or has wrong type");
+        }
+    }
+
+    #[test]
+    fn test_custom_element_props_and_events() {
+        let state = AppState::new();
+        let panel_state = state.scope("panel");
+
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let events_clone = events.clone();
+
+        panel_state.add_element(UiElement::Custom {
+            id: "gauge".to_string(),
+            kind: "gauge".to_string(),
+            props: serde_json::json!({ "value": 42, "unit": "%" }),
+            on_event: Some(Arc::new(Box::new(move |payload| {
+                events_clone.lock().unwrap().push(payload);
+            }))),
+        });
+
+        let elements = serde_json::to_value(state.get_all_elements_for_client()).unwrap();
+        assert_eq!(elements[0], serde_json::json!({
+            "kind": "custom",
+            "id": "panel.gauge",
+            "custom_kind": "gauge",
+            "props": { "value": 42, "unit": "%" },
+        }));
+
+        let event = serde_json::from_str::<ClientMessage>(
+            r#"{"type":"custom_event","id":"panel.gauge","payload":{"action":"reset","at":[1,2]}}"#,
+        ).unwrap();
+        if let ClientMessage::CustomEvent { id, payload } = event {
+            state.handle_custom_event(&id, payload);
+        }
+        assert_eq!(*events.lock().unwrap(), vec![serde_json::json!({ "action": "reset", "at": [1, 2] })]);
+
+        // Changing props keeps the handler
+        panel_state.set_custom_props("gauge", serde_json::json!({ "value": 0 }));
+        state.handle_custom_event("panel.gauge", serde_json::json!("ping"));
+        assert_eq!(events.lock().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_labels_serialized() {
+        let state = AppState::new();
+        let settings_state = state.scope("settings");
+
+        settings_state.add_element(UiElement::NumberInput {
+            id: "port".to_string(),
+            value: 8080.0,
+            min: Some(1.0),
+            max: Some(65535.0),
+            step: None,
+            label: Some("Port".to_string()),
+            placeholder: Some("8080".to_string()),
+            tooltip: Some("TCP port to listen on".to_string()),
+            on_change: None,
+        });
+
+        let elements = serde_json::to_value(state.get_all_elements_for_client()).unwrap();
+        assert_eq!(elements[0], serde_json::json!({
+            "kind": "number",
+            "id": "settings.port",
+            "value": 8080.0,
+            "min": 1.0,
+            "max": 65535.0,
+            "step": null,
+            "label": "Port",
+            "placeholder": "8080",
+            "tooltip": "TCP port to listen on",
+        }));
+    }
+
+    #[test]
+    fn test_enabled_and_visible() {
+        let state = AppState::new();
+        let job_state = state.scope("job");
+
+        let clicks = Arc::new(Mutex::new(0));
+        let clicks_clone = clicks.clone();
+
+        job_state.add_element(UiElement::Button {
+            id: "start".to_string(),
+            text: "Start".to_string(),
+            on_click: Some(Arc::new(Box::new(move || {
+                *clicks_clone.lock().unwrap() += 1;
+            }))),
+        });
+        job_state.add_element(UiElement::Input {
+            id: "name".to_string(),
+            value: "build".to_string(),
+            label: None,
+            placeholder: None,
+            tooltip: None,
+            on_input: None,
+        });
+
+        let mut update_rx = state.update_tx.subscribe();
+        job_state.set_enabled("start", false);
+        job_state.set_enabled("start", false);
+        job_state.set_enabled("name", false);
+        job_state.set_visible("name", false);
+        job_state.set_visible("missing", false);
+        assert_eq!(serde_json::to_string(&update_rx.try_recv().unwrap()).unwrap(), r#"{"type":"enabled","id":"job.start","enabled":false}"#);
+        assert_eq!(serde_json::to_string(&update_rx.try_recv().unwrap()).unwrap(), r#"{"type":"enabled","id":"job.name","enabled":false}"#);
+        assert_eq!(serde_json::to_string(&update_rx.try_recv().unwrap()).unwrap(), r#"{"type":"visible","id":"job.name","visible":false}"#);
+        assert!(update_rx.try_recv().is_err());
+
+        // Events for disabled elements are ignored
+        let (reply_tx, _reply_rx) = mpsc::unbounded_channel();
+        let mut pending = PendingSuggestions::default();
+        state.handle_message(ClientMessage::Click { id: "job.start".to_string() }, &reply_tx, &mut pending);
+        state.handle_message(
+            ClientMessage::Input { id: "job.name".to_string(), value: "deploy".to_string() },
+            &reply_tx,
+            &mut pending,
+        );
+        assert_eq!(*clicks.lock().unwrap(), 0);
+        if let Some(UiElement::Input { value, .. }) = state.elements.lock().unwrap().get("job.name") {
+            assert_eq!(value, "build");
+        } else {
+            panic!("Element job.name not found or has wrong type");
+        }
+
+        // New clients get the state with the elements
+        let elements = serde_json::to_value(state.get_all_elements_for_client()).unwrap();
+        let name = elements.as_array().unwrap().iter().find(|e| e["id"] == "job.name").unwrap();
+        assert_eq!(name["enabled"], false);
+        assert_eq!(name["visible"], false);
+
+        job_state.set_enabled("start", true);
+        state.handle_message(ClientMessage::Click { id: "job.start".to_string() }, &reply_tx, &mut pending);
+        assert_eq!(*clicks.lock().unwrap(), 1);
+    }
+
+    #[test]
+    fn test_classes_and_style() {
+        let state = AppState::new();
+        let cards_state = state.scope("cards");
+
+        cards_state.add_element(UiElement::Text {
+            id: "status".to_string(),
+            text: "Failed".to_string(),
+        });
+
+        let mut update_rx = state.update_tx.subscribe();
+        cards_state.add_class("status", "error");
+        cards_state.add_class("status", "error");
+        cards_state.add_class("status", "bold");
+        assert!(!cards_state.toggle_class("status", "bold"));
+        cards_state.remove_class("status", "missing");
+        // Class lists are split on whitespace and empty classes are ignored
+        cards_state.add_class("status", " wide  tall ");
+        assert!(!cards_state.toggle_class("status", "wide\ttall"));
+        cards_state.add_class("status", "");
+        assert!(!cards_state.toggle_class("status", "  "));
+        cards_state.set_style("status", "color", "red");
+        cards_state.set_style("status", "color", "red");
+        cards_state.set_style("status", "margin-left", "4px");
+        cards_state.set_style("status", "margin-left", "");
+        cards_state.add_class("missing", "error");
+        let messages: Vec<String> = std::iter::from_fn(|| update_rx.try_recv().ok())
+            .map(|message| serde_json::to_string(&message).unwrap())
+            .collect();
+        assert_eq!(messages, vec![
+            r#"{"type":"add_class","id":"cards.status","class":"error"}"#,
+            r#"{"type":"add_class","id":"cards.status","class":"bold"}"#,
+            r#"{"type":"remove_class","id":"cards.status","class":"bold"}"#,
+            r#"{"type":"add_class","id":"cards.status","class":"wide"}"#,
+            r#"{"type":"add_class","id":"cards.status","class":"tall"}"#,
+            r#"{"type":"remove_class","id":"cards.status","class":"wide"}"#,
+            r#"{"type":"remove_class","id":"cards.status","class":"tall"}"#,
+            r#"{"type":"style","id":"cards.status","property":"color","value":"red"}"#,
+            r#"{"type":"style","id":"cards.status","property":"margin-left","value":"4px"}"#,
+            r#"{"type":"style","id":"cards.status","property":"margin-left","value":""}"#,
+        ]);
+
+        // Late-joining clients get the same presentation
+        let elements = serde_json::to_value(state.get_all_elements_for_client()).unwrap();
+        assert_eq!(elements[0], serde_json::json!({
+            "kind": "text",
+            "id": "cards.status",
+            "text": "Failed",
+            "classes": ["error"],
+            "style": { "color": "red" },
+        }));
+    }
+
+    #[test]
+    fn test_validation() {
+        let state = AppState::new();
+        let signup_state = state.scope("signup");
+        let other_state = state.scope("other");
+
+        let names = Arc::new(Mutex::new(Vec::new()));
+        let names_clone = names.clone();
+
+        signup_state.add_element(UiElement::Input {
+            id: "name".to_string(),
+            value: String::new(),
+            label: None,
+            placeholder: None,
+            tooltip: None,
+            on_input: Some(Arc::new(Box::new(move |value| {
+                names_clone.lock().unwrap().push(value.to_string());
+            }))),
+        });
+        signup_state.add_element(UiElement::NumberInput {
+            id: "age".to_string(),
+            value: 30.0,
+            min: None,
+            max: None,
+            step: None,
+            label: None,
+            placeholder: None,
+            tooltip: None,
+            on_change: None,
+        });
+        other_state.add_element(UiElement::Checkbox {
+            id: "terms".to_string(),
+            checked: false,
+            label: None,
+            tooltip: None,
+            on_change: None,
+        });
+
+        signup_state.set_validators("name", vec![
+            Validator::Required,
+            Validator::pattern("^[A-Za-z ]+$", "Letters only").unwrap(),
+        ]);
+        signup_state.set_validators("age", vec![Validator::Range { min: Some(18.0), max: None }]);
+        other_state.set_validators("terms", vec![Validator::Required]);
+        assert!(!signup_state.is_valid());
+        assert!(!other_state.is_valid());
+
+        let mut update_rx = state.update_tx.subscribe();
+        state.handle_input("signup.name", "R2-D2");
+        state.handle_input("signup.name", "R2-D2 ");
+        state.handle_input("signup.name", "Ada");
+        state.handle_change("signup.age", serde_json::json!(12));
+        let messages: Vec<String> = std::iter::from_fn(|| update_rx.try_recv().ok())
+            .map(|message| serde_json::to_string(&message).unwrap())
+            .collect();
+        assert_eq!(messages, vec![
+            r#"{"type":"validation","id":"signup.name","error":"Letters only"}"#,
+            r#"{"type":"validation","id":"signup.name","error":null}"#,
+            r#"{"type":"validation","id":"signup.age","error":"Must be at least 18"}"#,
+        ]);
+        // Handlers see invalid values too
+        assert_eq!(*names.lock().unwrap(), vec!["R2-D2", "R2-D2 ", "Ada"]);
+
+        // New clients see the error
+        let elements = serde_json::to_value(state.get_all_elements_for_client()).unwrap();
+        let age = elements.as_array().unwrap().iter().find(|e| e["id"] == "signup.age").unwrap();
+        assert_eq!(age["error"], "Must be at least 18");
+
+        assert!(!signup_state.is_valid());
+        state.handle_change("signup.age", serde_json::json!(18));
+        assert!(signup_state.is_valid());
+        assert!(!state.is_valid());
+
+        // Disabled elements are not validated
+        other_state.set_enabled("terms", false);
+        assert!(state.is_valid());
+
+        // Replacing an element from the server checks the error shown for it again
+        while update_rx.try_recv().is_ok() {}
+        state.handle_input("signup.name", "");
+        signup_state.update_element("name", UiElement::Input {
+            id: "name".to_string(),
+            value: "Grace".to_string(),
+            label: None,
+            placeholder: None,
+            tooltip: None,
+            on_input: None,
+        });
+        let messages: Vec<serde_json::Value> = std::iter::from_fn(|| update_rx.try_recv().ok())
+            .map(|message| serde_json::to_value(message).unwrap())
+            .collect();
+        assert_eq!(messages.len(), 3);
+        assert_eq!(messages[0]["error"], "This field is required");
+        assert_eq!(messages[1], serde_json::json!({ "type": "validation", "id": "signup.name", "error": null }));
+        assert_eq!(messages[2]["type"], "update");
+        assert!(messages[2]["element"].get("error").is_none());
+    }
+
+    #[test]
+    fn test_radio_group_checked() {
+        let state = AppState::new();
+        for (id, checked) in [("small", true), ("large", false)] {
+            state.add_element(UiElement::Radio {
+                id: id.to_string(),
+                name: "size".to_string(),
+                value: id.to_string(),
+                checked,
+                label: None,
+                tooltip: None,
+                on_change: None,
+            });
+        }
+
+        state.handle_change("large", serde_json::json!(true));
+        {
+            let elements = state.elements.lock().unwrap();
+            assert!(matches!(elements.get("small"), Some(UiElement::Radio { checked: false, .. })));
+            assert!(matches!(elements.get("large"), Some(UiElement::Radio { checked: true, .. })));
+        }
+
+        // Required is satisfied by checking any button of the group
+        for id in ["red", "blue"] {
+            state.add_element(UiElement::Radio {
+                id: id.to_string(),
+                name: "color".to_string(),
+                value: id.to_string(),
+                checked: false,
+                label: None,
+                tooltip: None,
+                on_change: None,
+            });
+            state.set_validators(id, vec![Validator::Required]);
+        }
+        assert!(!state.is_valid());
+        let mut update_rx = state.update_tx.subscribe();
+        assert!(!state.validate_element("red"));
+        assert!(!state.validate_element("blue"));
+        state.handle_change("blue", serde_json::json!(true));
+        assert!(state.is_valid());
+        let messages: Vec<String> = std::iter::from_fn(|| update_rx.try_recv().ok())
+            .map(|message| serde_json::to_string(&message).unwrap())
+            .collect();
+        assert_eq!(messages, vec![
+            r#"{"type":"validation","id":"red","error":"This field is required"}"#,
+            r#"{"type":"validation","id":"blue","error":"This field is required"}"#,
+            r#"{"type":"validation","id":"blue","error":null}"#,
+            r#"{"type":"validation","id":"red","error":null}"#,
+        ]);
+    }
+
+    #[test]
+    fn test_form_submit() {
+        #[derive(Deserialize)]
+        struct Signup {
+            name: String,
+            age: f64,
+            size: Option<String>,
+        }
+
+        let state = AppState::new();
+        let signup_state = state.scope("signup");
+
+        let submitted = Arc::new(Mutex::new(Vec::new()));
+        let submitted_clone = submitted.clone();
+        let inputs = Arc::new(Mutex::new(Vec::new()));
+
+        state.add_element(UiElement::Form {
+            id: "signup".to_string(),
+            on_submit: Some(Arc::new(Box::new(move |values| {
+                submitted_clone.lock().unwrap().push(values);
+            }))),
+        });
+        for id in ["name", "secret"] {
+            let inputs = inputs.clone();
+            signup_state.add_element(UiElement::Input {
+                id: id.to_string(),
+                value: String::new(),
+                label: None,
+                placeholder: None,
+                tooltip: None,
+                on_input: Some(Arc::new(Box::new(move |value| {
+                    inputs.lock().unwrap().push(value.to_string());
+                }))),
+            });
+        }
+        signup_state.add_element(UiElement::NumberInput {
+            id: "age".to_string(),
+            value: 30.0,
+            min: None,
+            max: None,
+            step: None,
+            label: None,
+            placeholder: None,
+            tooltip: None,
+            on_change: None,
+        });
+        for (id, checked) in [("small", true), ("large", false)] {
+            signup_state.add_element(UiElement::Radio {
+                id: id.to_string(),
+                name: "size".to_string(),
+                value: id.to_string(),
+                checked,
+                label: None,
+                tooltip: None,
+                on_change: None,
+            });
+        }
+        signup_state.set_validators("name", vec![Validator::Required]);
+        signup_state.set_enabled("secret", false);
+
+        let elements = serde_json::to_value(state.get_all_elements_for_client()).unwrap();
+        let form = elements.as_array().unwrap().iter().find(|e| e["id"] == "signup").unwrap();
+        assert_eq!(*form, serde_json::json!({ "kind": "form", "id": "signup" }));
+
+        // Invalid forms are not submitted, and the errors are shown
+        let mut update_rx = state.update_tx.subscribe();
+        let (reply_tx, _reply_rx) = mpsc::unbounded_channel();
+        let mut pending = PendingSuggestions::default();
+        let message = r#"{"type":"submit","id":"signup","values":{"name":"","age":30}}"#;
+        state.handle_message(serde_json::from_str(message).unwrap(), &reply_tx, &mut pending);
+        assert!(submitted.lock().unwrap().is_empty());
+        assert_eq!(
+            serde_json::to_string(&update_rx.try_recv().unwrap()).unwrap(),
+            r#"{"type":"validation","id":"signup.name","error":"This field is required"}"#
+        );
+
+        // Values come from the elements; disabled and unknown keys are ignored
+        let mut other_rx = state.update_tx.subscribe();
+        let message = r#"{"type":"submit","id":"signup","values":{
+            "name":"Ada","age":41,"small":false,"large":true,"secret":"x","extra":1
+        }}"#;
+        state.handle_message(serde_json::from_str(message).unwrap(), &reply_tx, &mut pending);
+
+        // Submitted values run the element handlers and are shown in other clients
+        assert_eq!(*inputs.lock().unwrap(), vec!["Ada"]);
+        let mut updated: Vec<(String, serde_json::Value)> = std::iter::from_fn(|| other_rx.try_recv().ok())
+            .map(|message| serde_json::to_value(message).unwrap())
+            .filter(|message| message["type"] == "update")
+            .map(|message| (message["id"].as_str().unwrap().to_string(), message["element"].clone()))
+            .collect();
+        updated.sort_by(|a, b| a.0.cmp(&b.0));
+        let ids: Vec<&str> = updated.iter().map(|(id, _)| id.as_str()).collect();
+        assert_eq!(ids, vec!["signup.age", "signup.large", "signup.name", "signup.small"]);
+        assert_eq!(updated[2].1["value"], "Ada");
+        assert_eq!(updated[3].1["checked"], false);
+
+        let submitted = submitted.lock().unwrap();
+        assert_eq!(submitted.len(), 1);
+        assert_eq!(submitted[0], HashMap::from([
+            ("name".to_string(), serde_json::json!("Ada")),
+            ("age".to_string(), serde_json::json!(41.0)),
+            ("size".to_string(), serde_json::json!("large")),
+        ]));
+        let signup: Signup = form_values(submitted[0].clone()).unwrap();
+        assert_eq!((signup.name.as_str(), signup.age, signup.size.as_deref()), ("Ada", 41.0, Some("large")));
+        assert!(state.is_valid());
+    }
+
+    #[tokio::test]
+    async fn test_dialogs() {
+        tokio::time::pause();
+        let state = AppState::new();
+
+        // Without connected clients nobody can answer
+        assert!(!state.confirm("Title", "No clients").await);
+
+        state.client_connected();
+        let mut update_rx = state.update_tx.subscribe();
+
+        let state_for_confirm = state.clone();
+        let confirm = tokio::spawn(async move { state_for_confirm.confirm("Delete?", "Really?").await });
+        let message = serde_json::to_value(update_rx.recv().await.unwrap()).unwrap();
+        assert_eq!(message["type"], "dialog");
+        assert_eq!(message["dialog_type"], "confirm");
+        assert_eq!(message["title"], "Delete?");
+        assert_eq!(state.pending_dialog_messages().len(), 1);
+
+        let dialog_id = message["dialog_id"].as_u64().unwrap();
+        state.handle_dialog_response(dialog_id, serde_json::json!(true));
+        assert!(confirm.await.unwrap());
+
+        // Answered dialogs are closed in all clients
+        let message = serde_json::to_value(update_rx.recv().await.unwrap()).unwrap();
+        assert_eq!(message, serde_json::json!({ "type": "dialog_close", "dialog_id": dialog_id }));
+        assert!(state.pending_dialog_messages().is_empty());
+
+        let state_for_prompt = state.scope("form");
+        let prompt = tokio::spawn(async move { state_for_prompt.prompt("Name", "Enter a name", "Bob").await });
+        let message = serde_json::to_value(update_rx.recv().await.unwrap()).unwrap();
+        assert_eq!(message["default_value"], "Bob");
+
+        // Dialogs stay open while the page reloads
+        state.client_disconnected();
+        tokio::time::sleep(DIALOG_RECONNECT_GRACE / 2).await;
+        state.client_connected();
+        state.client_disconnected();
+        tokio::time::sleep(DIALOG_RECONNECT_GRACE / 2 + Duration::from_secs(1)).await;
+        assert_eq!(state.pending_dialog_messages().len(), 1);
+
+        // They are cancelled once nobody reconnects in time
+        tokio::time::sleep(DIALOG_RECONNECT_GRACE).await;
+        assert_eq!(prompt.await.unwrap(), None);
+        assert!(state.pending_dialog_messages().is_empty());
+    }
+
+    #[test]
+    fn test_notifications() {
+        let state = AppState::new();
+        state.client_connected();
+        let mut update_rx = state.update_tx.subscribe();
+
+        state.notify(NotificationLevel::Info, "Saved", Some(Duration::from_millis(1500)));
+        let message = serde_json::to_value(update_rx.try_recv().unwrap()).unwrap();
+        assert_eq!(message["type"], "notify");
+        assert_eq!(message["level"], "info");
+        assert_eq!(message["message"], "Saved");
+        assert_eq!(message["duration_ms"], 1500);
+        assert_eq!(message["actions"], serde_json::json!([]));
+
+        let undone = Arc::new(Mutex::new(0));
+        let undone_clone = undone.clone();
+        state.notify_with_actions(NotificationLevel::Warning, "Deleted", None, vec![
+            NotificationAction { label: "Keep".to_string(), on_click: None },
+            NotificationAction {
+                label: "Undo".to_string(),
+                on_click: Some(Arc::new(Box::new(move || {
+                    *undone_clone.lock().unwrap() += 1;
+                }))),
+            },
+        ]);
+        let message = serde_json::to_value(update_rx.try_recv().unwrap()).unwrap();
+        assert_eq!(message["duration_ms"], serde_json::Value::Null);
+        assert_eq!(message["actions"], serde_json::json!(["Keep", "Undo"]));
+
+        // The first action wins and closes the notification in all clients
+        let notification_id = message["notification_id"].as_u64().unwrap();
+        state.handle_notification(notification_id, Some(1));
+        state.handle_notification(notification_id, Some(1));
+        assert_eq!(*undone.lock().unwrap(), 1);
+
+        let messages: Vec<String> = std::iter::from_fn(|| update_rx.try_recv().ok())
+            .map(|msg| serde_json::to_string(&msg).unwrap())
+            .collect();
+        assert_eq!(messages, vec![format!(r#"{{"type":"notification_close","notification_id":{}}}"#, notification_id)]);
+
+        // Unanswered notifications are closed once too many are open
+        let undo = || vec![NotificationAction { label: "Undo".to_string(), on_click: None }];
+        for _ in 0..MAX_PENDING_NOTIFICATIONS + 2 {
+            state.notify_with_actions(NotificationLevel::Info, "Moved", None, undo());
+        }
+        let messages: Vec<serde_json::Value> = std::iter::from_fn(|| update_rx.try_recv().ok())
+            .map(|msg| serde_json::to_value(msg).unwrap())
+            .collect();
+        let ids = |kind: &str| -> Vec<u64> {
+            messages
+                .iter()
+                .filter(|msg| msg["type"] == kind)
+                .map(|msg| msg["notification_id"].as_u64().unwrap())
+                .collect()
+        };
+        assert_eq!(ids("notification_close"), ids("notify")[..2]);
+        assert_eq!(state.notifications.lock().unwrap().len(), MAX_PENDING_NOTIFICATIONS);
+
+        // Without browsers nobody can answer, so the handlers are not kept
+        state.client_disconnected();
+        state.notify_with_actions(NotificationLevel::Info, "Moved", None, undo());
+        assert_eq!(state.notifications.lock().unwrap().len(), MAX_PENDING_NOTIFICATIONS);
+    }
+
+    // Test helper: Start a web server on a random port and wait for it to be ready
+    async fn start_test_server(state: AppState, html: &str, title: &str) -> u16 {
+        let config = RouterConfig::new(state, html).title(title);
+        let app = create_router(config);
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+            .await
+            .expect("Failed to bind");
+        let port = listener.local_addr().expect("Failed to get address").port();
+
+        tokio::spawn(async move {
+            axum::serve(listener, app).await.unwrap();
+        });
+
+        // Wait for server to be ready by polling HTTP endpoint
+        let url = format!("http://127.0.0.1:{}", port);
+        let client = reqwest::Client::new();
+        for _ in 0..10 {
+            if client.get(&url).send().await.is_ok() {
+                return port;
+            }
+            tokio::time::sleep(tokio::time::Duration::from_millis(50)).await;
+        }
+        panic!("Server failed to start");
+    }
+
+    #[tokio::test]
+    async fn test_image_served_with_version() {
+        let state = AppState::new();
+        let plots_state = state.scope("plots");
+
+        plots_state.add_element(UiElement::Image {
+            id: "chart".to_string(),
+            source: ImageSource::bytes(vec![1, 2, 3], "image/png"),
+            alt: "Chart".to_string(),
+        });
+
+        let port = start_test_server(state.clone(), "", "Image Test").await;
+        let url = format!("http://127.0.0.1:{}/image/plots.chart", port);
+        let client = reqwest::Client::new();
+
+        let response = client.get(&url).send().await.unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::OK);
+        assert_eq!(response.headers()["content-type"], "image/png");
+        let etag = response.headers()["etag"].clone();
+        assert_eq!(response.bytes().await.unwrap().as_ref(), &[1, 2, 3]);
+
+        // Unchanged images are revalidated without resending the data
+        let response = client.get(&url).header("if-none-match", etag.clone()).send().await.unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::NOT_MODIFIED);
+
+        // New bytes produce a new version and are served at the same URL
+        let mut update_rx = state.update_tx.subscribe();
+        plots_state.update_element(
+            "chart",
+            UiElement::Image {
+                id: "chart".to_string(),
+                source: ImageSource::bytes(vec![4, 5], "image/png"),
+                alt: "Chart".to_string(),
+            },
+        );
+        let message = serde_json::to_value(update_rx.try_recv().unwrap()).unwrap();
+        assert_ne!(format!("\"{}\"", message["element"]["version"].as_str().unwrap()), etag.to_str().unwrap());
+
+        let response = client.get(&url).header("if-none-match", etag).send().await.unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::OK);
+        assert_eq!(response.bytes().await.unwrap().as_ref(), &[4, 5]);
+
+        let response = client.get(format!("http://127.0.0.1:{}/image/missing", port)).send().await.unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::NOT_FOUND);
+
+        // File versions are computed once, not on every serialization
+        let path = std::env::temp_dir().join(format!("webui-image-{}.png", std::process::id()));
+        std::fs::write(&path, [1, 2, 3]).unwrap();
+        let source = ImageSource::path(&path);
+        let version = serde_json::to_value(&source).unwrap();
+        plots_state.update_element("chart", UiElement::Image {
+            id: "chart".to_string(),
+            source: source.clone(),
+            alt: "Chart".to_string(),
+        });
+        let response = client.get(&url).send().await.unwrap();
+        let etag = response.headers()["etag"].clone();
+        assert_eq!(response.bytes().await.unwrap().as_ref(), &[1, 2, 3]);
+
+        // A changed file is served with its new content
+        std::fs::write(&path, [6]).unwrap();
+        let file = std::fs::File::options().write(true).open(&path).unwrap();
+        file.set_modified(SystemTime::now() + Duration::from_secs(10)).unwrap();
+        let response = client.get(&url).header("if-none-match", etag).send().await.unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::OK);
+        assert_eq!(response.bytes().await.unwrap().as_ref(), &[6]);
+
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(serde_json::to_value(&source).unwrap(), version);
+        assert_ne!(serde_json::to_value(ImageSource::path(&path)).unwrap(), version);
+    }
+
+    #[tokio::test]
+    async fn test_file_upload_route() {
+        let state = AppState::new();
+
+        // Track uploaded files
+        let uploads = Arc::new(Mutex::new(Vec::new()));
+        let uploads_clone = uploads.clone();
+
+        state.add_element(UiElement::FileUpload {
+            id: "csv".to_string(),
+            accept: Some(".csv".to_string()),
+            multiple: true,
+            max_size: Some(4 * 1024 * 1024),
+            on_upload: Some(Arc::new(Box::new(move |file: UploadedFile| {
+                let uploads = uploads_clone.clone();
+                Box::pin(async move {
+                    let name = file.name.clone();
+                    let mime_type = file.mime_type.clone();
+                    let data = file.bytes().await.map_err(|err| err.to_string())?;
+                    if name.ends_with(".bad") {
+                        return Err("Not a CSV file".to_string());
+                    }
+                    uploads.lock().unwrap().push((name, mime_type, data.len()));
+                    Ok(())
+                })
+            }))),
+        });
+
+        let port = start_test_server(state, "", "Upload Test").await;
+        let client = reqwest::Client::new();
+        let upload = |name: &str, data: Vec<u8>| {
+            client
+                .post(format!("http://127.0.0.1:{}/upload/csv?name={}", port, name))
+                .header("content-type", "text/csv")
+                .body(data)
+                .send()
+        };
+
+        // Bodies larger than axum's default extractor limit are streamed through
+        let response = upload("big%20file.csv", vec![b'x'; 3 * 1024 * 1024]).await.unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::OK);
+
+        let response = upload("huge.csv", vec![b'x'; 5 * 1024 * 1024]).await.unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::PAYLOAD_TOO_LARGE);
+
+        let response = upload("data.bad", b"a,b".to_vec()).await.unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(response.text().await.unwrap(), "Not a CSV file");
+
+        assert_eq!(*uploads.lock().unwrap(), vec![
+            ("big file.csv".to_string(), "text/csv".to_string(), 3 * 1024 * 1024),
+        ]);
+    }
+
+    #[tokio::test]
+    async fn test_download_route() {
+        let state = AppState::new();
+        let port = start_test_server(state.clone(), "", "Download Test").await;
+        let client = reqwest::Client::new();
+
+        let (reply_tx, mut reply_rx) = mpsc::unbounded_channel();
+        let browser = ClientHandle { reply_tx };
+        state.scope("reports").download_to(&browser, "a,b\n1,2\n", "report 1.csv", "text/csv");
+
+        let message = serde_json::to_value(reply_rx.try_recv().unwrap()).unwrap();
+        assert_eq!(message["type"], "download");
+        assert_eq!(message["file_name"], "report 1.csv");
+        let url = format!("http://127.0.0.1:{}{}", port, message["url"].as_str().unwrap());
+
+        let response = client.get(&url).send().await.unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::OK);
+        assert_eq!(response.headers()["content-type"], "text/csv");
+        assert_eq!(
+            response.headers()["content-disposition"],
+            "attachment; filename=\"report 1.csv\"; filename*=UTF-8''report%201.csv"
+        );
+        assert_eq!(response.text().await.unwrap(), "a,b\n1,2\n");
+
+        // Downloads can only be fetched once
+        let response = client.get(&url).send().await.unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::NOT_FOUND);
+
+        // Expired downloads are removed
+        state.downloads.lock().unwrap().clear();
+        state.download_to(&browser, "old", "old.txt", "text/plain");
+        assert_eq!(state.downloads.lock().unwrap().len(), 1);
+        let later = Instant::now() + DOWNLOAD_TTL + Duration::from_secs(1);
+        assert!(state.take_download("unknown", later).is_none());
+        assert!(state.downloads.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_download_to_client() {
+        let state = AppState::new();
+        let mut update_rx = state.update_tx.subscribe();
+        let (reply_tx, mut reply_rx) = mpsc::unbounded_channel();
+        let client = ClientHandle { reply_tx };
+
+        // Downloads started by a handler only go to the browser that sent the event
+        CURRENT_CLIENT.sync_scope(client.clone(), || {
+            assert!(state.current_client().is_some());
+            assert!(state.download("a", "a.txt", "text/plain"));
+        });
+        state.download_to(&client, "b", "b.txt", "text/plain");
+        // Outside handlers there is no browser to send the file to
+        assert!(state.current_client().is_none());
+        assert!(!state.download("c", "c.txt", "text/plain"));
+        assert!(update_rx.try_recv().is_err());
+        let names: Vec<String> = std::iter::from_fn(|| reply_rx.try_recv().ok())
+            .map(|message| serde_json::to_value(message).unwrap()["file_name"].as_str().unwrap().to_string())
+            .collect();
+        assert_eq!(names, vec!["a.txt", "b.txt"]);
+        assert_eq!(state.downloads.lock().unwrap().len(), 2);
+
+        let tokens: HashSet<String> = (0..100).map(|_| download_token()).collect();
+        assert_eq!(tokens.len(), 100);
+        assert!(tokens.iter().all(|token| token.len() == 32));
+    }
+
+    // Test helper: Create browser and navigate to URL in blocking thread
+    async fn create_browser_and_navigate(url: &str) -> (Arc<Browser>, Arc<Tab>) {
+        let url = url.to_string();
+        tokio::task::spawn_blocking(move || {
+            let browser = Browser::default().expect("Failed to launch browser");
+            let tab = browser.new_tab().expect("Failed to create tab");
+            tab.navigate_to(&url).expect("Failed to navigate");
+            tab.wait_for_element("body").expect("Failed to find body");
+            (Arc::new(browser), tab)
+        })
+        .await
+        .expect("Browser task panicked")
+    }
+
+    #[tokio::test]
+    async fn test_button_click_e2e() {
+        let state = AppState::new();
+
+        // Track whether button was clicked
+        let clicked = Arc::new(Mutex::new(false));
+        let clicked_clone = clicked.clone();
+
+        state.add_element(UiElement::Button {
+            id: "test-btn".to_string(),
+            text: "Test Button".to_string(),
+            on_click: Some(Arc::new(Box::new(move || {
+                *clicked_clone.lock().unwrap() = true;
+            }))),
+        });
+
+        let html = r#"<ui-button id="test-btn"></ui-button>"#;
+        let port = start_test_server(state, html, "Button Test").await;
+        let url = format!("http://127.0.0.1:{}", port);
+
+        let (_browser, tab) = create_browser_and_navigate(&url).await;
+
+        // Click button in blocking thread
+        tokio::task::spawn_blocking(move || {
+            let button = tab.wait_for_element("ui-button#test-btn").expect("Failed to find button");
+            button.click().expect("Failed to click button");
+        })
+        .await
+        .expect("Click task panicked");
+
+        // Wait for click event to propagate through WebSocket
+        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+
+        // Verify the button was clicked
+        assert!(*clicked.lock().unwrap(), "Button click handler was not called");
+    }
+
+    #[tokio::test]
+    async fn test_input_e2e() {
+        let state = AppState::new();
+
+        // Track input value
+        let input_value = Arc::new(Mutex::new(String::new()));
+        let input_value_clone = input_value.clone();
+
+        state.add_element(UiElement::Input {
+            id: "test-input".to_string(),
+            value: "".to_string(),
+            label: None,
+            placeholder: None,
+            tooltip: None,
+            on_input: Some(Arc::new(Box::new(move |value| {
+                *input_value_clone.lock().unwrap() = value.to_string();
+            }))),
+        });
+
+        let html = r#"<ui-input id="test-input"></ui-input>"#;
+        let port = start_test_server(state, html, "Input Test").await;
+        let url = format!("http://127.0.0.1:{}", port);
+
+        let (_browser, tab) = create_browser_and_navigate(&url).await;
+
+        // Type into input in blocking thread
+        tokio::task::spawn_blocking(move || {
+            let input = tab.wait_for_element("ui-input#test-input input").expect("Failed to find input");
+            input.type_into("Hello World").expect("Failed to type into input");
+        })
+        .await
+        .expect("Input task panicked");
+
+        // Wait for input event to propagate through WebSocket
+        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+
+        // Verify the input handler was called with the correct value
+        let final_value = input_value.lock().unwrap();
+        assert!(!final_value.is_empty(), "Input handler was not called");
+        assert!(final_value.contains("Hello World"),
+            "Input handler received incorrect value: expected 'Hello World', got '{}'",
+            final_value);
+    }
+
+    #[tokio::test]
+    async fn test_checkbox_e2e() {
+        let state = AppState::new();
+
+        // Track checkbox state
+        let checked_state = Arc::new(Mutex::new(false));
+        let checked_state_clone = checked_state.clone();
+
+        state.add_element(UiElement::Checkbox {
+            id: "test-checkbox".to_string(),
+            checked: false,
+            label: None,
+            tooltip: None,
+            on_change: Some(Arc::new(Box::new(move |checked| {
+                *checked_state_clone.lock().unwrap() = checked;
+            }))),
+        });
+
+        let html = r#"<ui-checkbox id="test-checkbox"></ui-checkbox>"#;
+        let port = start_test_server(state, html, "Checkbox Test").await;
+        let url = format!("http://127.0.0.1:{}", port);
+
+        let (_browser, tab) = create_browser_and_navigate(&url).await;
+
+        // Click checkbox in blocking thread
+        tokio::task::spawn_blocking(move || {
+            let checkbox = tab.wait_for_element("ui-checkbox#test-checkbox input").expect("Failed to find checkbox");
+            checkbox.click().expect("Failed to click checkbox");
+        })
+        .await
+        .expect("Checkbox task panicked");
+
+        // Wait for change event to propagate through WebSocket
+        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+
+        // Verify the checkbox was toggled
+        assert!(*checked_state.lock().unwrap(), "Checkbox change handler was not called or incorrect value");
+    }
+
+    #[tokio::test]
+    async fn test_slider_e2e() {
+        let state = AppState::new();
+
+        // Track slider value
+        let slider_value = Arc::new(Mutex::new(0.0));
+        let slider_value_clone = slider_value.clone();
+
+        state.add_element(UiElement::Slider {
+            id: "test-slider".to_string(),
+            value: 0.0,
+            min: 0.0,
+            max: 100.0,
+            step: Some(1.0),
+            label: None,
+            tooltip: None,
+            on_change: Some(Arc::new(Box::new(move |value| {
+                *slider_value_clone.lock().unwrap() = value;
+            }))),
+        });
+
+        let html = r#"<ui-slider id="test-slider"></ui-slider>"#;
+        let port = start_test_server(state, html, "Slider Test").await;
+        let url = format!("http://127.0.0.1:{}", port);
+
+        let (_browser, tab) = create_browser_and_navigate(&url).await;
+
+        // Set slider value in blocking thread
+        tokio::task::spawn_blocking(move || {
+            tab.wait_for_element("ui-slider#test-slider input[type='range']").expect("Failed to find slider");
+            // Set value and trigger change event
+            tab.evaluate("const slider = document.querySelector('ui-slider#test-slider input'); slider.value = 75; slider.dispatchEvent(new Event('change', { bubbles: true }));", false)
+                .expect("Failed to set slider value and dispatch event");
+        })
+        .await
+        .expect("Slider task panicked");
+
+        // Wait for change event to propagate through WebSocket
+        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+
+        // Verify the slider value was changed
+        let final_value = *slider_value.lock().unwrap();
+        assert!(final_value > 0.0, "Slider change handler was not called");
+    }
+
+    #[tokio::test]
+    async fn test_radio_e2e() {
+        let state = AppState::new();
+
+        // Track radio state
+        let radio_checked = Arc::new(Mutex::new(false));
+        let radio_checked_clone = radio_checked.clone();
+
+        state.add_element(UiElement::Radio {
+            id: "test-radio".to_string(),
+            name: "test-group".to_string(),
+            value: "option1".to_string(),
+            checked: false,
+            label: None,
+            tooltip: None,
+            on_change: Some(Arc::new(Box::new(move |checked| {
+                *radio_checked_clone.lock().unwrap() = checked;
+            }))),
+        });
+
+        let html = r#"<ui-radio id="test-radio" name="test-group"></ui-radio>"#;
+        let port = start_test_server(state, html, "Radio Test").await;
+        let url = format!("http://127.0.0.1:{}", port);
+
+        let (_browser, tab) = create_browser_and_navigate(&url).await;
+
+        // Click radio button in blocking thread
+        tokio::task::spawn_blocking(move || {
+            let radio = tab.wait_for_element("ui-radio#test-radio input").expect("Failed to find radio");
+            radio.click().expect("Failed to click radio");
+        })
+        .await
+        .expect("Radio task panicked");
+
+        // Wait for change event to propagate through WebSocket
+        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+
+        // Verify the radio was selected
+        assert!(*radio_checked.lock().unwrap(), "Radio change handler was not called or incorrect value");
+    }
+
+    #[tokio::test]
+    async fn test_number_input_e2e() {
+        let state = AppState::new();
+
+        // Track number value
+        let number_value = Arc::new(Mutex::new(0.0));
+        let number_value_clone = number_value.clone();
+
+        state.add_element(UiElement::NumberInput {
+            id: "test-number".to_string(),
+            value: 0.0,
+            min: Some(0.0),
+            max: Some(100.0),
+            step: Some(1.0),
+            label: None,
+            placeholder: None,
+            tooltip: None,
+            on_change: Some(Arc::new(Box::new(move |value| {
+                *number_value_clone.lock().unwrap() = value;
+            }))),
+        });
+
+        let html = r#"<ui-number id="test-number"></ui-number>"#;
+        let port = start_test_server(state, html, "Number Test").await;
+        let url = format!("http://127.0.0.1:{}", port);
+
+        let (_browser, tab) = create_browser_and_navigate(&url).await;
+
+        // Set number value in blocking thread
+        tokio::task::spawn_blocking(move || {
+            tab.wait_for_element("ui-number#test-number input[type='number']").expect("Failed to find number input");
+            // Set value and trigger change event
+            tab.evaluate("const number = document.querySelector('ui-number#test-number input'); number.value = 42; number.dispatchEvent(new Event('change', { bubbles: true }));", false)
+                .expect("Failed to set number value and dispatch event");
+        })
+        .await
+        .expect("Number input task panicked");
+
+        // Wait for change event to propagate through WebSocket
+        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+
+        // Verify the number value was changed
+        let final_value = *number_value.lock().unwrap();
+        assert!(final_value > 0.0, "Number input change handler was not called");
+        assert!((final_value - 42.0).abs() < 0.01, "Number input received incorrect value: expected 42, got {}", final_value);
+    }
+
+    #[tokio::test]
+    async fn test_select_e2e() {
+        let state = AppState::new();
+
+        // Track selected value
+        let selected_value = Arc::new(Mutex::new(String::new()));
+        let selected_value_clone = selected_value.clone();
+
+        state.add_element(UiElement::Select {
+            id: "test-select".to_string(),
+            options: vec![
+                ("a".to_string(), "Option A".to_string()),
+                ("b".to_string(), "Option B".to_string()),
+            ],
+            selected: Some("a".to_string()),
+            on_change: Some(Arc::new(Box::new(move |value| {
+                *selected_value_clone.lock().unwrap() = value.to_string();
+            }))),
+        });
+
+        let html = r#"<ui-select id="test-select"></ui-select>"#;
+        let port = start_test_server(state, html, "Select Test").await;
+        let url = format!("http://127.0.0.1:{}", port);
+
+        let (_browser, tab) = create_browser_and_navigate(&url).await;
+
+        // Select an option in blocking thread
+        tokio::task::spawn_blocking(move || {
+            tab.wait_for_element("ui-select#test-select option[value='b']").expect("Failed to find select option");
+            // Set value and trigger change event
+            tab.evaluate("const select = document.querySelector('ui-select#test-select select'); select.value = 'b'; select.dispatchEvent(new Event('change', { bubbles: true }));", false)
+                .expect("Failed to set select value and dispatch event");
+        })
+        .await
+        .expect("Select task panicked");
+
+        // Wait for change event to propagate through WebSocket
+        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+
+        // Verify the select handler received the new value
+        assert_eq!(*selected_value.lock().unwrap(), "b", "Select change handler was not called or incorrect value");
+    }
+
+    #[tokio::test]
+    async fn test_textarea_e2e() {
+        let state = AppState::new();
+
+        // Track textarea value
+        let textarea_value = Arc::new(Mutex::new(String::new()));
+        let textarea_value_clone = textarea_value.clone();
+
+        state.add_element(UiElement::TextArea {
+            id: "test-textarea".to_string(),
+            value: "".to_string(),
+            rows: Some(4),
+            cols: None,
+            on_input: Some(Arc::new(Box::new(move |value| {
+                *textarea_value_clone.lock().unwrap() = value.to_string();
+            }))),
+        });
+
+        let html = r#"<ui-textarea id="test-textarea"></ui-textarea>"#;
+        let port = start_test_server(state, html, "TextArea Test").await;
+        let url = format!("http://127.0.0.1:{}", port);
+
+        let (_browser, tab) = create_browser_and_navigate(&url).await;
+
+        // Type multiple lines in blocking thread
+        tokio::task::spawn_blocking(move || {
+            tab.wait_for_element("ui-textarea#test-textarea textarea").expect("Failed to find textarea");
+            tab.evaluate("const textarea = document.querySelector('ui-textarea#test-textarea textarea'); textarea.value = 'line 1\\nline 2'; textarea.dispatchEvent(new Event('input', { bubbles: true }));", false)
+                .expect("Failed to set textarea value and dispatch event");
+        })
+        .await
+        .expect("TextArea task panicked");
+
+        // Wait for input event to propagate through WebSocket
+        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+
+        // Verify the input handler received both lines
+        assert_eq!(*textarea_value.lock().unwrap(), "line 1\nline 2", "TextArea input handler was not called or incorrect value");
+    }
+
+    #[tokio::test]
+    async fn test_table_row_click_e2e() {
+        let state = AppState::new();
+
+        // Track clicked row
+        let clicked_row = Arc::new(Mutex::new(String::new()));
+        let clicked_row_clone = clicked_row.clone();
+
+        state.add_element(UiElement::Table {
+            id: "test-table".to_string(),
+            columns: vec![TableColumn {
+                key: "name".to_string(),
+                label: "Name".to_string(),
+                sortable: false,
+            }],
+            rows: vec![
+                TableRow { key: "row-1".to_string(), cells: vec![serde_json::json!("First")] },
+                TableRow { key: "row-2".to_string(), cells: vec![serde_json::json!("Second")] },
+            ],
+            on_row_click: Some(Arc::new(Box::new(move |row| {
+                *clicked_row_clone.lock().unwrap() = row.to_string();
+            }))),
+            on_sort: None,
+        });
+
+        let html = r#"<ui-table id="test-table"></ui-table>"#;
+        let port = start_test_server(state, html, "Table Test").await;
+        let url = format!("http://127.0.0.1:{}", port);
+
+        let (_browser, tab) = create_browser_and_navigate(&url).await;
+
+        // Click the second row in blocking thread
+        tokio::task::spawn_blocking(move || {
+            let row = tab.wait_for_element("ui-table#test-table tr[data-key='row-2'] td").expect("Failed to find table row");
+            row.click().expect("Failed to click table row");
+        })
+        .await
+        .expect("Table task panicked");
+
+        // Wait for table event to propagate through WebSocket
+        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+
+        // Verify the row click handler received the row key
+        assert_eq!(*clicked_row.lock().unwrap(), "row-2", "Table row click handler was not called or incorrect row");
     }
 
-    #[test]
-    fn test_nested_scopes() {
+    #[tokio::test]
+    async fn test_list_item_click_e2e() {
         let state = AppState::new();
 
-        // Create nested scopes
-        let form_state = state.scope("form");
-        let inputs_state = form_state.scope("inputs");
+        // Track clicked item
+        let clicked_item = Arc::new(Mutex::new(String::new()));
+        let clicked_item_clone = clicked_item.clone();
 
-        // Add element in nested scope
-        inputs_state.add_element(UiElement::Input {
-            id: "name".to_string(),
-            value: "".to_string(),
-            on_input: None,
+        state.add_element(UiElement::List {
+            id: "test-list".to_string(),
+            items: vec![],
+            on_item_click: Some(Arc::new(Box::new(move |key| {
+                *clicked_item_clone.lock().unwrap() = key.to_string();
+            }))),
         });
 
-        // Should be stored with full nested path
-        let element_map = state.elements.lock().unwrap();
-        assert!(element_map.contains_key("form.inputs.name"),
-            "Should contain form.inputs.name");
-    }
+        let html = r#"<ui-list id="test-list"></ui-list>"#;
+        let port = start_test_server(state.clone(), html, "List Test").await;
+        let url = format!("http://127.0.0.1:{}", port);
 
-    #[test]
-    fn test_scope_update_element() {
-        let state = AppState::new();
-        let form_state = state.scope("form");
+        let (_browser, tab) = create_browser_and_navigate(&url).await;
 
-        // Add an element
-        form_state.add_element(UiElement::Text {
-            id: "status".to_string(),
-            text: "Initial".to_string(),
+        // Wait for the client to connect, then insert an item at runtime
+        tokio::time::sleep(tokio::time::Duration::from_millis(200)).await;
+        state.insert_list_item("test-list", 0, ListItem {
+            key: "job-1".to_string(),
+            value: serde_json::json!({ "name": "Backup" }),
         });
 
-        // Update using scoped state
-        form_state.update_element(
-            "status",
-            UiElement::Text {
-                id: "status".to_string(),
-                text: "Updated".to_string(),
-            },
-        );
+        // Click the inserted item in blocking thread
+        tokio::task::spawn_blocking(move || {
+            let item = tab.wait_for_element("ui-list#test-list li[data-key='job-1']").expect("Failed to find list item");
+            item.click().expect("Failed to click list item");
+        })
+        .await
+        .expect("List task panicked");
 
-        // Verify it's stored with full path
-        let element_map = state.elements.lock().unwrap();
-        if let Some(UiElement::Text { text, .. }) = element_map.get("form.status") {
-            assert_eq!(text, "Updated");
-        } else {
-            panic!("Element form.status not found or has wrong type");
-        }
+        // Wait for click event to propagate through WebSocket
+        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+
+        // Verify the item click handler received the item key
+        assert_eq!(*clicked_item.lock().unwrap(), "job-1", "List item click handler was not called or incorrect key");
     }
 
-    // Test helper: Start a web server on a random port and wait for it to be ready
-    async fn start_test_server(state: AppState, html: &str, title: &str) -> u16 {
-        let config = RouterConfig::new(state, html).title(title);
-        let app = create_router(config);
+    #[tokio::test]
+    async fn test_tree_expand_e2e() {
+        let state = AppState::new();
 
-        let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
-            .await
-            .expect("Failed to bind");
-        let port = listener.local_addr().expect("Failed to get address").port();
+        // Track selected node
+        let selected_node = Arc::new(Mutex::new(String::new()));
+        let selected_node_clone = selected_node.clone();
 
-        tokio::spawn(async move {
-            axum::serve(listener, app).await.unwrap();
+        state.add_element(UiElement::Tree {
+            id: "test-tree".to_string(),
+            nodes: vec![TreeNode::branch("root", "Root")],
+            selected: None,
+            on_expand: Some(Arc::new(Box::new(|node_id| {
+                Box::pin(async move { vec![TreeNode::leaf(format!("{}-child", node_id), "Child")] })
+            }))),
+            on_select: Some(Arc::new(Box::new(move |node_id| {
+                *selected_node_clone.lock().unwrap() = node_id.to_string();
+            }))),
         });
 
-        // Wait for server to be ready by polling HTTP endpoint
+        let html = r#"<ui-tree id="test-tree"></ui-tree>"#;
+        let port = start_test_server(state, html, "Tree Test").await;
         let url = format!("http://127.0.0.1:{}", port);
-        let client = reqwest::Client::new();
-        for _ in 0..10 {
-            if client.get(&url).send().await.is_ok() {
-                return port;
-            }
-            tokio::time::sleep(tokio::time::Duration::from_millis(50)).await;
-        }
-        panic!("Server failed to start");
-    }
 
-    // Test helper: Create browser and navigate to URL in blocking thread
-    async fn create_browser_and_navigate(url: &str) -> (Arc<Browser>, Arc<Tab>) {
-        let url = url.to_string();
+        let (_browser, tab) = create_browser_and_navigate(&url).await;
+
+        // Expand the root, then select the loaded child in blocking thread
         tokio::task::spawn_blocking(move || {
-            let browser = Browser::default().expect("Failed to launch browser");
-            let tab = browser.new_tab().expect("Failed to create tab");
-            tab.navigate_to(&url).expect("Failed to navigate");
-            tab.wait_for_element("body").expect("Failed to find body");
-            (Arc::new(browser), tab)
+            let toggle = tab
+                .wait_for_element("ui-tree#test-tree li[data-node='root'] .ui-tree-toggle")
+                .expect("Failed to find tree toggle");
+            toggle.click().expect("Failed to click tree toggle");
+            let child = tab
+                .wait_for_element("ui-tree#test-tree li[data-node='root-child'] .ui-tree-row")
+                .expect("Failed to find loaded child node");
+            child.click().expect("Failed to click child node");
         })
         .await
-        .expect("Browser task panicked")
+        .expect("Tree task panicked");
+
+        // Wait for select event to propagate through WebSocket
+        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+
+        // Verify the select handler received the child node ID
+        assert_eq!(*selected_node.lock().unwrap(), "root-child", "Tree select handler was not called or incorrect node");
     }
 
     #[tokio::test]
-    async fn test_button_click_e2e() {
+    async fn test_combobox_e2e() {
         let state = AppState::new();
 
-        // Track whether button was clicked
-        let clicked = Arc::new(Mutex::new(false));
-        let clicked_clone = clicked.clone();
+        // Track selected suggestion
+        let selected = Arc::new(Mutex::new(String::new()));
+        let selected_clone = selected.clone();
 
-        state.add_element(UiElement::Button {
-            id: "test-btn".to_string(),
-            text: "Test Button".to_string(),
-            on_click: Some(Arc::new(Box::new(move || {
-                *clicked_clone.lock().unwrap() = true;
+        state.add_element(UiElement::Combobox {
+            id: "test-combobox".to_string(),
+            value: String::new(),
+            on_suggest: Some(Arc::new(Box::new(|query| {
+                Box::pin(async move { vec![format!("{}-1", query), format!("{}-2", query)] })
+            }))),
+            on_select: Some(Arc::new(Box::new(move |value| {
+                *selected_clone.lock().unwrap() = value.to_string();
             }))),
         });
 
-        let html = r#"<ui-button id="test-btn"></ui-button>"#;
-        let port = start_test_server(state, html, "Button Test").await;
+        let html = r#"<ui-combobox id="test-combobox"></ui-combobox>"#;
+        let port = start_test_server(state, html, "Combobox Test").await;
         let url = format!("http://127.0.0.1:{}", port);
 
         let (_browser, tab) = create_browser_and_navigate(&url).await;
 
-        // Click button in blocking thread
+        // Type a query and pick the second suggestion in blocking thread
         tokio::task::spawn_blocking(move || {
-            let button = tab.wait_for_element("ui-button#test-btn").expect("Failed to find button");
-            button.click().expect("Failed to click button");
+            let input = tab.wait_for_element("ui-combobox#test-combobox input").expect("Failed to find combobox input");
+            input.click().expect("Failed to focus combobox input");
+            tab.type_str("web").expect("Failed to type query");
+            let suggestion = tab
+                .wait_for_element("ui-combobox#test-combobox li:nth-child(2)")
+                .expect("Failed to find suggestion");
+            suggestion.click().expect("Failed to click suggestion");
         })
         .await
-        .expect("Click task panicked");
+        .expect("Combobox task panicked");
 
-        // Wait for click event to propagate through WebSocket
+        // Wait for change event to propagate through WebSocket
         tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
 
-        // Verify the button was clicked
-        assert!(*clicked.lock().unwrap(), "Button click handler was not called");
+        // Verify the select handler received the suggestion
+        assert_eq!(*selected.lock().unwrap(), "web-2", "Combobox select handler was not called or incorrect value");
     }
 
     #[tokio::test]
-    async fn test_input_e2e() {
+    async fn test_custom_element_e2e() {
         let state = AppState::new();
 
-        // Track input value
-        let input_value = Arc::new(Mutex::new(String::new()));
-        let input_value_clone = input_value.clone();
+        // Track received event payloads
+        let payload = Arc::new(Mutex::new(serde_json::Value::Null));
+        let payload_clone = payload.clone();
 
-        state.add_element(UiElement::Input {
-            id: "test-input".to_string(),
-            value: "".to_string(),
-            on_input: Some(Arc::new(Box::new(move |value| {
-                *input_value_clone.lock().unwrap() = value.to_string();
+        state.add_element(UiElement::Custom {
+            id: "test-counter".to_string(),
+            kind: "counter".to_string(),
+            props: serde_json::json!({ "start": 41 }),
+            on_event: Some(Arc::new(Box::new(move |value| {
+                *payload_clone.lock().unwrap() = value;
             }))),
         });
 
-        let html = r#"<ui-input id="test-input"></ui-input>"#;
-        let port = start_test_server(state, html, "Input Test").await;
+        // Registered before the WebUI client initializes
+        let html = r#"<ui-counter id="test-counter"></ui-counter>
+        <script>
+            document.addEventListener('DOMContentLoaded', () => {
+                registerUiElement('counter', class extends UiCustomElement {
+                    setProps(props) {
+                        this.count = props.start;
+                        this.innerHTML = '<button>+</button>';
+                        this.querySelector('button').addEventListener('click', () => {
+                            this.sendEvent({ count: ++this.count });
+                        });
+                    }
+                });
+            });
+        </script>"#;
+        let port = start_test_server(state, html, "Custom Element Test").await;
         let url = format!("http://127.0.0.1:{}", port);
 
         let (_browser, tab) = create_browser_and_navigate(&url).await;
 
-        // Type into input in blocking thread
+        // Click the button rendered by the custom element in blocking thread
         tokio::task::spawn_blocking(move || {
-            let input = tab.wait_for_element("ui-input#test-input input").expect("Failed to find input");
-            input.type_into("Hello World").expect("Failed to type into input");
+            let button = tab.wait_for_element("ui-counter#test-counter button").expect("Failed to find custom element button");
+            button.click().expect("Failed to click custom element button");
         })
         .await
-        .expect("Input task panicked");
+        .expect("Custom element task panicked");
 
-        // Wait for input event to propagate through WebSocket
+        // Wait for event to propagate through WebSocket
         tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
 
-        // Verify the input handler was called with the correct value
-        let final_value = input_value.lock().unwrap();
-        assert!(!final_value.is_empty(), "Input handler was not called");
-        assert!(final_value.contains("Hello World"),
-            "Input handler received incorrect value: expected 'Hello World', got '{}'",
-            final_value);
+        // Verify the event handler received the payload
+        assert_eq!(*payload.lock().unwrap(), serde_json::json!({ "count": 42 }), "Custom event handler was not called or incorrect payload");
     }
 
     #[tokio::test]
-    async fn test_checkbox_e2e() {
+    async fn test_disabled_child_e2e() {
         let state = AppState::new();
+        let form_state = state.scope("test-form");
 
-        // Track checkbox state
-        let checked_state = Arc::new(Mutex::new(false));
-        let checked_state_clone = checked_state.clone();
-
-        state.add_element(UiElement::Checkbox {
-            id: "test-checkbox".to_string(),
-            checked: false,
-            on_change: Some(Arc::new(Box::new(move |checked| {
-                *checked_state_clone.lock().unwrap() = checked;
-            }))),
+        state.add_element(UiElement::Form {
+            id: "test-form".to_string(),
+            on_submit: None,
         });
+        for id in ["locked", "open"] {
+            form_state.add_element(UiElement::Input {
+                id: id.to_string(),
+                value: "".to_string(),
+                label: None,
+                placeholder: None,
+                tooltip: None,
+                on_input: None,
+            });
+        }
+        form_state.set_enabled("locked", false);
 
-        let html = r#"<ui-checkbox id="test-checkbox"></ui-checkbox>"#;
-        let port = start_test_server(state, html, "Checkbox Test").await;
+        let html = r#"<ui-form id="test-form">
+            <ui-input id="locked"></ui-input>
+            <ui-input id="open"></ui-input>
+        </ui-form>"#;
+        let port = start_test_server(state.clone(), html, "Disabled Child Test").await;
         let url = format!("http://127.0.0.1:{}", port);
 
         let (_browser, tab) = create_browser_and_navigate(&url).await;
-
-        // Click checkbox in blocking thread
+        let tab_clone = tab.clone();
         tokio::task::spawn_blocking(move || {
-            let checkbox = tab.wait_for_element("ui-checkbox#test-checkbox input").expect("Failed to find checkbox");
-            checkbox.click().expect("Failed to click checkbox");
+            tab_clone.wait_for_element("ui-input#test-form\\.open input").expect("Failed to find input");
         })
         .await
-        .expect("Checkbox task panicked");
+        .expect("Disabled child task panicked");
 
-        // Wait for change event to propagate through WebSocket
+        // Updating the enabled container must not re-enable its disabled child
+        state.update_element("test-form", UiElement::Form {
+            id: "test-form".to_string(),
+            on_submit: None,
+        });
         tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
 
-        // Verify the checkbox was toggled
-        assert!(*checked_state.lock().unwrap(), "Checkbox change handler was not called or incorrect value");
+        let disabled = tokio::task::spawn_blocking(move || {
+            tab.evaluate(
+                "['locked', 'open'].map(id => document.querySelector(`ui-input[id='test-form.${id}'] input`).disabled).join()",
+                false,
+            )
+            .expect("Failed to read disabled state")
+            .value
+        })
+        .await
+        .expect("Disabled child task panicked");
+
+        assert_eq!(disabled, Some(serde_json::json!("true,false")));
     }
 
     #[tokio::test]
-    async fn test_slider_e2e() {
+    async fn test_form_e2e() {
         let state = AppState::new();
+        let form_state = state.scope("test-form");
 
-        // Track slider value
-        let slider_value = Arc::new(Mutex::new(0.0));
-        let slider_value_clone = slider_value.clone();
+        // Track submitted values
+        let submitted = Arc::new(Mutex::new(Vec::new()));
+        let submitted_clone = submitted.clone();
 
-        state.add_element(UiElement::Slider {
-            id: "test-slider".to_string(),
-            value: 0.0,
-            min: 0.0,
-            max: 100.0,
-            step: Some(1.0),
-            on_change: Some(Arc::new(Box::new(move |value| {
-                *slider_value_clone.lock().unwrap() = value;
+        state.add_element(UiElement::Form {
+            id: "test-form".to_string(),
+            on_submit: Some(Arc::new(Box::new(move |values| {
+                submitted_clone.lock().unwrap().push(values);
             }))),
         });
+        form_state.add_element(UiElement::Input {
+            id: "name".to_string(),
+            value: "".to_string(),
+            label: None,
+            placeholder: None,
+            tooltip: None,
+            on_input: None,
+        });
+        form_state.add_element(UiElement::NumberInput {
+            id: "age".to_string(),
+            value: 30.0,
+            min: None,
+            max: None,
+            step: None,
+            label: None,
+            placeholder: None,
+            tooltip: None,
+            on_change: None,
+        });
+        form_state.add_element(UiElement::Button {
+            id: "send".to_string(),
+            text: "Send".to_string(),
+            on_click: None,
+        });
 
-        let html = r#"<ui-slider id="test-slider"></ui-slider>"#;
-        let port = start_test_server(state, html, "Slider Test").await;
+        let html = r#"<ui-form id="test-form">
+            <ui-input id="name"></ui-input>
+            <ui-number id="age"></ui-number>
+            <ui-button id="send" submit></ui-button>
+        </ui-form>"#;
+        let port = start_test_server(state, html, "Form Test").await;
         let url = format!("http://127.0.0.1:{}", port);
 
         let (_browser, tab) = create_browser_and_navigate(&url).await;
 
-        // Set slider value in blocking thread
+        // Submit with Enter, then with the submit button, in blocking thread
         tokio::task::spawn_blocking(move || {
-            tab.wait_for_element("ui-slider#test-slider input[type='range']").expect("Failed to find slider");
-            // Set value and trigger change event
-            tab.evaluate("const slider = document.querySelector('ui-slider#test-slider input'); slider.value = 75; slider.dispatchEvent(new Event('change', { bubbles: true }));", false)
-                .expect("Failed to set slider value and dispatch event");
+            let input = tab.wait_for_element("ui-input#test-form\\.name input").expect("Failed to find input");
+            input.type_into("Ada").expect("Failed to type into input");
+            tab.press_key("Enter").expect("Failed to press Enter");
+            let button = tab.wait_for_element("ui-button#test-form\\.send button").expect("Failed to find button");
+            button.click().expect("Failed to click button");
         })
         .await
-        .expect("Slider task panicked");
+        .expect("Form task panicked");
 
-        // Wait for change event to propagate through WebSocket
+        // Wait for submit events to propagate through WebSocket
         tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
 
-        // Verify the slider value was changed
-        let final_value = *slider_value.lock().unwrap();
-        assert!(final_value > 0.0, "Slider change handler was not called");
+        let expected: HashMap<String, serde_json::Value> = HashMap::from([
+            ("name".to_string(), serde_json::json!("Ada")),
+            ("age".to_string(), serde_json::json!(30.0)),
+        ]);
+        assert_eq!(*submitted.lock().unwrap(), vec![expected.clone(), expected], "Form was not submitted twice with all values");
     }
 
     #[tokio::test]
-    async fn test_radio_e2e() {
+    async fn test_labels_e2e() {
         let state = AppState::new();
 
-        // Track radio state
-        let radio_checked = Arc::new(Mutex::new(false));
-        let radio_checked_clone = radio_checked.clone();
+        let checked_state = Arc::new(Mutex::new(false));
+        let checked_state_clone = checked_state.clone();
 
-        state.add_element(UiElement::Radio {
-            id: "test-radio".to_string(),
-            name: "test-group".to_string(),
-            value: "option1".to_string(),
+        state.add_element(UiElement::Checkbox {
+            id: "test-terms".to_string(),
             checked: false,
+            label: Some("Accept terms".to_string()),
+            tooltip: Some("Required".to_string()),
             on_change: Some(Arc::new(Box::new(move |checked| {
-                *radio_checked_clone.lock().unwrap() = checked;
+                *checked_state_clone.lock().unwrap() = checked;
             }))),
         });
+        state.add_element(UiElement::Input {
+            id: "test-name".to_string(),
+            value: "".to_string(),
+            label: Some("Name".to_string()),
+            placeholder: Some("Jane Doe".to_string()),
+            tooltip: None,
+            on_input: None,
+        });
 
-        let html = r#"<ui-radio id="test-radio" name="test-group"></ui-radio>"#;
-        let port = start_test_server(state, html, "Radio Test").await;
+        let html = r#"<ui-checkbox id="test-terms"></ui-checkbox><ui-input id="test-name"></ui-input>"#;
+        let port = start_test_server(state, html, "Labels Test").await;
         let url = format!("http://127.0.0.1:{}", port);
 
         let (_browser, tab) = create_browser_and_navigate(&url).await;
 
-        // Click radio button in blocking thread
-        tokio::task::spawn_blocking(move || {
-            let radio = tab.wait_for_element("ui-radio#test-radio input").expect("Failed to find radio");
-            radio.click().expect("Failed to click radio");
+        // Clicking the label toggles the associated checkbox
+        let rendered = tokio::task::spawn_blocking(move || {
+            let label = tab
+                .wait_for_element("ui-checkbox#test-terms label[for='test-terms-control']")
+                .expect("Failed to find checkbox label");
+            label.click().expect("Failed to click label");
+            tab.evaluate(
+                "const name = document.querySelector('ui-input#test-name input'); \
+                 [document.querySelector('label[for=\"' + name.id + '\"]').textContent, name.placeholder, \
+                  document.getElementById('test-terms').title].join()",
+                false,
+            )
+            .expect("Failed to read labels")
+            .value
         })
         .await
-        .expect("Radio task panicked");
+        .expect("Labels task panicked");
 
         // Wait for change event to propagate through WebSocket
         tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
 
-        // Verify the radio was selected
-        assert!(*radio_checked.lock().unwrap(), "Radio change handler was not called or incorrect value");
+        assert_eq!(rendered, Some(serde_json::json!("Name,Jane Doe,Required")));
+        assert!(*checked_state.lock().unwrap(), "Clicking the label did not check the checkbox");
     }
 
     #[tokio::test]
-    async fn test_number_input_e2e() {
+    async fn test_canvas_pointer_e2e() {
         let state = AppState::new();
 
-        // Track number value
-        let number_value = Arc::new(Mutex::new(0.0));
-        let number_value_clone = number_value.clone();
-
-        state.add_element(UiElement::NumberInput {
-            id: "test-number".to_string(),
-            value: 0.0,
-            min: Some(0.0),
-            max: Some(100.0),
-            step: Some(1.0),
-            on_change: Some(Arc::new(Box::new(move |value| {
-                *number_value_clone.lock().unwrap() = value;
+        // Track pointer events
+        let pointer_events = Arc::new(Mutex::new(Vec::new()));
+        let pointer_events_clone = pointer_events.clone();
+
+        state.add_element(UiElement::Canvas {
+            id: "test-canvas".to_string(),
+            width: 200,
+            height: 100,
+            commands: vec![DrawCommand::Rect { x: 0.0, y: 0.0, width: 200.0, height: 100.0, fill: false }],
+            on_pointer: Some(Arc::new(Box::new(move |event| {
+                pointer_events_clone.lock().unwrap().push(event.kind);
             }))),
         });
 
-        let html = r#"<ui-number id="test-number"></ui-number>"#;
-        let port = start_test_server(state, html, "Number Test").await;
+        let html = r#"<ui-canvas id="test-canvas"></ui-canvas>"#;
+        let port = start_test_server(state, html, "Canvas Test").await;
         let url = format!("http://127.0.0.1:{}", port);
 
         let (_browser, tab) = create_browser_and_navigate(&url).await;
 
-        // Set number value in blocking thread
+        // Click the canvas in blocking thread
         tokio::task::spawn_blocking(move || {
-            tab.wait_for_element("ui-number#test-number input[type='number']").expect("Failed to find number input");
-            // Set value and trigger change event
-            tab.evaluate("const number = document.querySelector('ui-number#test-number input'); number.value = 42; number.dispatchEvent(new Event('change', { bubbles: true }));", false)
-                .expect("Failed to set number value and dispatch event");
+            let canvas = tab.wait_for_element("ui-canvas#test-canvas canvas").expect("Failed to find canvas");
+            canvas.click().expect("Failed to click canvas");
         })
         .await
-        .expect("Number input task panicked");
+        .expect("Canvas task panicked");
+
+        // Wait for pointer events to propagate through WebSocket
+        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+
+        // Verify the pointer handler saw the press and release
+        let events = pointer_events.lock().unwrap();
+        assert_eq!(events.first(), Some(&PointerKind::Down), "Canvas pointer handler was not called");
+        assert_eq!(events.last(), Some(&PointerKind::Up), "Canvas pointer release was not reported");
+    }
+
+    #[tokio::test]
+    async fn test_tabs_e2e() {
+        let state = AppState::new();
+
+        state.add_element(UiElement::Tabs {
+            id: "test-tabs".to_string(),
+            tabs: vec![
+                ("first".to_string(), "First".to_string()),
+                ("second".to_string(), "Second".to_string()),
+            ],
+            active: "first".to_string(),
+            on_change: None,
+        });
+
+        let html = r#"<ui-tabs id="test-tabs">
+            <div data-tab="first" id="first-panel">One</div>
+            <div data-tab="second" id="second-panel">Two</div>
+        </ui-tabs>"#;
+        let port = start_test_server(state.clone(), html, "Tabs Test").await;
+        let url = format!("http://127.0.0.1:{}", port);
+
+        let (_browser, tab) = create_browser_and_navigate(&url).await;
+
+        // Click the second tab in blocking thread
+        let hidden = tokio::task::spawn_blocking(move || {
+            let button = tab
+                .wait_for_element("ui-tabs#test-tabs button[data-tab='second']")
+                .expect("Failed to find tab button");
+            button.click().expect("Failed to click tab button");
+            tab.evaluate("[document.getElementById('first-panel').hidden, document.getElementById('second-panel').hidden].join()", false)
+                .expect("Failed to read panel visibility")
+                .value
+        })
+        .await
+        .expect("Tabs task panicked");
 
         // Wait for change event to propagate through WebSocket
         tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
 
-        // Verify the number value was changed
-        let final_value = *number_value.lock().unwrap();
-        assert!(final_value > 0.0, "Number input change handler was not called");
-        assert!((final_value - 42.0).abs() < 0.01, "Number input received incorrect value: expected 42, got {}", final_value);
+        assert_eq!(hidden, Some(serde_json::json!("true,false")));
+        assert_eq!(state.active_tab("test-tabs").as_deref(), Some("second"), "Active tab was not stored");
     }
 
     #[tokio::test]
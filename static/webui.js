@@ -7,6 +7,8 @@
  * 1. Include this script in your HTML: <script src="/static/webui.js"></script>
  * 2. Add UI elements to your HTML: <ui-button id="btn1"></ui-button>
  * 3. The WebUI client will automatically connect and synchronize with the server
+ *
+ * Custom elements for UiElement::Custom are registered with registerUiElement().
  */
 
 // Helper function to get the scope path for an element
@@ -20,6 +22,12 @@ function getElementScopePath(element) {
             if (scopeName) {
                 scopes.unshift(scopeName);
             }
+        } else if (current.tagName && current.tagName.toLowerCase() === 'ui-form') {
+            // A form is the scope of its elements, named by its local ID
+            const scopeName = current._originalId ?? current.id;
+            if (scopeName) {
+                scopes.unshift(scopeName);
+            }
         }
         current = current.parentElement;
     }
@@ -48,6 +56,46 @@ function autoRewriteId(element) {
     }
 }
 
+// Helper function to show a server-controlled label and tooltip for the form control
+// inside an element. The label is linked with <label for> and placed before the control,
+// or after it for checkboxes and radio buttons.
+function setControlLabel(element, control, label, tooltip, labelAfter = false) {
+    control.id = `${element.id}-control`;
+    let labelEl = element.querySelector(':scope > label.ui-label');
+    if (label) {
+        if (!labelEl) {
+            labelEl = document.createElement('label');
+            labelEl.className = 'ui-label';
+            if (labelAfter) {
+                control.after(labelEl);
+            } else {
+                control.before(labelEl);
+            }
+        }
+        labelEl.htmlFor = control.id;
+        labelEl.textContent = label;
+    } else if (labelEl) {
+        labelEl.remove();
+    }
+    if (tooltip) {
+        element.title = tooltip;
+    } else {
+        element.removeAttribute('title');
+    }
+}
+
+// Helper function to find the form controls that belong to an element itself,
+// leaving out those of <ui-*> elements nested inside it
+function ownControls(element) {
+    return Array.from(element.querySelectorAll('input, select, textarea, button')).filter(control => {
+        let host = control.parentElement;
+        while (host && !host.tagName.toLowerCase().startsWith('ui-')) {
+            host = host.parentElement;
+        }
+        return host === element;
+    });
+}
+
 // Custom UI Elements
 
 /**
@@ -135,16 +183,14 @@ class UiText extends HTMLElement {
 class UiInput extends HTMLElement {
     constructor() {
         super();
-        this._label = document.createElement('label');
         this._input = document.createElement('input');
         this._input.type = 'text';
-        this._label.appendChild(this._input);
     }
 
     connectedCallback() {
         autoRewriteId(this);
 
-        this.appendChild(this._label);
+        this.appendChild(this._input);
         this._input.addEventListener('input', () => {
             this.dispatchEvent(new CustomEvent('ui-input', {
                 bubbles: true,
@@ -157,9 +203,16 @@ class UiInput extends HTMLElement {
         this._input.value = value;
     }
 
-    setLabel(label) {
-        const labelText = document.createTextNode(label + ': ');
-        this._label.insertBefore(labelText, this._input);
+    setLabel(label, tooltip) {
+        setControlLabel(this, this._input, label, tooltip);
+    }
+
+    setPlaceholder(placeholder) {
+        this._input.placeholder = placeholder ?? '';
+    }
+
+    get formValue() {
+        return this._input.value;
     }
 }
 
@@ -174,16 +227,14 @@ class UiInput extends HTMLElement {
 class UiCheckbox extends HTMLElement {
     constructor() {
         super();
-        this._label = document.createElement('label');
         this._input = document.createElement('input');
         this._input.type = 'checkbox';
-        this._label.appendChild(this._input);
     }
 
     connectedCallback() {
         autoRewriteId(this);
 
-        this.appendChild(this._label);
+        this.appendChild(this._input);
         this._input.addEventListener('change', () => {
             this.dispatchEvent(new CustomEvent('ui-change', {
                 bubbles: true,
@@ -195,6 +246,14 @@ class UiCheckbox extends HTMLElement {
     setChecked(checked) {
         this._input.checked = checked;
     }
+
+    setLabel(label, tooltip) {
+        setControlLabel(this, this._input, label, tooltip, true);
+    }
+
+    get formValue() {
+        return this._input.checked;
+    }
 }
 
 /**
@@ -232,6 +291,14 @@ class UiSlider extends HTMLElement {
             this._input.step = step;
         }
     }
+
+    setLabel(label, tooltip) {
+        setControlLabel(this, this._input, label, tooltip);
+    }
+
+    get formValue() {
+        return parseFloat(this._input.value);
+    }
 }
 
 /**
@@ -246,16 +313,14 @@ class UiSlider extends HTMLElement {
 class UiRadio extends HTMLElement {
     constructor() {
         super();
-        this._label = document.createElement('label');
         this._input = document.createElement('input');
         this._input.type = 'radio';
-        this._label.appendChild(this._input);
     }
 
     connectedCallback() {
         autoRewriteId(this);
 
-        this.appendChild(this._label);
+        this.appendChild(this._input);
         this._input.addEventListener('change', () => {
             this.dispatchEvent(new CustomEvent('ui-change', {
                 bubbles: true,
@@ -269,6 +334,14 @@ class UiRadio extends HTMLElement {
         this._input.name = name;
         this._input.value = value;
     }
+
+    setLabel(label, tooltip) {
+        setControlLabel(this, this._input, label, tooltip, true);
+    }
+
+    get formValue() {
+        return this._input.checked;
+    }
 }
 
 /**
@@ -310,199 +383,2191 @@ class UiNumber extends HTMLElement {
             this._input.step = step;
         }
     }
-}
 
-// Register custom elements
-customElements.define('ui-scope', UiScope);
-customElements.define('ui-button', UiButton);
-customElements.define('ui-text', UiText);
-customElements.define('ui-input', UiInput);
-customElements.define('ui-checkbox', UiCheckbox);
-customElements.define('ui-slider', UiSlider);
-customElements.define('ui-radio', UiRadio);
-customElements.define('ui-number', UiNumber);
+    setLabel(label, tooltip) {
+        setControlLabel(this, this._input, label, tooltip);
+    }
+
+    setPlaceholder(placeholder) {
+        this._input.placeholder = placeholder ?? '';
+    }
+
+    get formValue() {
+        // NaN for an empty field is sent as null
+        return parseFloat(this._input.value);
+    }
+}
 
 /**
- * WebUIClient - Manages WebSocket connection and UI synchronization
+ * <ui-select> - Dropdown selection
  *
- * Automatically connects to the server, handles reconnection,
- * and synchronizes UI elements.
+ * Attributes:
+ *   id - Unique identifier (required, will be auto-scoped)
+ *
+ * The option list and selected value are synchronized between client and server.
  */
-class WebUIClient {
+class UiSelect extends HTMLElement {
     constructor() {
-        this.ws = null;
-        this.elements = new Map();
-        this.connect();
+        super();
+        this._select = document.createElement('select');
     }
 
-    connect() {
-        const protocol = window.location.protocol === 'https:' ? 'wss:' : 'ws:';
-        this.ws = new WebSocket(`${protocol}//${window.location.host}/ws`);
+    connectedCallback() {
+        autoRewriteId(this);
 
-        this.ws.onopen = () => {
-            console.log('WebUI: Connected');
-            this.updateConnectionStatus(true);
-        };
+        this.appendChild(this._select);
+        this._select.addEventListener('change', () => {
+            this.dispatchEvent(new CustomEvent('ui-change', {
+                bubbles: true,
+                detail: { id: this.id, value: this._select.value }
+            }));
+        });
+    }
 
-        this.ws.onclose = () => {
-            console.log('WebUI: Disconnected');
-            this.updateConnectionStatus(false);
-            setTimeout(() => this.connect(), 2000);
-        };
+    setOptions(options, selected) {
+        this._select.replaceChildren();
+        options.forEach(([value, label]) => {
+            const option = document.createElement('option');
+            option.value = value;
+            option.textContent = label;
+            this._select.appendChild(option);
+        });
+        // No selection is shown as an empty select rather than the first option
+        this._select.value = selected !== null && selected !== undefined ? selected : '';
+    }
 
-        this.ws.onerror = (error) => {
-            console.error('WebUI: Error', error);
-        };
+    get formValue() {
+        return this._select.value || null;
+    }
+}
 
-        this.ws.onmessage = (event) => {
-            const msg = JSON.parse(event.data);
-            this.handleMessage(msg);
-        };
+/**
+ * <ui-textarea> - Multi-line text input
+ *
+ * Attributes:
+ *   id - Unique identifier (required, will be auto-scoped)
+ *
+ * The text is synchronized between client and server. Server updates
+ * preserve the cursor position and selection while the user is editing.
+ */
+class UiTextArea extends HTMLElement {
+    constructor() {
+        super();
+        this._textarea = document.createElement('textarea');
     }
 
-    updateConnectionStatus(connected) {
-        // Update connection indicator if present
-        const statusEl = document.querySelector('.webui-connection-status');
-        if (statusEl) {
-            if (connected) {
-                statusEl.className = 'webui-connection-status connected';
-                statusEl.textContent = 'Connected';
-            } else {
-                statusEl.className = 'webui-connection-status disconnected';
-                statusEl.textContent = 'Disconnected';
-            }
-        }
+    connectedCallback() {
+        autoRewriteId(this);
 
-        // Dispatch event for custom handling
-        document.dispatchEvent(new CustomEvent('webui-connection', {
-            detail: { connected }
-        }));
+        this.appendChild(this._textarea);
+        this._textarea.addEventListener('input', () => {
+            this.dispatchEvent(new CustomEvent('ui-input', {
+                bubbles: true,
+                detail: { id: this.id, value: this._textarea.value }
+            }));
+        });
     }
 
-    handleMessage(msg) {
-        console.log('WebUI: Received', msg);
+    setValue(value, rows, cols) {
+        if (rows !== null && rows !== undefined) {
+            this._textarea.rows = rows;
+        }
+        if (cols !== null && cols !== undefined) {
+            this._textarea.cols = cols;
+        }
+        if (this._textarea.value === value) {
+            return;
+        }
 
-        switch (msg.type) {
-            case 'init':
-                this.initializeUI(msg.elements);
-                break;
-            case 'update':
-                this.updateElement(msg.id, msg.element);
-                break;
+        // Assigning value moves the cursor to the end, so restore it when focused
+        const focused = document.activeElement === this._textarea;
+        const start = this._textarea.selectionStart;
+        const end = this._textarea.selectionEnd;
+        const direction = this._textarea.selectionDirection;
+        this._textarea.value = value;
+        if (focused) {
+            this._textarea.setSelectionRange(
+                Math.min(start, value.length),
+                Math.min(end, value.length),
+                direction
+            );
         }
     }
 
-    initializeUI(elements) {
-        elements.forEach(element => {
-            this.updateElement(element.id, element);
-        });
+    get formValue() {
+        return this._textarea.value;
     }
+}
 
-    updateElement(id, data) {
-        // Find the element in the DOM
-        let el = document.getElementById(id);
+/**
+ * <ui-table> - Data table with sortable columns and clickable rows
+ *
+ * Attributes:
+ *   id - Unique identifier (required, will be auto-scoped)
+ *
+ * Columns and rows are set by the server. Rows can also be appended, updated
+ * and deleted individually. Row clicks and header clicks on sortable columns
+ * are sent to the server; sorting itself is done by the server.
+ */
+class UiTable extends HTMLElement {
+    constructor() {
+        super();
+        this._table = document.createElement('table');
+        this._thead = document.createElement('thead');
+        this._tbody = document.createElement('tbody');
+        this._table.appendChild(this._thead);
+        this._table.appendChild(this._tbody);
+        this._columns = [];
+        this._sort = null;
+    }
 
-        if (!el) {
-            console.warn(`WebUI: Element with id="${id}" not found in DOM`);
-            return;
-        }
+    connectedCallback() {
+        autoRewriteId(this);
+        this.appendChild(this._table);
+    }
 
-        // Store reference
-        this.elements.set(id, el);
+    setTable(columns, rows) {
+        this._columns = columns;
+        this._renderHeader();
+        this._tbody.replaceChildren(...rows.map(row => this._createRow(row)));
+    }
 
-        // Update the element based on its type
-        switch (data.kind) {
-            case 'button':
-                if (el.tagName.toLowerCase() === 'ui-button') {
-                    el.setText(data.text);
-                }
-                break;
-            case 'text':
-                if (el.tagName.toLowerCase() === 'ui-text') {
-                    el.setText(data.text);
-                }
-                break;
-            case 'input':
-                if (el.tagName.toLowerCase() === 'ui-input') {
-                    el.setValue(data.value);
-                }
-                break;
-            case 'checkbox':
-                if (el.tagName.toLowerCase() === 'ui-checkbox') {
-                    el.setChecked(data.checked);
-                }
-                break;
-            case 'slider':
-                if (el.tagName.toLowerCase() === 'ui-slider') {
-                    el.setValue(data.value, data.min, data.max, data.step);
-                }
-                break;
-            case 'radio':
-                if (el.tagName.toLowerCase() === 'ui-radio') {
-                    el.setChecked(data.checked, data.name, data.value);
-                }
-                break;
-            case 'number':
-                if (el.tagName.toLowerCase() === 'ui-number') {
-                    el.setValue(data.value, data.min, data.max, data.step);
-                }
-                break;
+    appendRow(row) {
+        this._tbody.appendChild(this._createRow(row));
+    }
+
+    updateRow(row) {
+        const existing = this._findRow(row.key);
+        if (existing) {
+            existing.replaceWith(this._createRow(row));
         }
     }
 
-    sendClick(id) {
-        this.send({
-            type: 'click',
-            id: id
-        });
+    deleteRow(key) {
+        const existing = this._findRow(key);
+        if (existing) {
+            existing.remove();
+        }
     }
 
-    sendInput(id, value) {
-        this.send({
-            type: 'input',
-            id: id,
-            value: value
+    _findRow(key) {
+        return Array.from(this._tbody.rows).find(tr => tr.dataset.key === key);
+    }
+
+    _renderHeader() {
+        const tr = document.createElement('tr');
+        this._columns.forEach(column => {
+            const th = document.createElement('th');
+            th.textContent = column.label;
+            if (column.sortable) {
+                th.classList.add('sortable');
+                if (this._sort && this._sort.column === column.key) {
+                    th.classList.add(this._sort.ascending ? 'sort-asc' : 'sort-desc');
+                }
+                th.addEventListener('click', () => {
+                    // Clicking the current sort column toggles its direction
+                    const ascending = !(this._sort && this._sort.column === column.key && this._sort.ascending);
+                    this._sort = { column: column.key, ascending };
+                    this._renderHeader();
+                    this._sendEvent({ kind: 'sort', column: column.key, ascending });
+                });
+            }
+            tr.appendChild(th);
         });
+        this._thead.replaceChildren(tr);
     }
 
-    sendChange(id, value) {
-        this.send({
-            type: 'change',
-            id: id,
-            value: value
+    _createRow(row) {
+        const tr = document.createElement('tr');
+        tr.dataset.key = row.key;
+        row.cells.forEach(cell => {
+            const td = document.createElement('td');
+            td.textContent = formatCell(cell);
+            tr.appendChild(td);
         });
+        tr.addEventListener('click', () => {
+            this._sendEvent({ kind: 'row_click', row: row.key });
+        });
+        return tr;
     }
 
-    send(msg) {
-        if (this.ws && this.ws.readyState === WebSocket.OPEN) {
-            this.ws.send(JSON.stringify(msg));
-        }
+    _sendEvent(event) {
+        this.dispatchEvent(new CustomEvent('ui-table', {
+            bubbles: true,
+            detail: { id: this.id, event }
+        }));
     }
 }
 
-// Auto-initialize when DOM is ready
-let webuiClient;
+/**
+ * <ui-list> - List of items with incremental updates
+ *
+ * Attributes:
+ *   id - Unique identifier (required, will be auto-scoped)
+ *
+ * Items are set by the server and can be inserted, removed and moved
+ * individually. Item clicks are sent to the server with the item key.
+ */
+class UiList extends HTMLElement {
+    constructor() {
+        super();
+        this._list = document.createElement('ul');
+    }
 
-if (document.readyState === 'loading') {
-    document.addEventListener('DOMContentLoaded', initWebUI);
-} else {
-    initWebUI();
-}
+    connectedCallback() {
+        autoRewriteId(this);
+        this.appendChild(this._list);
+    }
 
-function initWebUI() {
-    // Initialize client
-    webuiClient = new WebUIClient();
+    setItems(items) {
+        this._list.replaceChildren(...items.map(item => this._createItem(item)));
+    }
 
-    // Handle UI events
-    document.addEventListener('ui-click', (e) => {
-        webuiClient.sendClick(e.detail.id);
-    });
+    insertItem(index, item) {
+        this._list.insertBefore(this._createItem(item), this._list.children[index] || null);
+    }
 
-    document.addEventListener('ui-input', (e) => {
-        webuiClient.sendInput(e.detail.id, e.detail.value);
+    removeItem(key) {
+        const existing = this._findItem(key);
+        if (existing) {
+            existing.remove();
+        }
+    }
+
+    moveItem(key, index) {
+        const existing = this._findItem(key);
+        if (existing) {
+            // Index refers to the list without the moved item
+            existing.remove();
+            this._list.insertBefore(existing, this._list.children[index] || null);
+        }
+    }
+
+    _findItem(key) {
+        return Array.from(this._list.children).find(li => li.dataset.key === key);
+    }
+
+    _createItem(item) {
+        const li = document.createElement('li');
+        li.dataset.key = item.key;
+        if (item.value !== null && typeof item.value === 'object' && !Array.isArray(item.value)) {
+            // Records show each field in its own span, styleable by field name
+            Object.entries(item.value).forEach(([field, value]) => {
+                const span = document.createElement('span');
+                span.className = `field-${field}`;
+                span.textContent = formatCell(value);
+                li.appendChild(span);
+            });
+        } else {
+            li.textContent = formatCell(item.value);
+        }
+        li.addEventListener('click', () => {
+            this.dispatchEvent(new CustomEvent('ui-item-click', {
+                bubbles: true,
+                detail: { id: this.id, key: item.key }
+            }));
+        });
+        return li;
+    }
+}
+
+/**
+ * <ui-image> - Image served by the WebUI server
+ *
+ * Attributes:
+ *   id - Unique identifier (required, will be auto-scoped)
+ *
+ * The image is loaded from /image/{id}. The server sends a version that changes
+ * with the image content, so new images are fetched without stale caching.
+ */
+class UiImage extends HTMLElement {
+    constructor() {
+        super();
+        this._img = document.createElement('img');
+    }
+
+    connectedCallback() {
+        autoRewriteId(this);
+        this.appendChild(this._img);
+    }
+
+    setImage(version, alt) {
+        const src = `/image/${encodeURIComponent(this.id)}?v=${encodeURIComponent(version)}`;
+        if (this._img.getAttribute('src') !== src) {
+            this._img.src = src;
+        }
+        this._img.alt = alt;
+    }
+}
+
+/**
+ * <ui-progress> - Progress bar
+ *
+ * Attributes:
+ *   id - Unique identifier (required, will be auto-scoped)
+ *
+ * A null value shows an indeterminate progress bar.
+ */
+class UiProgress extends HTMLElement {
+    constructor() {
+        super();
+        this._progress = document.createElement('progress');
+    }
+
+    connectedCallback() {
+        autoRewriteId(this);
+        this.appendChild(this._progress);
+    }
+
+    setProgress(value, max) {
+        this._progress.max = max;
+        this.setValue(value);
+    }
+
+    setValue(value) {
+        if (value === null || value === undefined) {
+            this._progress.removeAttribute('value');
+        } else {
+            this._progress.value = value;
+        }
+    }
+}
+
+/**
+ * <ui-meter> - Gauge for a value within a known range
+ *
+ * Attributes:
+ *   id - Unique identifier (required, will be auto-scoped)
+ */
+class UiMeter extends HTMLElement {
+    constructor() {
+        super();
+        this._meter = document.createElement('meter');
+    }
+
+    connectedCallback() {
+        autoRewriteId(this);
+        this.appendChild(this._meter);
+    }
+
+    setMeter(data) {
+        this._meter.min = data.min;
+        this._meter.max = data.max;
+        ['low', 'high', 'optimum'].forEach(attr => {
+            if (data[attr] !== null && data[attr] !== undefined) {
+                this._meter[attr] = data[attr];
+            } else {
+                this._meter.removeAttribute(attr);
+            }
+        });
+        this._meter.value = data.value;
+    }
+
+    setValue(value) {
+        this._meter.value = value;
+    }
+}
+
+/**
+ * <ui-file-upload> - File picker that uploads files to the server
+ *
+ * Attributes:
+ *   id - Unique identifier (required, will be auto-scoped)
+ *
+ * Each selected file is uploaded to /upload/{id} in its own request. Upload
+ * progress and the server's response are shown inside the element.
+ */
+class UiFileUpload extends HTMLElement {
+    constructor() {
+        super();
+        this._input = document.createElement('input');
+        this._input.type = 'file';
+        this._progress = document.createElement('progress');
+        this._progress.hidden = true;
+        this._status = document.createElement('span');
+        this._status.className = 'status';
+        this._maxSize = null;
+    }
+
+    connectedCallback() {
+        autoRewriteId(this);
+
+        this.appendChild(this._input);
+        this.appendChild(this._progress);
+        this.appendChild(this._status);
+        this._input.addEventListener('change', async () => {
+            const files = Array.from(this._input.files);
+            this._input.disabled = true;
+            for (const file of files) {
+                await this._upload(file);
+            }
+            // The server may have disabled the element meanwhile
+            this._input.disabled = this.classList.contains('webui-disabled');
+            this._input.value = '';
+        });
+    }
+
+    setOptions(accept, multiple, maxSize) {
+        if (accept !== null && accept !== undefined) {
+            this._input.accept = accept;
+        } else {
+            this._input.removeAttribute('accept');
+        }
+        this._input.multiple = multiple;
+        this._maxSize = maxSize;
+    }
+
+    _upload(file) {
+        if (this._maxSize !== null && this._maxSize !== undefined && file.size > this._maxSize) {
+            this._setStatus(`${file.name} is larger than the maximum of ${this._maxSize} bytes`, true);
+            return Promise.resolve();
+        }
+
+        return new Promise(resolve => {
+            const xhr = new XMLHttpRequest();
+            const url = `/upload/${encodeURIComponent(this.id)}?name=${encodeURIComponent(file.name)}`;
+            xhr.open('POST', url);
+            xhr.setRequestHeader('Content-Type', file.type || 'application/octet-stream');
+
+            this._progress.hidden = false;
+            this._progress.value = 0;
+            this._progress.max = file.size || 1;
+            this._setStatus(`Uploading ${file.name}...`, false);
+
+            xhr.upload.addEventListener('progress', (e) => {
+                this._progress.value = e.loaded;
+            });
+            xhr.addEventListener('load', () => {
+                this._progress.hidden = true;
+                if (xhr.status >= 200 && xhr.status < 300) {
+                    this._setStatus(`Uploaded ${file.name}`, false);
+                } else {
+                    this._setStatus(xhr.responseText || `Upload of ${file.name} failed (${xhr.status})`, true);
+                }
+                resolve();
+            });
+            xhr.addEventListener('error', () => {
+                this._progress.hidden = true;
+                this._setStatus(`Upload of ${file.name} failed`, true);
+                resolve();
+            });
+            xhr.send(file);
+        });
+    }
+
+    _setStatus(text, isError) {
+        this._status.textContent = text;
+        this._status.classList.toggle('error', isError);
+    }
+}
+
+/**
+ * <ui-canvas> - Drawing surface driven by server-side drawing commands
+ *
+ * Attributes:
+ *   id - Unique identifier (required, will be auto-scoped)
+ *
+ * Commands are executed on the 2D context in order. Pointer presses, drags
+ * and releases are sent to the server in canvas pixel coordinates.
+ */
+class UiCanvas extends HTMLElement {
+    constructor() {
+        super();
+        this._canvas = document.createElement('canvas');
+        this._ctx = this._canvas.getContext('2d');
+        this._pendingMove = null;
+    }
+
+    connectedCallback() {
+        autoRewriteId(this);
+
+        this.appendChild(this._canvas);
+        this._canvas.addEventListener('pointerdown', (e) => {
+            this._canvas.setPointerCapture(e.pointerId);
+            this._sendPointer('down', e);
+        });
+        this._canvas.addEventListener('pointermove', (e) => {
+            if (!this._canvas.hasPointerCapture(e.pointerId)) {
+                return;
+            }
+            // Send at most one move per animation frame
+            if (this._pendingMove === null) {
+                requestAnimationFrame(() => {
+                    this._sendPointer('move', this._pendingMove);
+                    this._pendingMove = null;
+                });
+            }
+            this._pendingMove = e;
+        });
+        this._canvas.addEventListener('pointerup', (e) => {
+            this._sendPointer('up', e);
+        });
+    }
+
+    setCanvas(width, height, commands) {
+        // Resizing also clears the canvas and resets the context state
+        this._canvas.width = width;
+        this._canvas.height = height;
+        this.draw(commands);
+    }
+
+    draw(commands) {
+        const ctx = this._ctx;
+        commands.forEach(cmd => {
+            switch (cmd.op) {
+                case 'clear':
+                    this._canvas.width = this._canvas.width;
+                    break;
+                case 'fill_style':
+                    ctx.fillStyle = cmd.color;
+                    break;
+                case 'stroke_style':
+                    ctx.strokeStyle = cmd.color;
+                    break;
+                case 'line_width':
+                    ctx.lineWidth = cmd.width;
+                    break;
+                case 'font':
+                    ctx.font = cmd.font;
+                    break;
+                case 'line':
+                    ctx.beginPath();
+                    ctx.moveTo(cmd.x1, cmd.y1);
+                    ctx.lineTo(cmd.x2, cmd.y2);
+                    ctx.stroke();
+                    break;
+                case 'rect':
+                    if (cmd.fill) {
+                        ctx.fillRect(cmd.x, cmd.y, cmd.width, cmd.height);
+                    } else {
+                        ctx.strokeRect(cmd.x, cmd.y, cmd.width, cmd.height);
+                    }
+                    break;
+                case 'circle':
+                    ctx.beginPath();
+                    ctx.arc(cmd.x, cmd.y, cmd.radius, 0, 2 * Math.PI);
+                    cmd.fill ? ctx.fill() : ctx.stroke();
+                    break;
+                case 'text':
+                    ctx.fillText(cmd.text, cmd.x, cmd.y);
+                    break;
+                case 'path':
+                    ctx.beginPath();
+                    cmd.points.forEach(([x, y], i) => {
+                        if (i === 0) {
+                            ctx.moveTo(x, y);
+                        } else {
+                            ctx.lineTo(x, y);
+                        }
+                    });
+                    if (cmd.close) {
+                        ctx.closePath();
+                    }
+                    cmd.fill ? ctx.fill() : ctx.stroke();
+                    break;
+            }
+        });
+    }
+
+    _sendPointer(kind, e) {
+        // Convert from CSS pixels to canvas pixels in case the canvas is scaled
+        const rect = this._canvas.getBoundingClientRect();
+        const x = (e.clientX - rect.left) * (this._canvas.width / rect.width);
+        const y = (e.clientY - rect.top) * (this._canvas.height / rect.height);
+        this.dispatchEvent(new CustomEvent('ui-pointer', {
+            bubbles: true,
+            detail: { id: this.id, kind, x, y }
+        }));
+    }
+}
+
+/**
+ * <ui-chart> - Line or bar chart with streaming updates
+ *
+ * Attributes:
+ *   id - Unique identifier (required, will be auto-scoped)
+ *
+ * The chart fills the element, whose size is set in CSS. New points are
+ * appended per series and redrawn at most once per animation frame.
+ */
+class UiChart extends HTMLElement {
+    static PALETTE = ['#2196f3', '#f44336', '#4caf50', '#ff9800', '#9c27b0', '#00bcd4', '#795548', '#607d8b'];
+    static MARGIN = { top: 30, right: 15, bottom: 40, left: 55 };
+
+    constructor() {
+        super();
+        this._canvas = document.createElement('canvas');
+        this._chart = null;
+        this._frame = null;
+        this._resizeObserver = new ResizeObserver(() => this._scheduleRender());
+    }
+
+    connectedCallback() {
+        autoRewriteId(this);
+        this.appendChild(this._canvas);
+        this._resizeObserver.observe(this);
+    }
+
+    disconnectedCallback() {
+        this._resizeObserver.disconnect();
+    }
+
+    setChart(data) {
+        this._chart = {
+            type: data.chart_type,
+            xLabel: data.x_label,
+            yLabel: data.y_label,
+            maxPoints: data.max_points,
+            series: data.series.map(s => ({ name: s.name, color: s.color, points: s.points.slice() })),
+        };
+        this._scheduleRender();
+    }
+
+    appendPoints(seriesName, points) {
+        const series = this._chart && this._chart.series.find(s => s.name === seriesName);
+        if (!series) {
+            return;
+        }
+        series.points.push(...points);
+        const maxPoints = this._chart.maxPoints;
+        if (maxPoints !== null && maxPoints !== undefined && series.points.length > maxPoints) {
+            series.points.splice(0, series.points.length - maxPoints);
+        }
+        this._scheduleRender();
+    }
+
+    _scheduleRender() {
+        if (this._frame === null) {
+            this._frame = requestAnimationFrame(() => {
+                this._frame = null;
+                this._render();
+            });
+        }
+    }
+
+    _render() {
+        const width = this.clientWidth;
+        const height = this.clientHeight;
+        if (!this._chart || width === 0 || height === 0) {
+            return;
+        }
+
+        // Draw at device resolution for sharp lines on high-DPI screens
+        const dpr = window.devicePixelRatio || 1;
+        this._canvas.width = width * dpr;
+        this._canvas.height = height * dpr;
+        this._canvas.style.width = `${width}px`;
+        this._canvas.style.height = `${height}px`;
+        const ctx = this._canvas.getContext('2d');
+        ctx.setTransform(dpr, 0, 0, dpr, 0, 0);
+        ctx.clearRect(0, 0, width, height);
+        ctx.font = '12px sans-serif';
+
+        const m = UiChart.MARGIN;
+        const plot = { left: m.left, top: m.top, width: width - m.left - m.right, height: height - m.top - m.bottom };
+        if (plot.width <= 0 || plot.height <= 0) {
+            return;
+        }
+
+        const chart = this._chart;
+        const isBar = chart.type === 'bar';
+        const allPoints = chart.series.flatMap(s => s.points);
+        const xs = [...new Set(allPoints.map(([x]) => x))].sort((a, b) => a - b);
+
+        let [xMin, xMax] = xs.length ? [xs[0], xs[xs.length - 1]] : [0, 1];
+        let [yMin, yMax] = allPoints.length
+            ? [Math.min(...allPoints.map(([, y]) => y)), Math.max(...allPoints.map(([, y]) => y))]
+            : [0, 1];
+        if (isBar) {
+            yMin = Math.min(yMin, 0);
+            yMax = Math.max(yMax, 0);
+        }
+        if (yMin === yMax) {
+            yMin -= 1;
+            yMax += 1;
+        }
+
+        // Bars are centered on their x value, so leave half a slot on each side
+        const gaps = xs.slice(1).map((x, i) => x - xs[i]);
+        const slot = gaps.length ? Math.min(...gaps) : 1;
+        if (isBar) {
+            xMin -= slot / 2;
+            xMax += slot / 2;
+        } else if (xMin === xMax) {
+            xMin -= 1;
+            xMax += 1;
+        }
+
+        const yTicks = niceTicks(yMin, yMax, 5);
+        yMin = Math.min(yMin, yTicks[0]);
+        yMax = Math.max(yMax, yTicks[yTicks.length - 1]);
+        const xTicks = isBar && xs.length <= 20 ? xs : niceTicks(xMin, xMax, 6).filter(x => x >= xMin && x <= xMax);
+
+        const sx = x => plot.left + (x - xMin) / (xMax - xMin) * plot.width;
+        const sy = y => plot.top + plot.height - (y - yMin) / (yMax - yMin) * plot.height;
+
+        // Grid and tick labels
+        ctx.strokeStyle = '#eee';
+        ctx.fillStyle = '#666';
+        ctx.lineWidth = 1;
+        ctx.textAlign = 'right';
+        ctx.textBaseline = 'middle';
+        yTicks.forEach(y => {
+            ctx.beginPath();
+            ctx.moveTo(plot.left, sy(y));
+            ctx.lineTo(plot.left + plot.width, sy(y));
+            ctx.stroke();
+            ctx.fillText(formatTick(y), plot.left - 6, sy(y));
+        });
+        ctx.textAlign = 'center';
+        ctx.textBaseline = 'top';
+        xTicks.forEach(x => {
+            ctx.fillText(formatTick(x), sx(x), plot.top + plot.height + 6);
+        });
+
+        // Axes
+        ctx.strokeStyle = '#999';
+        ctx.beginPath();
+        ctx.moveTo(plot.left, plot.top);
+        ctx.lineTo(plot.left, plot.top + plot.height);
+        ctx.lineTo(plot.left + plot.width, plot.top + plot.height);
+        ctx.stroke();
+
+        // Data
+        ctx.save();
+        ctx.beginPath();
+        ctx.rect(plot.left, plot.top, plot.width, plot.height);
+        ctx.clip();
+        const colorOf = (series, i) => series.color || UiChart.PALETTE[i % UiChart.PALETTE.length];
+        if (isBar) {
+            const groupWidth = (slot / (xMax - xMin)) * plot.width * 0.8;
+            const barWidth = groupWidth / Math.max(chart.series.length, 1);
+            chart.series.forEach((series, i) => {
+                ctx.fillStyle = colorOf(series, i);
+                series.points.forEach(([x, y]) => {
+                    const left = sx(x) - groupWidth / 2 + i * barWidth;
+                    const top = Math.min(sy(y), sy(0));
+                    ctx.fillRect(left, top, barWidth, Math.abs(sy(y) - sy(0)));
+                });
+            });
+        } else {
+            ctx.lineWidth = 2;
+            chart.series.forEach((series, i) => {
+                ctx.strokeStyle = colorOf(series, i);
+                ctx.beginPath();
+                series.points.forEach(([x, y], j) => {
+                    if (j === 0) {
+                        ctx.moveTo(sx(x), sy(y));
+                    } else {
+                        ctx.lineTo(sx(x), sy(y));
+                    }
+                });
+                ctx.stroke();
+            });
+        }
+        ctx.restore();
+
+        // Legend
+        ctx.textAlign = 'left';
+        ctx.textBaseline = 'middle';
+        let legendX = plot.left;
+        chart.series.forEach((series, i) => {
+            ctx.fillStyle = colorOf(series, i);
+            ctx.fillRect(legendX, 9, 12, 12);
+            ctx.fillStyle = '#333';
+            ctx.fillText(series.name, legendX + 16, 15);
+            legendX += 16 + ctx.measureText(series.name).width + 16;
+        });
+
+        // Axis labels
+        ctx.fillStyle = '#333';
+        if (chart.xLabel) {
+            ctx.textAlign = 'center';
+            ctx.textBaseline = 'bottom';
+            ctx.fillText(chart.xLabel, plot.left + plot.width / 2, height - 2);
+        }
+        if (chart.yLabel) {
+            ctx.save();
+            ctx.translate(12, plot.top + plot.height / 2);
+            ctx.rotate(-Math.PI / 2);
+            ctx.textAlign = 'center';
+            ctx.textBaseline = 'middle';
+            ctx.fillText(chart.yLabel, 0, 0);
+            ctx.restore();
+        }
+    }
+}
+
+/**
+ * <ui-tabs> - Tab bar switching between panels
+ *
+ * Attributes:
+ *   id - Unique identifier (required, will be auto-scoped)
+ *
+ * Child elements with a data-tab attribute are the panels. Only the panel
+ * whose data-tab matches the active tab is shown.
+ */
+class UiTabs extends HTMLElement {
+    constructor() {
+        super();
+        this._bar = document.createElement('div');
+        this._bar.className = 'ui-tabs-bar';
+        this._bar.setAttribute('role', 'tablist');
+        this._active = null;
+    }
+
+    connectedCallback() {
+        autoRewriteId(this);
+
+        this.prepend(this._bar);
+        // Hide all panels until the server tells which one is active
+        this.showPanel(this._active);
+    }
+
+    setTabs(tabs, active) {
+        this._bar.replaceChildren();
+        tabs.forEach(([tabId, label]) => {
+            const button = document.createElement('button');
+            button.type = 'button';
+            button.setAttribute('role', 'tab');
+            button.dataset.tab = tabId;
+            button.textContent = label;
+            button.addEventListener('click', () => {
+                if (this._active === tabId) {
+                    return;
+                }
+                this.showPanel(tabId);
+                this.dispatchEvent(new CustomEvent('ui-change', {
+                    bubbles: true,
+                    detail: { id: this.id, value: tabId }
+                }));
+            });
+            this._bar.appendChild(button);
+        });
+        this.showPanel(active);
+    }
+
+    showPanel(tabId) {
+        this._active = tabId;
+        this._bar.querySelectorAll('button').forEach(button => {
+            const selected = button.dataset.tab === tabId;
+            button.classList.toggle('active', selected);
+            button.setAttribute('aria-selected', selected);
+        });
+        Array.from(this.children).forEach(child => {
+            if (child.dataset.tab !== undefined && child !== this._bar) {
+                child.hidden = child.dataset.tab !== tabId;
+            }
+        });
+    }
+}
+
+/**
+ * <ui-tree> - Tree of nodes with lazily loaded children
+ *
+ * Attributes:
+ *   id - Unique identifier (required, will be auto-scoped)
+ *
+ * Expanding, collapsing and selecting nodes is reported to the server, which
+ * loads missing children and sends back single nodes as they change.
+ */
+class UiTree extends HTMLElement {
+    constructor() {
+        super();
+        this._root = document.createElement('ul');
+        this._root.setAttribute('role', 'tree');
+        this._items = new Map();
+        this._selected = null;
+    }
+
+    connectedCallback() {
+        autoRewriteId(this);
+        this.appendChild(this._root);
+    }
+
+    setTree(nodes, selected) {
+        this._items.clear();
+        this._selected = selected;
+        this._root.replaceChildren(...nodes.map(node => this._createNode(node)));
+    }
+
+    updateNode(node) {
+        const existing = this._items.get(node.id);
+        if (existing) {
+            existing.replaceWith(this._createNode(node));
+        }
+    }
+
+    _select(nodeId) {
+        const previous = this._items.get(this._selected);
+        if (previous) {
+            previous.firstChild.classList.remove('selected');
+        }
+        this._selected = nodeId;
+        this._items.get(nodeId).firstChild.classList.add('selected');
+    }
+
+    _createNode(node) {
+        const li = document.createElement('li');
+        li.setAttribute('role', 'treeitem');
+        li.dataset.node = node.id;
+
+        const row = document.createElement('div');
+        row.className = 'ui-tree-row';
+        if (node.id === this._selected) {
+            row.classList.add('selected');
+        }
+        const toggle = document.createElement('span');
+        toggle.className = 'ui-tree-toggle';
+        const label = document.createElement('span');
+        label.className = 'ui-tree-label';
+        label.textContent = node.label;
+        row.append(toggle, label);
+        li.appendChild(row);
+
+        if (node.has_children) {
+            li.setAttribute('aria-expanded', node.expanded);
+            toggle.textContent = node.expanded ? '\u25be' : '\u25b8';
+            toggle.addEventListener('click', (e) => {
+                e.stopPropagation();
+                // Show the new state right away; the server confirms it and sends missing children
+                const expanded = !node.expanded;
+                this.updateNode({ ...node, expanded });
+                this._dispatch({ kind: 'toggle', node: node.id, expanded });
+            });
+        }
+        row.addEventListener('click', () => {
+            this._select(node.id);
+            this._dispatch({ kind: 'select', node: node.id });
+        });
+
+        if (node.has_children && node.expanded) {
+            const group = document.createElement('ul');
+            group.setAttribute('role', 'group');
+            if (node.children === null) {
+                const loading = document.createElement('li');
+                loading.className = 'ui-tree-loading';
+                loading.textContent = 'Loading\u2026';
+                group.appendChild(loading);
+            } else {
+                node.children.forEach(child => group.appendChild(this._createNode(child)));
+            }
+            li.appendChild(group);
+        }
+
+        this._items.set(node.id, li);
+        return li;
+    }
+
+    _dispatch(event) {
+        this.dispatchEvent(new CustomEvent('ui-tree', {
+            bubbles: true,
+            detail: { id: this.id, event }
+        }));
+    }
+}
+
+/**
+ * <ui-datetime> - Date, time or date-and-time picker
+ *
+ * Attributes:
+ *   id - Unique identifier (required, will be auto-scoped)
+ *
+ * Uses the native date, time or datetime-local input. Values are ISO-8601
+ * strings; an empty string means no value. The server validates each change.
+ */
+class UiDateTime extends HTMLElement {
+    constructor() {
+        super();
+        this._input = document.createElement('input');
+        this._input.type = 'date';
+    }
+
+    connectedCallback() {
+        autoRewriteId(this);
+
+        this.appendChild(this._input);
+        this._input.addEventListener('change', () => {
+            this.dispatchEvent(new CustomEvent('ui-change', {
+                bubbles: true,
+                detail: { id: this.id, value: this._input.value }
+            }));
+        });
+    }
+
+    setValue(mode, value, min, max) {
+        this._input.type = mode;
+        this._input.min = min || '';
+        this._input.max = max || '';
+        // Pickers with seconds need a step below one minute to show them
+        this._input.step = [value, min, max].some(v => v && v.split(':').length > 2) ? 1 : '';
+        this._input.value = value || '';
+    }
+
+    get formValue() {
+        return this._input.value || null;
+    }
+}
+
+/**
+ * <ui-color> - Color picker with optional opacity and preset swatches
+ *
+ * Attributes:
+ *   id - Unique identifier (required, will be auto-scoped)
+ *
+ * Colors are #rrggbb strings, or #rrggbbaa when opacity is enabled. The
+ * server validates and normalizes each change.
+ */
+class UiColor extends HTMLElement {
+    constructor() {
+        super();
+        this._input = document.createElement('input');
+        this._input.type = 'color';
+        this._alpha = document.createElement('input');
+        this._alpha.type = 'range';
+        this._alpha.min = 0;
+        this._alpha.max = 255;
+        this._alpha.title = 'Opacity';
+        this._alpha.hidden = true;
+        this._presets = document.createElement('div');
+        this._presets.className = 'ui-color-presets';
+    }
+
+    connectedCallback() {
+        autoRewriteId(this);
+
+        this.append(this._input, this._alpha, this._presets);
+        this._input.addEventListener('change', () => this._changed());
+        this._alpha.addEventListener('change', () => this._changed());
+    }
+
+    setValue(value, alpha, presets) {
+        this._alpha.hidden = !alpha;
+        this._setColor(value);
+        this._presets.replaceChildren(...presets.map(preset => {
+            const swatch = document.createElement('button');
+            swatch.type = 'button';
+            swatch.title = preset;
+            swatch.style.background = preset;
+            swatch.addEventListener('click', () => {
+                this._setColor(preset);
+                this._changed();
+            });
+            return swatch;
+        }));
+    }
+
+    _setColor(value) {
+        this._input.value = value.slice(0, 7);
+        this._alpha.value = value.length === 9 ? parseInt(value.slice(7), 16) : 255;
+    }
+
+    get formValue() {
+        let value = this._input.value;
+        if (!this._alpha.hidden) {
+            value += Number(this._alpha.value).toString(16).padStart(2, '0');
+        }
+        return value;
+    }
+
+    _changed() {
+        this.dispatchEvent(new CustomEvent('ui-change', {
+            bubbles: true,
+            detail: { id: this.id, value: this.formValue }
+        }));
+    }
+}
+
+/**
+ * <ui-markdown> - Formatted text rendered from Markdown
+ *
+ * Attributes:
+ *   id - Unique identifier (required, will be auto-scoped)
+ *
+ * The server renders and sanitizes the HTML. When the server handles links,
+ * clicks are sent to it instead of navigating.
+ */
+class UiMarkdown extends HTMLElement {
+    constructor() {
+        super();
+        this._routeLinks = false;
+    }
+
+    connectedCallback() {
+        autoRewriteId(this);
+
+        this.addEventListener('click', (e) => {
+            const link = e.target.closest('a[href]');
+            if (!this._routeLinks || !link) {
+                return;
+            }
+            e.preventDefault();
+            this.dispatchEvent(new CustomEvent('ui-link', {
+                bubbles: true,
+                detail: { id: this.id, href: link.getAttribute('href') }
+            }));
+        });
+    }
+
+    setHtml(html, routeLinks) {
+        this._routeLinks = routeLinks;
+        this.innerHTML = html;
+    }
+}
+
+/**
+ * <ui-log> - Append-only log output
+ *
+ * Attributes:
+ *   id - Unique identifier (required, will be auto-scoped)
+ *
+ * New lines are appended as they arrive and old lines dropped beyond the
+ * maximum. The view follows new lines unless the user has scrolled up.
+ */
+class UiLog extends HTMLElement {
+    constructor() {
+        super();
+        this._lines = document.createElement('div');
+        this._lines.className = 'ui-log-lines';
+        this._resume = document.createElement('button');
+        this._resume.className = 'ui-log-resume';
+        this._resume.textContent = 'New output \u2193';
+        this._resume.hidden = true;
+        this._maxLines = Infinity;
+        this._follow = true;
+    }
+
+    connectedCallback() {
+        autoRewriteId(this);
+
+        this.append(this._lines, this._resume);
+        this._lines.addEventListener('scroll', () => {
+            const lines = this._lines;
+            this._follow = lines.scrollHeight - lines.scrollTop - lines.clientHeight < 5;
+            if (this._follow) {
+                this._resume.hidden = true;
+            }
+        });
+        this._resume.addEventListener('click', () => {
+            this._follow = true;
+            this._resume.hidden = true;
+            this._scrollToEnd();
+        });
+    }
+
+    setLines(lines, maxLines) {
+        this._maxLines = maxLines;
+        this._lines.replaceChildren();
+        this.appendLines(lines);
+    }
+
+    appendLines(lines) {
+        lines.forEach(line => {
+            const div = document.createElement('div');
+            div.className = `ui-log-line ui-log-${line.level}`;
+            div.textContent = line.text;
+            this._lines.appendChild(div);
+        });
+        while (this._lines.children.length > this._maxLines) {
+            this._lines.firstChild.remove();
+        }
+        if (this._follow) {
+            this._scrollToEnd();
+        } else {
+            this._resume.hidden = false;
+        }
+    }
+
+    _scrollToEnd() {
+        this._lines.scrollTop = this._lines.scrollHeight;
+    }
+}
+
+/**
+ * <ui-combobox> - Text input with server-side suggestions
+ *
+ * Attributes:
+ *   id - Unique identifier (required, will be auto-scoped)
+ *
+ * Queries are sent after a short pause in typing. Each query gets a request
+ * id, and suggestions for anything but the latest query are ignored.
+ */
+class UiCombobox extends HTMLElement {
+    static DEBOUNCE_MS = 200;
+
+    constructor() {
+        super();
+        this._input = document.createElement('input');
+        this._input.type = 'text';
+        this._input.autocomplete = 'off';
+        this._input.setAttribute('role', 'combobox');
+        this._list = document.createElement('ul');
+        this._list.className = 'ui-combobox-suggestions';
+        this._list.setAttribute('role', 'listbox');
+        this._list.hidden = true;
+        this._requestId = 0;
+        this._timer = null;
+        this._highlighted = -1;
+    }
+
+    connectedCallback() {
+        autoRewriteId(this);
+
+        this.append(this._input, this._list);
+        this._input.addEventListener('input', () => {
+            // Any typing makes outstanding results stale
+            const requestId = ++this._requestId;
+            clearTimeout(this._timer);
+            const query = this._input.value;
+            if (query === '') {
+                this._hide();
+                return;
+            }
+            this._timer = setTimeout(() => {
+                this.dispatchEvent(new CustomEvent('ui-suggest', {
+                    bubbles: true,
+                    detail: { id: this.id, query: query, request_id: requestId }
+                }));
+            }, UiCombobox.DEBOUNCE_MS);
+        });
+        this._input.addEventListener('keydown', (e) => {
+            const items = this._list.children;
+            if (e.key === 'ArrowDown' || e.key === 'ArrowUp') {
+                if (this._list.hidden || items.length === 0) {
+                    return;
+                }
+                e.preventDefault();
+                const step = e.key === 'ArrowDown' ? 1 : -1;
+                this._highlight((this._highlighted + step + items.length) % items.length);
+            } else if (e.key === 'Enter') {
+                e.preventDefault();
+                const item = !this._list.hidden && items[this._highlighted];
+                this._select(item ? item.textContent : this._input.value);
+            } else if (e.key === 'Escape') {
+                this._hide();
+            }
+        });
+        this._input.addEventListener('blur', () => this._hide());
+    }
+
+    setValue(value) {
+        this._input.value = value;
+    }
+
+    setSuggestions(requestId, suggestions) {
+        if (requestId !== this._requestId) {
+            return;
+        }
+        this._list.replaceChildren(...suggestions.map(suggestion => {
+            const li = document.createElement('li');
+            li.setAttribute('role', 'option');
+            li.textContent = suggestion;
+            // mousedown fires before the input loses focus and hides the list
+            li.addEventListener('mousedown', (e) => {
+                e.preventDefault();
+                this._select(suggestion);
+            });
+            return li;
+        }));
+        this._highlighted = -1;
+        this._list.hidden = suggestions.length === 0;
+    }
+
+    _highlight(index) {
+        Array.from(this._list.children).forEach((li, i) => {
+            li.classList.toggle('highlighted', i === index);
+        });
+        this._highlighted = index;
+    }
+
+    _select(value) {
+        this._input.value = value;
+        this._hide();
+        this.dispatchEvent(new CustomEvent('ui-change', {
+            bubbles: true,
+            detail: { id: this.id, value: value }
+        }));
+    }
+
+    _hide() {
+        // Results of requests still running are no longer wanted
+        this._requestId++;
+        clearTimeout(this._timer);
+        this._list.hidden = true;
+        this._highlighted = -1;
+    }
+
+    get formValue() {
+        return this._input.value;
+    }
+}
+
+/**
+ * UiCustomElement - Base class for application-defined elements
+ *
+ * Extend this class and register it with registerUiElement(kind, cls) to
+ * implement UiElement::Custom { kind, .. } in the browser:
+ *
+ *   class UiGauge extends UiCustomElement {
+ *       setProps(props) { this.textContent = `${props.value} ${props.unit}`; }
+ *   }
+ *   registerUiElement('gauge', UiGauge);   // <ui-gauge id="...">
+ *
+ * setProps(props) is called with the element's props from the server on
+ * every update. Call this.sendEvent(payload) to deliver any JSON value to
+ * the element's on_event handler. Subclasses overriding connectedCallback
+ * must call super.connectedCallback(). A formValue getter includes the
+ * element in <ui-form> submissions.
+ */
+class UiCustomElement extends HTMLElement {
+    connectedCallback() {
+        autoRewriteId(this);
+
+        // Props can arrive before the element class is registered
+        if (this._pendingProps !== undefined) {
+            const props = this._pendingProps;
+            delete this._pendingProps;
+            this.setProps(props);
+        }
+    }
+
+    setProps(props) {
+    }
+
+    sendEvent(payload) {
+        this.dispatchEvent(new CustomEvent('ui-custom-event', {
+            bubbles: true,
+            detail: { id: this.id, payload: payload }
+        }));
+    }
+}
+
+/**
+ * <ui-form> - Form that submits all of its elements' values at once
+ *
+ * Attributes:
+ *   id - Unique identifier (required, will be auto-scoped)
+ *
+ * The form is also the scope of the elements inside it, like <ui-scope>
+ * with name set to the form's ID. It is submitted by clicking a
+ * <ui-button submit> or a <button type="submit">, or by pressing Enter in a
+ * text field. The submit event holds the formValue of every enabled element
+ * in the form, keyed by its ID within the form.
+ */
+class UiForm extends HTMLElement {
+    connectedCallback() {
+        autoRewriteId(this);
+
+        this.addEventListener('click', (e) => {
+            if (e.target.closest('ui-button[submit], button[type="submit"]')) {
+                e.preventDefault();
+                this.submit();
+            }
+        });
+        this.addEventListener('keydown', (e) => {
+            // Elements such as the combobox use Enter themselves
+            if (e.key === 'Enter' && !e.defaultPrevented && e.target instanceof HTMLInputElement
+                && !['checkbox', 'radio', 'range', 'color', 'button'].includes(e.target.type)) {
+                e.preventDefault();
+                this.submit();
+            }
+        });
+    }
+
+    submit() {
+        const prefix = `${this.id}.`;
+        const values = {};
+        this.querySelectorAll('*').forEach(el => {
+            if ('formValue' in el && el.id.startsWith(prefix) && el.closest('ui-form') === this
+                && !el.classList.contains('webui-disabled')) {
+                values[el.id.slice(prefix.length)] = el.formValue;
+            }
+        });
+        this.dispatchEvent(new CustomEvent('ui-submit', {
+            bubbles: true,
+            detail: { id: this.id, values: values }
+        }));
+    }
+}
+
+// Helper function to compute evenly spaced, round axis tick values
+function niceTicks(min, max, count) {
+    if (!(max > min)) {
+        return [min];
+    }
+    const rawStep = (max - min) / count;
+    const magnitude = Math.pow(10, Math.floor(Math.log10(rawStep)));
+    const step = [1, 2, 5, 10].map(f => f * magnitude).find(s => s >= rawStep);
+    const ticks = [];
+    for (let t = Math.floor(min / step) * step; t <= max + step / 2; t += step) {
+        ticks.push(t);
+        if (t >= max) {
+            break;
+        }
+    }
+    return ticks;
+}
+
+// Helper function to format a tick value without floating point noise
+function formatTick(value) {
+    return String(Number(value.toPrecision(6)));
+}
+
+// Helper function to display a JSON cell value as text
+function formatCell(value) {
+    if (value === null || value === undefined) {
+        return '';
+    }
+    if (typeof value === 'object') {
+        return JSON.stringify(value);
+    }
+    return String(value);
+}
+
+// Register custom elements
+customElements.define('ui-scope', UiScope);
+customElements.define('ui-button', UiButton);
+customElements.define('ui-text', UiText);
+customElements.define('ui-input', UiInput);
+customElements.define('ui-checkbox', UiCheckbox);
+customElements.define('ui-slider', UiSlider);
+customElements.define('ui-radio', UiRadio);
+customElements.define('ui-number', UiNumber);
+customElements.define('ui-select', UiSelect);
+customElements.define('ui-textarea', UiTextArea);
+customElements.define('ui-table', UiTable);
+customElements.define('ui-list', UiList);
+customElements.define('ui-image', UiImage);
+customElements.define('ui-progress', UiProgress);
+customElements.define('ui-meter', UiMeter);
+customElements.define('ui-file-upload', UiFileUpload);
+customElements.define('ui-canvas', UiCanvas);
+customElements.define('ui-chart', UiChart);
+customElements.define('ui-tabs', UiTabs);
+customElements.define('ui-tree', UiTree);
+customElements.define('ui-datetime', UiDateTime);
+customElements.define('ui-color', UiColor);
+customElements.define('ui-markdown', UiMarkdown);
+customElements.define('ui-log', UiLog);
+customElements.define('ui-combobox', UiCombobox);
+customElements.define('ui-form', UiForm);
+
+/**
+ * Registers the implementation of UiElement::Custom elements of the given kind.
+ *
+ * The class is defined as the custom element <ui-{kind}>, so kind must be
+ * lowercase and may contain hyphens. Load scripts calling this with the defer
+ * attribute, or any time after webui.js.
+ *
+ * @param {string} kind - The `kind` of the UiElement::Custom elements
+ * @param {typeof UiCustomElement} elementClass - Class extending UiCustomElement
+ */
+function registerUiElement(kind, elementClass) {
+    customElements.define(`ui-${kind}`, elementClass);
+}
+
+/**
+ * WebUIClient - Manages WebSocket connection and UI synchronization
+ *
+ * Automatically connects to the server, handles reconnection,
+ * and synchronizes UI elements.
+ */
+class WebUIClient {
+    constructor() {
+        this.ws = null;
+        this.elements = new Map();
+        this.dialogs = new Map();
+        this.notifications = new Map();
+        this.connect();
+    }
+
+    connect() {
+        const protocol = window.location.protocol === 'https:' ? 'wss:' : 'ws:';
+        this.ws = new WebSocket(`${protocol}//${window.location.host}/ws`);
+
+        this.ws.onopen = () => {
+            console.log('WebUI: Connected');
+            this.updateConnectionStatus(true);
+        };
+
+        this.ws.onclose = () => {
+            console.log('WebUI: Disconnected');
+            this.updateConnectionStatus(false);
+            // Open dialogs are shown again on reconnect if still pending
+            Array.from(this.dialogs.keys()).forEach(id => this.closeDialog(id));
+            setTimeout(() => this.connect(), 2000);
+        };
+
+        this.ws.onerror = (error) => {
+            console.error('WebUI: Error', error);
+        };
+
+        this.ws.onmessage = (event) => {
+            const msg = JSON.parse(event.data);
+            this.handleMessage(msg);
+        };
+    }
+
+    updateConnectionStatus(connected) {
+        // Update connection indicator if present
+        const statusEl = document.querySelector('.webui-connection-status');
+        if (statusEl) {
+            if (connected) {
+                statusEl.className = 'webui-connection-status connected';
+                statusEl.textContent = 'Connected';
+            } else {
+                statusEl.className = 'webui-connection-status disconnected';
+                statusEl.textContent = 'Disconnected';
+            }
+        }
+
+        // Dispatch event for custom handling
+        document.dispatchEvent(new CustomEvent('webui-connection', {
+            detail: { connected }
+        }));
+    }
+
+    handleMessage(msg) {
+        console.log('WebUI: Received', msg);
+
+        switch (msg.type) {
+            case 'init':
+                this.initializeUI(msg.elements);
+                break;
+            case 'update':
+                this.updateElement(msg.id, msg.element);
+                break;
+            case 'table_append':
+                this.withElement(msg.id, 'ui-table', el => el.appendRow(msg.row));
+                break;
+            case 'table_update':
+                this.withElement(msg.id, 'ui-table', el => el.updateRow(msg.row));
+                break;
+            case 'table_delete':
+                this.withElement(msg.id, 'ui-table', el => el.deleteRow(msg.key));
+                break;
+            case 'list_insert':
+                this.withElement(msg.id, 'ui-list', el => el.insertItem(msg.index, msg.item));
+                break;
+            case 'list_remove':
+                this.withElement(msg.id, 'ui-list', el => el.removeItem(msg.key));
+                break;
+            case 'list_move':
+                this.withElement(msg.id, 'ui-list', el => el.moveItem(msg.key, msg.index));
+                break;
+            case 'value':
+                this.withElement(msg.id, ['ui-progress', 'ui-meter'], el => el.setValue(msg.value));
+                break;
+            case 'download':
+                this.startDownload(msg.url, msg.file_name);
+                break;
+            case 'canvas_draw':
+                this.withElement(msg.id, 'ui-canvas', el => el.draw(msg.commands));
+                break;
+            case 'tree_node':
+                this.withElement(msg.id, 'ui-tree', el => el.updateNode(msg.node));
+                break;
+            case 'log_append':
+                this.withElement(msg.id, 'ui-log', el => el.appendLines(msg.lines));
+                break;
+            case 'suggestions':
+                this.withElement(msg.id, 'ui-combobox', el => el.setSuggestions(msg.request_id, msg.suggestions));
+                break;
+            case 'chart_append':
+                this.withElement(msg.id, 'ui-chart', el => el.appendPoints(msg.series, msg.points));
+                break;
+            case 'enabled':
+            case 'visible': {
+                const el = document.getElementById(msg.id);
+                if (el) {
+                    this.applyState(el, msg.enabled, msg.visible);
+                }
+                break;
+            }
+            case 'add_class':
+            case 'remove_class': {
+                const el = document.getElementById(msg.id);
+                if (el) {
+                    const classes = (el._serverClasses ?? []).filter(cls => cls !== msg.class);
+                    if (msg.type === 'add_class') {
+                        classes.push(msg.class);
+                    }
+                    this.applyPresentation(el, classes, el._serverStyle ?? {});
+                }
+                break;
+            }
+            case 'style': {
+                const el = document.getElementById(msg.id);
+                if (el) {
+                    const style = { ...el._serverStyle };
+                    if (msg.value) {
+                        style[msg.property] = msg.value;
+                    } else {
+                        delete style[msg.property];
+                    }
+                    this.applyPresentation(el, el._serverClasses ?? [], style);
+                }
+                break;
+            }
+            case 'validation': {
+                const el = document.getElementById(msg.id);
+                if (el) {
+                    this.applyValidation(el, msg.error);
+                }
+                break;
+            }
+            case 'dialog':
+                this.showDialog(msg);
+                break;
+            case 'dialog_close':
+                this.closeDialog(msg.dialog_id);
+                break;
+            case 'notify':
+                this.showNotification(msg);
+                break;
+            case 'notification_close':
+                this.closeNotification(msg.notification_id);
+                break;
+        }
+    }
+
+    showDialog(msg) {
+        // A dialog can arrive twice when connecting while it is being opened
+        if (this.dialogs.has(msg.dialog_id)) {
+            return;
+        }
+
+        const dialog = document.createElement('dialog');
+        dialog.className = `webui-dialog webui-dialog-${msg.dialog_type}`;
+
+        const form = document.createElement('form');
+        form.method = 'dialog';
+
+        const title = document.createElement('h3');
+        title.textContent = msg.title;
+        const message = document.createElement('p');
+        message.textContent = msg.message;
+        form.append(title, message);
+
+        let input = null;
+        if (msg.dialog_type === 'prompt') {
+            input = document.createElement('input');
+            input.type = 'text';
+            input.value = msg.default_value || '';
+            form.appendChild(input);
+        }
+
+        const buttons = document.createElement('div');
+        buttons.className = 'webui-dialog-buttons';
+        const cancel = document.createElement('button');
+        cancel.type = 'button';
+        cancel.textContent = 'Cancel';
+        const ok = document.createElement('button');
+        ok.type = 'submit';
+        ok.textContent = 'OK';
+        buttons.append(cancel, ok);
+        form.appendChild(buttons);
+        dialog.appendChild(form);
+
+        const respond = (accepted) => {
+            let value;
+            if (msg.dialog_type === 'prompt') {
+                value = accepted ? input.value : null;
+            } else {
+                value = accepted;
+            }
+            this.send({
+                type: 'dialog_response',
+                dialog_id: msg.dialog_id,
+                value: value
+            });
+            this.closeDialog(msg.dialog_id);
+        };
+        form.addEventListener('submit', (e) => {
+            e.preventDefault();
+            respond(true);
+        });
+        cancel.addEventListener('click', () => respond(false));
+        // Escape key
+        dialog.addEventListener('cancel', (e) => {
+            e.preventDefault();
+            respond(false);
+        });
+
+        document.body.appendChild(dialog);
+        this.dialogs.set(msg.dialog_id, dialog);
+        dialog.showModal();
+        (input || ok).focus();
+    }
+
+    closeDialog(dialogId) {
+        const dialog = this.dialogs.get(dialogId);
+        if (dialog) {
+            this.dialogs.delete(dialogId);
+            dialog.close();
+            dialog.remove();
+        }
+    }
+
+    showNotification(msg) {
+        let container = document.querySelector('.webui-notifications');
+        if (!container) {
+            container = document.createElement('div');
+            container.className = 'webui-notifications';
+            document.body.appendChild(container);
+        }
+
+        const toast = document.createElement('div');
+        toast.className = `webui-toast webui-toast-${msg.level}`;
+        toast.setAttribute('role', msg.level === 'error' ? 'alert' : 'status');
+
+        const message = document.createElement('span');
+        message.className = 'webui-toast-message';
+        message.textContent = msg.message;
+        toast.appendChild(message);
+
+        // Actions are handled on the server; clicking or dismissing closes the
+        // notification in every browser
+        const respond = (action) => {
+            this.send({
+                type: 'notification',
+                notification_id: msg.notification_id,
+                action: action
+            });
+            this.closeNotification(msg.notification_id);
+        };
+        msg.actions.forEach((label, index) => {
+            const button = document.createElement('button');
+            button.className = 'webui-toast-action';
+            button.textContent = label;
+            button.addEventListener('click', () => respond(index));
+            toast.appendChild(button);
+        });
+
+        const close = document.createElement('button');
+        close.className = 'webui-toast-close';
+        close.textContent = '\u00d7';
+        close.setAttribute('aria-label', 'Dismiss');
+        close.addEventListener('click', () => {
+            if (msg.actions.length > 0) {
+                respond(null);
+            } else {
+                this.closeNotification(msg.notification_id);
+            }
+        });
+        toast.appendChild(close);
+
+        container.appendChild(toast);
+        this.notifications.set(msg.notification_id, toast);
+
+        if (msg.duration_ms != null) {
+            setTimeout(() => this.closeNotification(msg.notification_id), msg.duration_ms);
+        }
+    }
+
+    closeNotification(notificationId) {
+        const toast = this.notifications.get(notificationId);
+        if (toast) {
+            this.notifications.delete(notificationId);
+            toast.remove();
+        }
+    }
+
+    startDownload(url, fileName) {
+        const link = document.createElement('a');
+        link.href = url;
+        link.download = fileName;
+        link.style.display = 'none';
+        document.body.appendChild(link);
+        link.click();
+        link.remove();
+    }
+
+    // Calls callback with the element if it exists and has one of the given tag names
+    withElement(id, tagNames, callback) {
+        const el = document.getElementById(id);
+        if (!el) {
+            console.warn(`WebUI: Element with id="${id}" not found in DOM`);
+            return;
+        }
+        if ([].concat(tagNames).includes(el.tagName.toLowerCase())) {
+            callback(el);
+        }
+    }
+
+    initializeUI(elements) {
+        elements.forEach(element => {
+            this.updateElement(element.id, element);
+        });
+    }
+
+    updateElement(id, data) {
+        // Find the element in the DOM
+        let el = document.getElementById(id);
+
+        if (!el) {
+            console.warn(`WebUI: Element with id="${id}" not found in DOM`);
+            return;
+        }
+
+        // Store reference
+        this.elements.set(id, el);
+
+        // Update the element based on its type
+        switch (data.kind) {
+            case 'button':
+                if (el.tagName.toLowerCase() === 'ui-button') {
+                    el.setText(data.text);
+                }
+                break;
+            case 'text':
+                if (el.tagName.toLowerCase() === 'ui-text') {
+                    el.setText(data.text);
+                }
+                break;
+            case 'input':
+                if (el.tagName.toLowerCase() === 'ui-input') {
+                    el.setValue(data.value);
+                    el.setLabel(data.label, data.tooltip);
+                    el.setPlaceholder(data.placeholder);
+                }
+                break;
+            case 'checkbox':
+                if (el.tagName.toLowerCase() === 'ui-checkbox') {
+                    el.setChecked(data.checked);
+                    el.setLabel(data.label, data.tooltip);
+                }
+                break;
+            case 'slider':
+                if (el.tagName.toLowerCase() === 'ui-slider') {
+                    el.setValue(data.value, data.min, data.max, data.step);
+                    el.setLabel(data.label, data.tooltip);
+                }
+                break;
+            case 'radio':
+                if (el.tagName.toLowerCase() === 'ui-radio') {
+                    el.setChecked(data.checked, data.name, data.value);
+                    el.setLabel(data.label, data.tooltip);
+                }
+                break;
+            case 'number':
+                if (el.tagName.toLowerCase() === 'ui-number') {
+                    el.setValue(data.value, data.min, data.max, data.step);
+                    el.setLabel(data.label, data.tooltip);
+                    el.setPlaceholder(data.placeholder);
+                }
+                break;
+            case 'select':
+                if (el.tagName.toLowerCase() === 'ui-select') {
+                    el.setOptions(data.options, data.selected);
+                }
+                break;
+            case 'textarea':
+                if (el.tagName.toLowerCase() === 'ui-textarea') {
+                    el.setValue(data.value, data.rows, data.cols);
+                }
+                break;
+            case 'table':
+                if (el.tagName.toLowerCase() === 'ui-table') {
+                    el.setTable(data.columns, data.rows);
+                }
+                break;
+            case 'list':
+                if (el.tagName.toLowerCase() === 'ui-list') {
+                    el.setItems(data.items);
+                }
+                break;
+            case 'image':
+                if (el.tagName.toLowerCase() === 'ui-image') {
+                    el.setImage(data.version, data.alt);
+                }
+                break;
+            case 'progress':
+                if (el.tagName.toLowerCase() === 'ui-progress') {
+                    el.setProgress(data.value, data.max);
+                }
+                break;
+            case 'meter':
+                if (el.tagName.toLowerCase() === 'ui-meter') {
+                    el.setMeter(data);
+                }
+                break;
+            case 'file_upload':
+                if (el.tagName.toLowerCase() === 'ui-file-upload') {
+                    el.setOptions(data.accept, data.multiple, data.max_size);
+                }
+                break;
+            case 'canvas':
+                if (el.tagName.toLowerCase() === 'ui-canvas') {
+                    el.setCanvas(data.width, data.height, data.commands);
+                }
+                break;
+            case 'chart':
+                if (el.tagName.toLowerCase() === 'ui-chart') {
+                    el.setChart(data);
+                }
+                break;
+            case 'tabs':
+                if (el.tagName.toLowerCase() === 'ui-tabs') {
+                    el.setTabs(data.tabs, data.active);
+                }
+                break;
+            case 'datetime':
+                if (el.tagName.toLowerCase() === 'ui-datetime') {
+                    el.setValue(data.mode, data.value, data.min, data.max);
+                }
+                break;
+            case 'color':
+                if (el.tagName.toLowerCase() === 'ui-color') {
+                    el.setValue(data.value, data.alpha, data.presets);
+                }
+                break;
+            case 'markdown':
+                if (el.tagName.toLowerCase() === 'ui-markdown') {
+                    el.setHtml(data.html, data.route_links);
+                }
+                break;
+            case 'log':
+                if (el.tagName.toLowerCase() === 'ui-log') {
+                    el.setLines(data.lines, data.max_lines);
+                }
+                break;
+            case 'combobox':
+                if (el.tagName.toLowerCase() === 'ui-combobox') {
+                    el.setValue(data.value);
+                }
+                break;
+            case 'custom':
+                if (el.tagName.toLowerCase() === `ui-${data.custom_kind}`) {
+                    if (el instanceof UiCustomElement) {
+                        el.setProps(data.props);
+                    } else {
+                        // Not registered yet; applied when the element is upgraded
+                        el._pendingProps = data.props;
+                    }
+                }
+                break;
+            case 'tree':
+                if (el.tagName.toLowerCase() === 'ui-tree') {
+                    el.setTree(data.nodes, data.selected);
+                }
+                break;
+        }
+
+        this.applyState(el, data.enabled !== false, data.visible !== false);
+        this.applyPresentation(el, data.classes ?? [], data.style ?? {});
+        this.applyValidation(el, data.error ?? null);
+    }
+
+    // Shows or clears the validation error of an element below its controls
+    applyValidation(el, error) {
+        el.classList.toggle('webui-invalid', error !== null);
+        ownControls(el).forEach(control => {
+            control.toggleAttribute('aria-invalid', error !== null);
+        });
+        let message = el.querySelector(':scope > .ui-error');
+        if (error === null) {
+            message?.remove();
+            return;
+        }
+        if (!message) {
+            message = document.createElement('div');
+            message.className = 'ui-error';
+            message.setAttribute('role', 'alert');
+            el.appendChild(message);
+        }
+        message.textContent = error;
+    }
+
+    // Replaces the classes and inline style set by the server, keeping those from the page
+    applyPresentation(el, classes, style) {
+        // classList throws for empty tokens or ones containing whitespace
+        classes = classes.flatMap(cls => String(cls).split(/\s+/)).filter(Boolean);
+        for (const cls of el._serverClasses ?? []) {
+            if (!classes.includes(cls)) {
+                el.classList.remove(cls);
+            }
+        }
+        el.classList.add(...classes);
+        el._serverClasses = classes;
+
+        for (const property of Object.keys(el._serverStyle ?? {})) {
+            if (!(property in style)) {
+                el.style.removeProperty(property);
+            }
+        }
+        for (const [property, value] of Object.entries(style)) {
+            el.style.setProperty(property, value);
+        }
+        el._serverStyle = { ...style };
+    }
+
+    // Applies the enabled and visible state that every element has
+    applyState(el, enabled, visible) {
+        if (enabled !== undefined) {
+            el.classList.toggle('webui-disabled', !enabled);
+            el.toggleAttribute('aria-disabled', !enabled);
+            ownControls(el).forEach(control => {
+                control.disabled = !enabled;
+            });
+        }
+        if (visible !== undefined) {
+            el.classList.toggle('webui-hidden', !visible);
+        }
+    }
+
+    sendClick(id) {
+        this.send({
+            type: 'click',
+            id: id
+        });
+    }
+
+    sendInput(id, value) {
+        this.send({
+            type: 'input',
+            id: id,
+            value: value
+        });
+    }
+
+    sendChange(id, value) {
+        this.send({
+            type: 'change',
+            id: id,
+            value: value
+        });
+    }
+
+    sendTableEvent(id, event) {
+        this.send({
+            type: 'table',
+            id: id,
+            event: event
+        });
+    }
+
+    sendTreeEvent(id, event) {
+        this.send({
+            type: 'tree',
+            id: id,
+            event: event
+        });
+    }
+
+    sendLinkClick(id, href) {
+        this.send({
+            type: 'link_click',
+            id: id,
+            href: href
+        });
+    }
+
+    sendSuggest(id, query, requestId) {
+        this.send({
+            type: 'suggest',
+            id: id,
+            query: query,
+            request_id: requestId
+        });
+    }
+
+    sendCustomEvent(id, payload) {
+        this.send({
+            type: 'custom_event',
+            id: id,
+            payload: payload
+        });
+    }
+
+    sendSubmit(id, values) {
+        this.send({
+            type: 'submit',
+            id: id,
+            values: values
+        });
+    }
+
+    sendItemClick(id, key) {
+        this.send({
+            type: 'item_click',
+            id: id,
+            key: key
+        });
+    }
+
+    sendPointer(id, kind, x, y) {
+        this.send({
+            type: 'pointer',
+            id: id,
+            kind: kind,
+            x: x,
+            y: y
+        });
+    }
+
+    send(msg) {
+        if (this.ws && this.ws.readyState === WebSocket.OPEN) {
+            this.ws.send(JSON.stringify(msg));
+        }
+    }
+}
+
+// Auto-initialize when DOM is ready
+let webuiClient;
+
+if (document.readyState === 'loading') {
+    document.addEventListener('DOMContentLoaded', initWebUI);
+} else {
+    initWebUI();
+}
+
+function initWebUI() {
+    // Initialize client
+    webuiClient = new WebUIClient();
+
+    // Handle UI events
+    document.addEventListener('ui-click', (e) => {
+        webuiClient.sendClick(e.detail.id);
+    });
+
+    document.addEventListener('ui-input', (e) => {
+        webuiClient.sendInput(e.detail.id, e.detail.value);
     });
 
     document.addEventListener('ui-change', (e) => {
         webuiClient.sendChange(e.detail.id, e.detail.value);
     });
+
+    document.addEventListener('ui-table', (e) => {
+        webuiClient.sendTableEvent(e.detail.id, e.detail.event);
+    });
+
+    document.addEventListener('ui-tree', (e) => {
+        webuiClient.sendTreeEvent(e.detail.id, e.detail.event);
+    });
+
+    document.addEventListener('ui-link', (e) => {
+        webuiClient.sendLinkClick(e.detail.id, e.detail.href);
+    });
+
+    document.addEventListener('ui-suggest', (e) => {
+        webuiClient.sendSuggest(e.detail.id, e.detail.query, e.detail.request_id);
+    });
+
+    document.addEventListener('ui-custom-event', (e) => {
+        webuiClient.sendCustomEvent(e.detail.id, e.detail.payload);
+    });
+
+    document.addEventListener('ui-submit', (e) => {
+        webuiClient.sendSubmit(e.detail.id, e.detail.values);
+    });
+
+    document.addEventListener('ui-item-click', (e) => {
+        webuiClient.sendItemClick(e.detail.id, e.detail.key);
+    });
+
+    document.addEventListener('ui-pointer', (e) => {
+        webuiClient.sendPointer(e.detail.id, e.detail.kind, e.detail.x, e.detail.y);
+    });
 }